@@ -122,6 +122,25 @@ impl VectorFrom<DifferentialEvent> for Value {
                     x.complete.is_some().into(),
                 ]
             },
+            DifferentialEvent::TraceStatistics(x) => {
+                vec![
+                    x.operator.into(),
+                    x.level.into(),
+                    x.batches.into(),
+                    x.batch_keys.into(),
+                    x.batch_vals.into(),
+                    x.updates.into(),
+                    x.heap_bytes.into(),
+                ]
+            },
+            DifferentialEvent::JoinWork(x) => {
+                vec![
+                    x.operator.into(),
+                    x.effort.into(),
+                    x.batches.into(),
+                    x.updates.into(),
+                ]
+            },
             _ => { vec![] },
         }
     }
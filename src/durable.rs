@@ -0,0 +1,520 @@
+//! Durable append-only logs of collection updates.
+//!
+//! A log is a directory of segment files, each holding a sequence of frames that carry either a batch
+//! of `(data, time, diff)` updates or a frontier. A frontier frame indicates that all updates at times
+//! not greater or equal to the frontier precede it in the log, and only updates followed by a frontier
+//! are durable: replaying a log ignores the updates of each segment after its last frontier. Each frame
+//! is a one byte tag, an eight byte little-endian length, and that many bytes of abomonated payload, and
+//! so logs are only meaningful to the same types on the same architecture that wrote them.
+//!
+//! Logs are kept bounded by compaction, which advances the times of logged updates to the most recent
+//! frontier, consolidates them, and writes the result to a base file that supersedes all earlier files.
+//! The accumulated collection at each time greater or equal to that frontier is unchanged, but the
+//! history before it is lost. Compaction happens once the segments since the last base hold more bytes
+//! than the base itself, which bounds the work per logged byte.
+//!
+//! A `DurableInput` wraps an `InputSession` and logs its updates, and when opened on an existing log it
+//! first replays the logged updates into the session. The `SinkLog` trait writes a collection to a log,
+//! each time it completes recording the consolidated updates at the times that completed.
+//!
+//! Logs are per worker: each worker should use its own directory, and reopen that same directory when
+//! the computation restarts.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use abomonation::{Abomonation, encode, decode};
+
+use timely::dataflow::Scope;
+use timely::dataflow::scopes::ScopeParent;
+use timely::dataflow::operators::Input as TimelyInput;
+use timely::dataflow::operators::generic::Operator;
+use timely::dataflow::channels::pact::Pipeline;
+use timely::order::PartialOrder;
+use timely::progress::{Antichain, Timestamp};
+
+use ::{Collection, ExchangeData};
+use ::difference::Semigroup;
+use lattice::Lattice;
+use input::InputSession;
+use consolidation::consolidate_updates;
+
+/// Tags frames carrying updates.
+const UPDATES: u8 = 0;
+/// Tags frames carrying a frontier.
+const FRONTIER: u8 = 1;
+/// The number of bytes preceding the payload of a frame.
+const HEADER: usize = 9;
+/// The number of bytes after which a segment is closed and a new segment started.
+const SEGMENT_BYTES: usize = 64 << 20;
+
+/// Appends updates and frontiers to a log.
+pub struct LogWriter<D, T: Timestamp, R> {
+    path: PathBuf,
+    /// The index of the segment being written.
+    segment: usize,
+    file: BufWriter<File>,
+    /// Bytes written to the current segment.
+    segment_bytes: usize,
+    /// Bytes in the segments written since the base.
+    logged_bytes: usize,
+    /// Bytes in the base.
+    base_bytes: usize,
+    /// The size at which segments are closed.
+    segment_limit: usize,
+    /// The most recently logged frontier.
+    frontier: Antichain<T>,
+    /// Indicates that no updates have been appended since the most recent frontier.
+    sealed: bool,
+    phantom: PhantomData<(D, R)>,
+}
+
+impl<D, T, R> LogWriter<D, T, R>
+where
+    D: ExchangeData+Abomonation,
+    T: Timestamp+Lattice+Abomonation,
+    R: ExchangeData+Abomonation+Semigroup,
+{
+    /// Opens the log in directory `path` for appending, creating it if it does not exist.
+    ///
+    /// Appended updates start a new segment, following any updates already in the log. Files left behind
+    /// by an interrupted compaction are removed.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        fs::create_dir_all(&path)?;
+
+        let (base, segments) = list_files(&path)?;
+        remove_superseded(&path, base)?;
+
+        let base_bytes = match base {
+            Some(index) => fs::metadata(path.join(base_name(index)))?.len() as usize,
+            None => 0,
+        };
+        let mut logged_bytes = 0;
+        for index in segments.iter().filter(|&&index| Some(index) > base) {
+            logged_bytes += fs::metadata(path.join(segment_name(*index)))?.len() as usize;
+        }
+
+        let segment = segments.iter().cloned().chain(base).max().map(|index| index + 1).unwrap_or(0);
+        let file = BufWriter::new(File::create(path.join(segment_name(segment)))?);
+
+        Ok(LogWriter {
+            path,
+            segment,
+            file,
+            segment_bytes: 0,
+            logged_bytes,
+            base_bytes,
+            segment_limit: SEGMENT_BYTES,
+            frontier: Antichain::from_elem(T::minimum()),
+            sealed: true,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sets the number of bytes after which a segment is closed and a new segment started.
+    pub fn set_segment_limit(&mut self, bytes: usize) {
+        self.segment_limit = bytes;
+    }
+
+    /// Consolidates and appends `updates` to the log, leaving `updates` empty.
+    ///
+    /// The updates are only durable once a subsequent frontier is logged with `advance_to`.
+    pub fn append(&mut self, updates: &mut Vec<(D, T, R)>) -> io::Result<()> {
+        consolidate_updates(updates);
+        if !updates.is_empty() {
+            let frame = frame(UPDATES, |bytes| unsafe { encode(&*updates, bytes) })?;
+            self.write_frame(&frame)?;
+            self.sealed = false;
+            updates.clear();
+        }
+        Ok(())
+    }
+
+    /// Logs that all updates at times not greater or equal to `frontier` have been appended.
+    ///
+    /// The log is synced to disk before this method returns, and may then be compacted.
+    pub fn advance_to(&mut self, frontier: &[T]) -> io::Result<()> {
+        let frontier = frontier.to_vec();
+        self.write_frame(&frame(FRONTIER, |bytes| unsafe { encode(&frontier, bytes) })?)?;
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.sealed = true;
+
+        self.frontier = Antichain::new();
+        for time in frontier.into_iter() {
+            self.frontier.insert(time);
+        }
+
+        if self.logged_bytes > ::std::cmp::max(self.base_bytes, self.segment_limit) {
+            self.compact()?;
+        }
+        else if self.segment_bytes >= self.segment_limit {
+            self.start_segment(self.segment + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the contents of the log with their consolidation at the most recently logged frontier.
+    ///
+    /// The new base is written to a temporary file and renamed into place before superseded files are
+    /// removed, so that an interrupted compaction leaves the log intact. It is an error to compact a log
+    /// with updates appended since the most recent frontier, as those updates are not yet durable.
+    pub fn compact(&mut self) -> io::Result<()> {
+        if !self.sealed {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "log: compacting updates not followed by a frontier"));
+        }
+        self.file.flush()?;
+
+        let (mut updates, _frontier) = replay::<D, T, R, _>(&self.path)?;
+        for update in updates.iter_mut() {
+            update.1.advance_by(self.frontier.borrow());
+        }
+        consolidate_updates(&mut updates);
+
+        // The base supersedes all segments up to and including the current one.
+        let index = self.segment;
+        let frontier = self.frontier.elements().to_vec();
+        let mut bytes = frame(UPDATES, |bytes| unsafe { encode(&updates, bytes) })?;
+        bytes.extend(frame(FRONTIER, |bytes| unsafe { encode(&frontier, bytes) })?);
+
+        let temporary = self.path.join(format!("{}.tmp", base_name(index)));
+        {
+            let mut file = File::create(&temporary)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, self.path.join(base_name(index)))?;
+        remove_superseded(&self.path, Some(index))?;
+
+        self.base_bytes = bytes.len();
+        self.logged_bytes = 0;
+        self.start_segment(index + 1)
+    }
+
+    /// Writes a frame to the current segment.
+    fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.file.write_all(frame)?;
+        self.segment_bytes += frame.len();
+        self.logged_bytes += frame.len();
+        Ok(())
+    }
+
+    /// Closes the current segment and starts the segment `index`.
+    fn start_segment(&mut self, index: usize) -> io::Result<()> {
+        self.file.flush()?;
+        self.file = BufWriter::new(File::create(self.path.join(segment_name(index)))?);
+        self.segment = index;
+        self.segment_bytes = 0;
+        Ok(())
+    }
+}
+
+/// Reads the log in directory `path`, returning its consolidated updates and last logged frontier.
+///
+/// Only updates followed by a frontier in the same segment are returned, as later updates were not made
+/// durable. A frame cut short by an interrupted write ends the segment containing it. If no frontier
+/// was logged, the minimal frontier is returned.
+pub fn replay<D, T, R, P>(path: P) -> io::Result<(Vec<(D, T, R)>, Antichain<T>)>
+where
+    D: ExchangeData+Abomonation,
+    T: Timestamp+Lattice+Abomonation,
+    R: ExchangeData+Abomonation+Semigroup,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut updates = Vec::new();
+    let mut frontier = Antichain::from_elem(T::minimum());
+
+    let (base, segments) = list_files(path)?;
+    let names =
+    base.map(base_name)
+        .into_iter()
+        .chain(segments.into_iter().filter(|&index| Some(index) > base).map(segment_name));
+
+    for name in names {
+        let bytes = fs::read(path.join(name))?;
+        // Updates read since the last frontier of the segment, which are durable only once followed by one.
+        let mut tentative = Vec::new();
+        let mut offset = 0;
+        while offset + HEADER <= bytes.len() {
+            let mut length = [0u8; 8];
+            length.copy_from_slice(&bytes[offset + 1 .. offset + HEADER]);
+            let length = u64::from_le_bytes(length) as usize;
+            if offset + HEADER + length > bytes.len() { break; }
+            let mut payload = bytes[offset + HEADER .. offset + HEADER + length].to_vec();
+            match bytes[offset] {
+                UPDATES => {
+                    let decoded = unsafe { decode::<Vec<(D, T, R)>>(&mut payload[..]) }
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "log: malformed updates"))?;
+                    tentative.extend(decoded.0.iter().cloned());
+                },
+                FRONTIER => {
+                    let decoded = unsafe { decode::<Vec<T>>(&mut payload[..]) }
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "log: malformed frontier"))?;
+                    frontier = Antichain::new();
+                    for time in decoded.0.iter() {
+                        frontier.insert(time.clone());
+                    }
+                    updates.extend(tentative.drain(..));
+                },
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "log: unrecognized frame tag")),
+            }
+            offset += HEADER + length;
+        }
+    }
+
+    consolidate_updates(&mut updates);
+    Ok((updates, frontier))
+}
+
+/// An `InputSession` whose updates are recorded in a log.
+///
+/// Updates are appended to the log as the session advances, and are durable once `advance_to` returns.
+/// Opening a session on an existing log replays the logged updates into the session and advances it to
+/// the logged frontier, so that a restarted computation resumes from where it was when last advanced.
+/// Replayed updates, and any updates introduced before the session is bound to a dataflow, are held
+/// until `to_collection` binds it.
+pub struct DurableInput<T: Timestamp+Lattice+Abomonation, D: ExchangeData+Abomonation, R: ExchangeData+Abomonation+Semigroup> {
+    session: InputSession<T, D, R>,
+    log: LogWriter<D, T, R>,
+    buffer: Vec<(D, T, R)>,
+    /// Updates and the time to resume from, held until the session is bound.
+    unbound: Option<(Vec<(D, T, R)>, T)>,
+}
+
+impl<T, D> DurableInput<T, D, isize>
+where
+    T: Timestamp+Lattice+Abomonation,
+    D: ExchangeData+Abomonation,
+{
+    /// Adds an element to the collection.
+    pub fn insert(&mut self, element: D) { self.update(element, 1); }
+    /// Removes an element from the collection.
+    pub fn remove(&mut self, element: D) { self.update(element, -1); }
+}
+
+impl<T, D, R> DurableInput<T, D, R>
+where
+    T: Timestamp+Lattice+Abomonation,
+    D: ExchangeData+Abomonation,
+    R: ExchangeData+Abomonation+Semigroup,
+{
+    /// Opens a session logging to directory `path`, replaying any updates already logged there.
+    ///
+    /// The session is advanced to the meet of the logged frontier, which is the logged time itself for
+    /// logs written by a `DurableInput`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let log = LogWriter::open(&path)?;
+        let (updates, frontier) = replay::<D, T, R, _>(&path)?;
+        let session = InputSession::new();
+        let mut elements = frontier.elements().iter();
+        let resume = match elements.next() {
+            Some(first) => elements.fold(first.clone(), |meet, time| meet.meet(time)),
+            None => session.time().clone(),
+        };
+        Ok(DurableInput { session, log, buffer: Vec::new(), unbound: Some((updates, resume)) })
+    }
+
+    /// Introduces the session as a collection.
+    ///
+    /// Replayed updates, and updates introduced before this call, are introduced once the session is bound.
+    pub fn to_collection<G: TimelyInput>(&mut self, scope: &mut G) -> Collection<G, D, R>
+    where
+        G: ScopeParent<Timestamp=T>,
+    {
+        let collection = self.session.to_collection(scope);
+        if let Some((updates, resume)) = self.unbound.take() {
+            for (data, time, diff) in updates.into_iter() {
+                self.session.update_at(data, time, diff);
+            }
+            self.session.advance_to(resume);
+        }
+        collection
+    }
+
+    /// Adds to the weight of an element in the collection.
+    pub fn update(&mut self, element: D, change: R) {
+        let time = self.time().clone();
+        self.update_at(element, time, change);
+    }
+
+    /// Adds to the weight of an element in the collection at a future time.
+    pub fn update_at(&mut self, element: D, time: T, change: R) {
+        self.buffer.push((element.clone(), time.clone(), change.clone()));
+        match self.unbound.as_mut() {
+            Some((updates, resume)) => {
+                assert!(resume.less_equal(&time));
+                updates.push((element, time, change));
+            },
+            None => self.session.update_at(element, time, change),
+        }
+    }
+
+    /// Logs the session's updates and advances the logical time for future records.
+    ///
+    /// The updates are durable once this method returns. As with `InputSession::advance_to`, timely
+    /// dataflow is informed only once the session is flushed.
+    pub fn advance_to(&mut self, time: T) -> io::Result<()> {
+        self.log.append(&mut self.buffer)?;
+        self.log.advance_to(&[time.clone()])?;
+        match self.unbound.as_mut() {
+            Some((_, resume)) => {
+                assert!(resume.less_equal(&time));
+                *resume = time;
+            },
+            None => self.session.advance_to(time),
+        }
+        Ok(())
+    }
+
+    /// Forces buffered data into the timely dataflow input, and advances its time to match that of the session.
+    pub fn flush(&mut self) {
+        self.session.flush();
+    }
+
+    /// Reveals the current time of the session.
+    pub fn time(&self) -> &T {
+        match self.unbound.as_ref() {
+            Some((_, resume)) => resume,
+            None => self.session.time(),
+        }
+    }
+
+    /// Provides access to the log, for example to adjust its segment size.
+    pub fn log(&mut self) -> &mut LogWriter<D, T, R> { &mut self.log }
+}
+
+/// Writes a collection to a log.
+pub trait SinkLog<G: Scope, D, R> {
+    /// Writes the updates of the collection to a log in directory `path`.
+    ///
+    /// Each time the input frontier advances, the updates at times it has passed are consolidated and
+    /// appended to the log, followed by the new frontier. Pending updates are held in time order, and so
+    /// advancing the frontier visits only the updates it completes and the distinct pending times. The
+    /// log is synced before the operator returns, and it panics if the log cannot be written.
+    fn sink_log<P: AsRef<Path>>(&self, path: P) -> io::Result<()>;
+}
+
+impl<G, D, R> SinkLog<G, D, R> for Collection<G, D, R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Abomonation,
+    D: ExchangeData+Abomonation,
+    R: ExchangeData+Abomonation+Semigroup,
+{
+    fn sink_log<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+
+        let mut log = LogWriter::<D, G::Timestamp, R>::open(path)?;
+
+        let mut buffer = Vec::new();
+        // Updates not yet at times the frontier has passed, grouped by time.
+        let mut pending: BTreeMap<G::Timestamp, Vec<(D, R)>> = BTreeMap::new();
+        let mut frontier = vec![<G::Timestamp as Timestamp>::minimum()];
+
+        self.inner.sink(Pipeline, "SinkLog", move |input| {
+
+            input.for_each(|_time, data| {
+                data.swap(&mut buffer);
+                for (datum, time, diff) in buffer.drain(..) {
+                    pending.entry(time).or_insert_with(Vec::new).push((datum, diff));
+                }
+            });
+
+            let current = input.frontier().frontier().iter().cloned().collect::<Vec<_>>();
+            if current != frontier {
+                // The `Ord` order of timestamps extends their partial order, and so times less than each
+                // element of the frontier in that order form a complete prefix of `pending`.
+                let later = match current.iter().min() {
+                    Some(least) => pending.split_off(least),
+                    None => BTreeMap::new(),
+                };
+                let mut complete = ::std::mem::replace(&mut pending, later);
+                // Partially ordered times that follow the prefix may also be complete.
+                let passed =
+                pending
+                    .keys()
+                    .filter(|time| !current.iter().any(|t| t.less_equal(time)))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for time in passed {
+                    if let Some(updates) = pending.remove(&time) {
+                        complete.insert(time, updates);
+                    }
+                }
+
+                let mut ready = Vec::new();
+                for (time, updates) in complete {
+                    ready.extend(updates.into_iter().map(|(datum, diff)| (datum, time.clone(), diff)));
+                }
+
+                log.append(&mut ready).expect("log: failed to append updates");
+                log.advance_to(&current[..]).expect("log: failed to advance frontier");
+                frontier = current;
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// The file name of segment `index`.
+fn segment_name(index: usize) -> String {
+    format!("segment-{:020}", index)
+}
+
+/// The file name of the base superseding segments up through `index`.
+fn base_name(index: usize) -> String {
+    format!("base-{:020}", index)
+}
+
+/// Lists the index of the most recent base, and the indices of segments in increasing order.
+fn list_files(path: &Path) -> io::Result<(Option<usize>, Vec<usize>)> {
+    let mut base = None;
+    let mut segments = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if let Some(index) = parse_index(&name, "base-") {
+            base = ::std::cmp::max(base, Some(index));
+        }
+        else if let Some(index) = parse_index(&name, "segment-") {
+            segments.push(index);
+        }
+    }
+    segments.sort();
+    Ok((base, segments))
+}
+
+/// Removes temporary files, and the bases and segments superseded by the base `index`.
+fn remove_superseded(path: &Path, base: Option<usize>) -> io::Result<()> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let superseded =
+        name.ends_with(".tmp") ||
+        parse_index(&name, "base-").map(|index| Some(index) < base).unwrap_or(false) ||
+        parse_index(&name, "segment-").map(|index| Some(index) <= base).unwrap_or(false);
+        if superseded {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses the index from a file name `prefix` followed by digits.
+fn parse_index(name: &str, prefix: &str) -> Option<usize> {
+    if name.starts_with(prefix) { name[prefix.len() ..].parse::<usize>().ok() } else { None }
+}
+
+/// Frames `payload` with `tag` and its length.
+fn frame<F: FnOnce(&mut Vec<u8>) -> io::Result<()>>(tag: u8, payload: F) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0u8; HEADER];
+    bytes[0] = tag;
+    payload(&mut bytes)?;
+    let length = (bytes.len() - HEADER) as u64;
+    bytes[1 .. HEADER].copy_from_slice(&length.to_le_bytes());
+    Ok(bytes)
+}
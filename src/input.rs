@@ -14,6 +14,7 @@ use timely::dataflow::scopes::ScopeParent;
 use ::Data;
 use ::difference::Semigroup;
 use collection::{Collection, AsCollection};
+use trace::MemoryPressure;
 
 /// Create a new collection and input handle to control the collection.
 pub trait Input : TimelyInput {
@@ -190,6 +191,7 @@ pub struct InputSession<T: Timestamp+Clone, D: Data, R: Semigroup> {
 	time: T,
 	buffer: Vec<(D, T, R)>,
 	handle: Handle<T,(D,T,R)>,
+	pressure: Vec<MemoryPressure>,
 }
 
 impl<T: Timestamp+Clone, D: Data> InputSession<T, D, isize> {
@@ -232,6 +234,7 @@ impl<T: Timestamp+Clone, D: Data, R: Semigroup> InputSession<T, D, R> {
             time: handle.time().clone(),
             buffer: Vec::new(),
             handle,
+            pressure: Vec::new(),
         }
     }
 
@@ -241,6 +244,7 @@ impl<T: Timestamp+Clone, D: Data, R: Semigroup> InputSession<T, D, R> {
 			time: handle.time().clone(),
 			buffer: Vec::new(),
 			handle,
+			pressure: Vec::new(),
 		}
 	}
 
@@ -292,6 +296,19 @@ impl<T: Timestamp+Clone, D: Data, R: Semigroup> InputSession<T, D, R> {
 		self.time = time;
 	}
 
+	/// Attaches a memory pressure handle, for example that of an arrangement of this input.
+	pub fn add_pressure(&mut self, pressure: MemoryPressure) {
+		self.pressure.push(pressure);
+	}
+
+	/// Indicates whether any attached memory pressure handle reports its trace over budget.
+	///
+	/// The session does not itself hold back updates. A program that feeds the session should instead
+	/// step the worker, without introducing further updates, for as long as this method returns true.
+	pub fn over_budget(&self) -> bool {
+		self.pressure.iter().any(|pressure| pressure.over_budget())
+	}
+
 	/// Reveals the current time of the session.
 	pub fn epoch(&self) -> &T { &self.time }
 	/// Reveals the current time of the session.
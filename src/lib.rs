@@ -91,6 +91,7 @@ pub trait ExchangeData : timely::ExchangeData + Ord + Debug { }
 impl<T: timely::ExchangeData + Ord + Debug> ExchangeData for T { }
 
 extern crate fnv;
+extern crate memmap2;
 extern crate timely;
 extern crate timely_sort;
 
@@ -110,4 +111,5 @@ pub mod input;
 pub mod difference;
 pub mod collection;
 pub mod logging;
-pub mod consolidation;
\ No newline at end of file
+pub mod consolidation;
+pub mod durable;
\ No newline at end of file
@@ -29,6 +29,10 @@ pub enum DifferentialEvent {
     MergeShortfall(MergeShortfall),
     /// Trace sharing event.
     TraceShare(TraceShare),
+    /// Sizes of a level of a trace.
+    TraceStatistics(TraceStatistics),
+    /// Work performed and outstanding in a join.
+    JoinWork(JoinWork),
 }
 
 /// Either the start or end of a merge event.
@@ -94,3 +98,39 @@ pub struct TraceShare {
 }
 
 impl From<TraceShare> for DifferentialEvent { fn from(e: TraceShare) -> Self { DifferentialEvent::TraceShare(e) } }
+
+/// The sizes of the batches at one level of a trace, logged for each level when a merge completes.
+#[derive(Debug, Clone, Abomonation, Ord, PartialOrd, Eq, PartialEq)]
+pub struct TraceStatistics {
+    /// Operator identifier.
+    pub operator: usize,
+    /// Which level of the trace.
+    pub level: usize,
+    /// Number of non-empty batches.
+    pub batches: usize,
+    /// Number of keys, counted once for each batch that contains them.
+    pub batch_keys: usize,
+    /// Number of (key, val) pairs, counted once for each batch that contains them.
+    pub batch_vals: usize,
+    /// Number of updates.
+    pub updates: usize,
+    /// Approximate heap bytes holding updates.
+    pub heap_bytes: usize,
+}
+
+impl From<TraceStatistics> for DifferentialEvent { fn from(e: TraceStatistics) -> Self { DifferentialEvent::TraceStatistics(e) } }
+
+/// The work a join performed when scheduled, and the work it has deferred.
+#[derive(Debug, Clone, Abomonation, Ord, PartialOrd, Eq, PartialEq)]
+pub struct JoinWork {
+    /// Operator identifier.
+    pub operator: usize,
+    /// Number of output updates produced.
+    pub effort: usize,
+    /// Number of batches with outstanding work.
+    pub batches: usize,
+    /// Number of updates in batches with outstanding work.
+    pub updates: usize,
+}
+
+impl From<JoinWork> for DifferentialEvent { fn from(e: JoinWork) -> Self { DifferentialEvent::JoinWork(e) } }
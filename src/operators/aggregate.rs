@@ -0,0 +1,377 @@
+//! Accumulates the records of each key with a semigroup, without replaying the key's history.
+//!
+//! Aggregates such as sums, vector sums, and the statistics of `DiffPair` are accumulations of the
+//! contributions of records. A `reduce` determines them by replaying the history of the values of each
+//! key at each time the key might change, whereas `aggregate` maintains the contributions of each key and
+//! the accumulations it has reported, and at each such time sums the contributions up to that time and
+//! reports a change only if the sum differs from the reported accumulation.
+//!
+//! The times at which the accumulation of a key might change are the joins of the times of its
+//! contributions, and so partially ordered times are supported. The contributions and reported
+//! accumulations are compacted as the input frontier advances.
+//!
+//! Keys are visited only once one of their times is complete. For totally ordered times the contributions
+//! up to each time are accumulated in a single pass over the key's contributions; partially ordered times
+//! that are not all comparable may require re-accumulating the contributions for some times.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use timely::dataflow::Scope;
+use timely::dataflow::operators::{Map, Capability};
+use timely::dataflow::operators::generic::Operator;
+use timely::dataflow::channels::pact::Exchange;
+use timely::order::PartialOrder;
+use timely::progress::frontier::{Antichain, AntichainRef, MutableAntichain};
+use timely_sort::Unsigned;
+
+use hashable::Hashable;
+use ::{ExchangeData, Collection, AsCollection};
+use ::difference::Semigroup;
+use lattice::Lattice;
+use consolidation::{consolidate, consolidate_updates};
+
+/// Extension trait for the `aggregate` differential dataflow method.
+pub trait Aggregate<G: Scope, K: ExchangeData, V: ExchangeData, R: Semigroup> where G::Timestamp: Lattice+Ord {
+    /// Reports for each key the accumulation of the contributions `logic(&val, &diff)` of its records.
+    ///
+    /// The contribution of a record must distribute over its diffs, so that `logic(val, a + b)` equals
+    /// `logic(val, a) + logic(val, b)`. Keys whose contributions accumulate to zero are not reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Aggregate;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // report the sum of the values of each key
+    ///         scope.new_collection_from(1 .. 10).1
+    ///              .map(|x| (x % 3, x))
+    ///              .aggregate(|&x, &diff| x as isize * diff)
+    ///              .assert_eq(&scope.new_collection_from(vec![(0, 18), (1, 12), (2, 15)]).1);
+    ///     });
+    /// }
+    /// ```
+    fn aggregate<A, F>(&self, logic: F) -> Collection<G, (K, A), isize>
+    where A: ExchangeData+Semigroup, F: Fn(&V, &R)->A+'static;
+}
+
+impl<G, K, V, R> Aggregate<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+{
+    fn aggregate<A, F>(&self, logic: F) -> Collection<G, (K, A), isize>
+    where A: ExchangeData+Semigroup, F: Fn(&V, &R)->A+'static {
+
+        // Contributions are formed before the exchange, so that only they are exchanged.
+        let exchange = Exchange::new(|update: &(K, G::Timestamp, A)| update.0.hashed().as_u64());
+        self.inner
+            .map(move |((key, val), time, diff)| { let contribution = logic(&val, &diff); (key, time, contribution) })
+            .unary_frontier(exchange, "Aggregate", |_capability, _info| {
+
+                let mut buffer = Vec::new();
+                // contributions at times not yet complete.
+                let mut stash: BTreeMap<G::Timestamp, Vec<(K, A)>> = BTreeMap::new();
+                // capabilities for the times of `stash` and of `pending`.
+                let mut capabilities: Vec<Capability<G::Timestamp>> = Vec::new();
+                let mut keys = BTreeMap::new();
+                // keys at the times their accumulations might change, which are not yet complete.
+                let mut pending: BTreeMap<G::Timestamp, BTreeSet<K>> = BTreeMap::new();
+                let mut updates = Vec::new();
+
+                move |input, output| {
+
+                    input.for_each(|capability, data| {
+                        if !capabilities.iter().any(|held| held.time().less_equal(capability.time())) {
+                            capabilities.retain(|held| !capability.time().less_equal(held.time()));
+                            capabilities.push(capability.retain());
+                        }
+                        data.swap(&mut buffer);
+                        for (key, time, contribution) in buffer.drain(..) {
+                            stash.entry(time).or_insert_with(Vec::new).push((key, contribution));
+                        }
+                    });
+
+                    let frontier = input.frontier();
+
+                    // Move complete contributions to the states of their keys.
+                    let mut received = BTreeMap::new();
+                    for (time, contributions) in split_complete(&mut stash, frontier.frontier()) {
+                        for (key, contribution) in contributions {
+                            received.entry(key).or_insert_with(Vec::new).push((time.clone(), contribution));
+                        }
+                    }
+                    for (key, contributions) in received {
+                        let times = keys.entry(key.clone()).or_insert_with(KeyState::new).receive(contributions);
+                        for time in times {
+                            pending.entry(time).or_insert_with(BTreeSet::new).insert(key.clone());
+                        }
+                    }
+
+                    // Report changes at complete times, and compact the states of the keys with such times.
+                    let mut visit = BTreeSet::new();
+                    for (_time, waiting) in split_complete(&mut pending, frontier.frontier()) {
+                        visit.extend(waiting);
+                    }
+                    for key in visit {
+                        let empty = {
+                            let state = keys.get_mut(&key).unwrap();
+                            state.update(&key, frontier, &mut updates);
+                            state.compact(frontier.frontier());
+                            state.is_empty()
+                        };
+                        if empty { keys.remove(&key); }
+                    }
+
+                    for (data, time, diff) in updates.drain(..) {
+                        let capability = capabilities.iter().find(|held| held.time().less_equal(&time)).expect("No capability for aggregate update");
+                        output.session(capability).give((data, time, diff));
+                    }
+
+                    // Retain capabilities only for incomplete times.
+                    let mut lower = Antichain::new();
+                    for time in stash.keys().chain(pending.keys()) {
+                        lower.insert(time.clone());
+                    }
+                    capabilities =
+                    lower
+                        .elements()
+                        .iter()
+                        .map(|time| capabilities.iter().find(|held| held.time().less_equal(time)).unwrap().delayed(time))
+                        .collect();
+
+                    if frontier.is_empty() {
+                        keys.clear();
+                    }
+                }
+            })
+            .as_collection()
+    }
+}
+
+/// The contributions and reported accumulations of a key.
+struct KeyState<T, A> {
+    /// Contributions to the accumulation, at times.
+    input: Vec<(T, A)>,
+    /// Reported accumulations.
+    output: Vec<(A, T, isize)>,
+    /// Times at which the accumulation might change, in order.
+    times: Vec<T>,
+}
+
+impl<T: Lattice+Ord+Clone, A: Semigroup> KeyState<T, A> {
+
+    fn new() -> Self {
+        KeyState {
+            input: Vec::new(),
+            output: Vec::new(),
+            times: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.input.is_empty() && self.output.is_empty() && self.times.is_empty()
+    }
+
+    /// Adds contributions, and returns the times at which they might change the accumulation.
+    ///
+    /// These are the joins of sets of the times of contributions that include a time of a new contribution.
+    fn receive(&mut self, contributions: Vec<(T, A)>) -> Vec<T> {
+
+        let mut new = contributions.iter().map(|&(ref time, _)| time.clone()).collect::<Vec<_>>();
+        new.sort();
+        new.dedup();
+        self.input.extend(contributions);
+
+        let mut times = self.input.iter().map(|&(ref time, _)| time.clone()).collect::<Vec<_>>();
+        close_under_joins(&mut times);
+        // Times must follow the least new time in the `Ord` order, which suffices for totally ordered times.
+        times.retain(|time| new.first().map(|least| least <= time).unwrap_or(false) && new.iter().any(|new| new.less_equal(time)));
+
+        self.times.extend(times.iter().cloned());
+        self.times.sort();
+        self.times.dedup();
+        times
+    }
+
+    /// Reports changes to the accumulation at the times not in advance of `frontier`.
+    ///
+    /// Times are visited in order, and so the changes at times less than a time are reported before it.
+    fn update<K: Clone>(&mut self, key: &K, frontier: &MutableAntichain<T>, output: &mut Vec<((K, A), T, isize)>) {
+
+        // The contributions and reported accumulations at times less or equal to a time are found in prefixes.
+        self.input.sort_by(|x, y| x.0.cmp(&y.0));
+        self.output.sort_by(|x, y| x.1.cmp(&y.1));
+        let mut contributed = Prefix::new();
+        let mut reported = Prefix::new();
+        // accumulations reported by this call, in order of time.
+        let mut added = Vec::new();
+        let mut reported_added = Prefix::new();
+
+        let mut remaining = Vec::new();
+        let mut changes = Vec::new();
+        for time in self.times.drain(..) {
+
+            if frontier.less_equal(&time) {
+                remaining.push(time);
+                continue;
+            }
+
+            let accumulation = contributed.accumulate(&self.input, &time, |update| &update.0, |sum: &mut Option<A>, update| {
+                match *sum {
+                    Some(ref mut sum) => *sum += &update.1,
+                    None => *sum = Some(update.1.clone()),
+                }
+            });
+            if let Some(accumulation) = accumulation {
+                if !accumulation.is_zero() {
+                    changes.push((accumulation, 1));
+                }
+            }
+
+            let previous = reported.accumulate(&self.output, &time, |update| &update.1, add_reported);
+            let previous_added = reported_added.accumulate(&added, &time, |update| &update.1, add_reported);
+            for (accumulation, diff) in previous.into_iter().chain(previous_added) {
+                changes.push((accumulation, -diff));
+            }
+            consolidate(&mut changes);
+
+            for (accumulation, diff) in changes.drain(..) {
+                added.push((accumulation.clone(), time.clone(), diff));
+                output.push(((key.clone(), accumulation), time.clone(), diff));
+            }
+        }
+
+        self.output.extend(added);
+        self.times = remaining;
+    }
+
+    /// Advances the times of contributions and reported accumulations by `frontier`, and consolidates them.
+    fn compact(&mut self, frontier: AntichainRef<T>) {
+        for &mut (ref mut time, _) in self.input.iter_mut() {
+            time.advance_by(frontier);
+        }
+        consolidate(&mut self.input);
+        for &mut (_, ref mut time, _) in self.output.iter_mut() {
+            time.advance_by(frontier);
+        }
+        consolidate_updates(&mut self.output);
+    }
+}
+
+/// Adds a reported accumulation to the consolidated accumulations `sum`.
+fn add_reported<T, A: Semigroup>(sum: &mut Vec<(A, isize)>, update: &(A, T, isize)) {
+    sum.push((update.0.clone(), update.2));
+    consolidate(sum);
+}
+
+/// Accumulates the updates of a list ordered by time, at times less or equal to each of increasing times.
+///
+/// The `Ord` order of times extends their partial order, and so the updates at times less or equal to a
+/// time form a prefix of the list. The accumulation of the prefix is extended as the times increase, and
+/// is used as long as the join of the times of the prefix is less or equal to the time, which is always
+/// the case for totally ordered times. Otherwise, the updates of the prefix are accumulated anew.
+struct Prefix<T, S> {
+    length: usize,
+    upper: Option<T>,
+    sum: S,
+}
+
+impl<T: Lattice+Ord+Clone, S: Default+Clone> Prefix<T, S> {
+
+    fn new() -> Self {
+        Prefix { length: 0, upper: None, sum: S::default() }
+    }
+
+    /// Accumulates with `add` the updates of `list` at times less or equal to `time`.
+    ///
+    /// The value of `time` must not be less than those of previous calls.
+    fn accumulate<X, F, L>(&mut self, list: &[X], time: &T, time_of: F, mut add: L) -> S
+    where F: Fn(&X)->&T, L: FnMut(&mut S, &X) {
+        while self.length < list.len() && time_of(&list[self.length]) <= time {
+            let next = time_of(&list[self.length]);
+            self.upper = Some(match self.upper.take() {
+                Some(upper) => upper.join(next),
+                None => next.clone(),
+            });
+            add(&mut self.sum, &list[self.length]);
+            self.length += 1;
+        }
+
+        if self.upper.as_ref().map(|upper| upper.less_equal(time)).unwrap_or(true) {
+            self.sum.clone()
+        }
+        else {
+            let mut sum = S::default();
+            for update in list[.. self.length].iter() {
+                if time_of(update).less_equal(time) {
+                    add(&mut sum, update);
+                }
+            }
+            sum
+        }
+    }
+}
+
+/// Extends `times` to be closed under joins, leaving it sorted and deduplicated.
+fn close_under_joins<T: Lattice+Ord+Clone>(times: &mut Vec<T>) {
+    times.sort();
+    times.dedup();
+
+    // Sorted times that form a chain, as totally ordered times do, are already closed.
+    if times.windows(2).all(|pair| pair[0].less_equal(&pair[1])) {
+        return;
+    }
+
+    // Each new join includes a time added in the previous round.
+    let mut added = times.clone();
+    while !added.is_empty() {
+        let mut joins = Vec::new();
+        for time1 in added.iter() {
+            for time2 in times.iter() {
+                if !time1.less_equal(time2) && !time2.less_equal(time1) {
+                    joins.push(time1.join(time2));
+                }
+            }
+        }
+        joins.sort();
+        joins.dedup();
+        joins.retain(|join| times.binary_search(join).is_err());
+        times.extend(joins.iter().cloned());
+        times.sort();
+        added = joins;
+    }
+}
+
+/// Removes and returns the entries of `pending` at times not in advance of `frontier`.
+///
+/// The times less than each element of `frontier` in the `Ord` order form a prefix of `pending`, which
+/// is split off. Partially ordered times after the prefix may also be complete, and so the remaining
+/// distinct times are also examined.
+fn split_complete<T: PartialOrder+Ord+Clone, V>(pending: &mut BTreeMap<T, V>, frontier: AntichainRef<T>) -> BTreeMap<T, V> {
+    let later = match frontier.iter().min() {
+        Some(least) => pending.split_off(least),
+        None => BTreeMap::new(),
+    };
+    let mut complete = ::std::mem::replace(pending, later);
+    let passed =
+    pending
+        .keys()
+        .filter(|time| !frontier.iter().any(|t| t.less_equal(time)))
+        .cloned()
+        .collect::<Vec<_>>();
+    for time in passed {
+        if let Some(entry) = pending.remove(&time) {
+            complete.insert(time, entry);
+        }
+    }
+    complete
+}
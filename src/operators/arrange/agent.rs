@@ -3,6 +3,10 @@
 use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+
+use abomonation::Abomonation;
 
 use timely::dataflow::Scope;
 use timely::dataflow::operators::generic::source;
@@ -11,9 +15,11 @@ use timely::progress::{Antichain, frontier::AntichainRef};
 use timely::dataflow::operators::CapabilitySet;
 
 use lattice::Lattice;
-use trace::{Trace, TraceReader, Batch, BatchReader, Cursor};
+use ::difference::Semigroup;
+use trace::{Trace, TraceReader, Batch, BatchReader, Cursor, Statistics, MemoryPressure, MergePolicy};
 
 use trace::wrappers::rc::TraceBox;
+use trace::implementations::checkpoint::{self, Persist};
 
 use timely::scheduling::Activator;
 
@@ -74,6 +80,8 @@ where
         self.trace.borrow_mut().trace.cursor_through(frontier)
     }
     fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F) { self.trace.borrow_mut().trace.map_batches(f) }
+    fn statistics(&mut self) -> Vec<Statistics> { self.trace.borrow_mut().trace.statistics() }
+    fn memory_pressure(&mut self) -> Option<MemoryPressure> { self.trace.borrow_mut().trace.memory_pressure() }
 }
 
 impl<Tr> TraceAgent<Tr>
@@ -83,6 +91,29 @@ where
 {
     /// Creates a new agent from a trace reader.
     pub fn new(trace: Tr, operator: ::timely::dataflow::operators::generic::OperatorInfo, logging: Option<::logging::Logger>) -> (Self, TraceWriter<Tr>)
+    where
+        Tr: Trace,
+        Tr::Batch: Batch<Tr::Key,Tr::Val,Tr::Time,Tr::R>,
+    {
+        Self::with_lower(trace, vec![<Tr::Time as Timestamp>::minimum()], operator, logging)
+    }
+
+    /// Creates a new agent from a trace reader that may already contain batches.
+    ///
+    /// Unlike `new`, which starts the writer from the minimal timestamp, the returned writer accepts
+    /// batches starting from the upper frontier of the batches already in `trace`.
+    pub fn resume(mut trace: Tr, operator: ::timely::dataflow::operators::generic::OperatorInfo, logging: Option<::logging::Logger>) -> (Self, TraceWriter<Tr>)
+    where
+        Tr: Trace,
+        Tr::Batch: Batch<Tr::Key,Tr::Val,Tr::Time,Tr::R>,
+    {
+        let mut upper = Antichain::new();
+        trace.read_upper(&mut upper);
+        Self::with_lower(trace, upper.elements().to_vec(), operator, logging)
+    }
+
+    /// Creates a new agent whose writer accepts batches starting from `lower`.
+    fn with_lower(trace: Tr, lower: Vec<Tr::Time>, operator: ::timely::dataflow::operators::generic::OperatorInfo, logging: Option<::logging::Logger>) -> (Self, TraceWriter<Tr>)
     where
         Tr: Trace,
         Tr::Batch: Batch<Tr::Key,Tr::Val,Tr::Time,Tr::R>,
@@ -106,7 +137,7 @@ where
         };
 
         let writer = TraceWriter::new(
-            vec![<Tr::Time as Timestamp>::minimum()],
+            lower,
             Rc::downgrade(&trace),
             queues,
         );
@@ -114,6 +145,108 @@ where
         (reader, writer)
     }
 
+    /// Rebuilds an agent from the trace checkpoint in directory `path`.
+    ///
+    /// The returned writer accepts batches starting from the upper frontier of the checkpoint,
+    /// which allows an arrangement to resume from its last sealed frontier, and the agent starts
+    /// from the compaction frontiers recorded in the checkpoint.
+    pub fn restore<P: AsRef<Path>>(path: P, operator: ::timely::dataflow::operators::generic::OperatorInfo, logging: Option<::logging::Logger>) -> io::Result<(Self, TraceWriter<Tr>)>
+    where
+        Tr: Trace,
+        Tr::Time: Abomonation,
+        Tr::Batch: Batch<Tr::Key,Tr::Val,Tr::Time,Tr::R>+Persist<Tr::Key,Tr::Val,Tr::Time,Tr::R>,
+    {
+        let trace = checkpoint::restore::<Tr, _>(path, operator.clone(), logging.clone(), None)?;
+        Ok(Self::resume(trace, operator, logging))
+    }
+
+    /// Writes the batches and compaction frontiers of the shared trace to the directory `path`.
+    ///
+    /// The checkpoint can be read back with `TraceAgent::restore`.
+    pub fn checkpoint<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()>
+    where
+        Tr::Time: Abomonation,
+        Tr::Batch: Persist<Tr::Key,Tr::Val,Tr::Time,Tr::R>,
+    {
+        checkpoint::write(&mut self.trace.borrow_mut().trace, path)
+    }
+
+    /// Sets a budget of heap bytes for the shared trace, or removes the budget if `None`.
+    ///
+    /// Once set, `memory_pressure` returns a handle that input sources can consult to slow the admission
+    /// of updates while the trace exceeds its budget.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>)
+    where
+        Tr: Trace,
+        Tr::Batch: Batch<Tr::Key,Tr::Val,Tr::Time,Tr::R>,
+    {
+        self.trace.borrow_mut().trace.set_memory_budget(budget)
+    }
+
+    /// Replaces the policy that schedules merges in the shared trace.
+    pub fn set_merge_policy<P: MergePolicy+'static>(&mut self, policy: P)
+    where
+        Tr: Trace,
+        Tr::Batch: Batch<Tr::Key,Tr::Val,Tr::Time,Tr::R>,
+    {
+        self.trace.borrow_mut().trace.set_merge_policy(Box::new(policy))
+    }
+
+    /// Reads the accumulated values of `key` as of `time`.
+    ///
+    /// The result contains each value whose differences at times less or equal to `time` accumulate to
+    /// a non-zero amount, in value order. It is `None` if the trace may not yet contain all updates at
+    /// times less or equal to `time`, because `time` is greater or equal to an element of the upper frontier,
+    /// or if `time` is not greater or equal to the agent's `advance_frontier`, at which point updates have
+    /// been advanced and may no longer be distinguished from updates at later times.
+    ///
+    /// The read uses a cursor over the entire trace, which `distinguish_since` does not restrict.
+    pub fn peek(&mut self, key: &Tr::Key, time: &Tr::Time) -> Option<Vec<(Tr::Val, Tr::R)>>
+    where
+        Tr::Key: Ord,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+    {
+        if !self.peekable(time) { return None; }
+
+        let (mut cursor, storage) = self.cursor();
+        let mut result = Vec::new();
+        cursor.seek_key(&storage, key);
+        if cursor.get_key(&storage) == Some(key) {
+            accumulate_vals(&mut cursor, &storage, time, |val, diff| result.push((val.clone(), diff)));
+        }
+        Some(result)
+    }
+
+    /// Reads the accumulated values of keys in the range `[lower, upper)` as of `time`.
+    ///
+    /// The result is in `(key, val)` order, and is `None` under the same conditions as `peek`.
+    pub fn peek_range(&mut self, lower: &Tr::Key, upper: &Tr::Key, time: &Tr::Time) -> Option<Vec<(Tr::Key, Tr::Val, Tr::R)>>
+    where
+        Tr::Key: Ord+Clone,
+        Tr::Val: Clone,
+        Tr::R: Semigroup,
+    {
+        if !self.peekable(time) { return None; }
+
+        let (mut cursor, storage) = self.cursor();
+        let mut result = Vec::new();
+        cursor.seek_key(&storage, lower);
+        while let Some(key) = cursor.get_key(&storage) {
+            if key >= upper { break; }
+            accumulate_vals(&mut cursor, &storage, time, |val, diff| result.push((key.clone(), val.clone(), diff)));
+            cursor.step_key(&storage);
+        }
+        Some(result)
+    }
+
+    /// Indicates whether the trace can report accumulations as of `time`.
+    fn peekable(&mut self, time: &Tr::Time) -> bool {
+        let mut upper = Antichain::new();
+        self.read_upper(&mut upper);
+        !upper.less_equal(time) && self.advance.less_equal(time)
+    }
+
     /// Attaches a new shared queue to the trace.
     ///
     /// The queue is first populated with existing batches from the trace,
@@ -566,3 +699,31 @@ where
         self.trace.borrow_mut().adjust_through_frontier(self.through.borrow(), empty_frontier.borrow());
     }
 }
+
+/// Accumulates the differences of each value of the cursor's current key at times less or equal to `time`,
+/// and calls `logic` with each value whose accumulation is non-zero.
+pub(crate) fn accumulate_vals<K, V, T, R, C, L>(cursor: &mut C, storage: &C::Storage, time: &T, mut logic: L)
+where
+    T: Lattice,
+    R: Semigroup,
+    C: Cursor<K, V, T, R>,
+    L: FnMut(&V, R),
+{
+    while let Some(val) = cursor.get_val(storage) {
+        let mut sum: Option<R> = None;
+        cursor.map_times(storage, |t, diff| {
+            if t.less_equal(time) {
+                match sum.as_mut() {
+                    Some(sum) => *sum += diff,
+                    None => sum = Some(diff.clone()),
+                }
+            }
+        });
+        if let Some(sum) = sum {
+            if !sum.is_zero() {
+                logic(val, sum);
+            }
+        }
+        cursor.step_val(storage);
+    }
+}
@@ -17,7 +17,7 @@
 //! see ill-defined data at times for which the trace is not complete. (All current implementations
 //! commit only completed data to the trace).
 
-use timely::dataflow::operators::{Enter, Map};
+use timely::dataflow::operators::{Enter, Map, Broadcast};
 use timely::order::{PartialOrder, TotalOrder};
 use timely::dataflow::{Scope, Stream};
 use timely::dataflow::operators::generic::Operator;
@@ -32,6 +32,7 @@ use ::{Data, ExchangeData, Collection, AsCollection, Hashable};
 use ::difference::Semigroup;
 use lattice::Lattice;
 use trace::{Trace, TraceReader, Batch, BatchReader, Batcher, Cursor};
+use trace::cursor::KeyRange;
 use trace::implementations::ord::OrdValSpine as DefaultValTrace;
 use trace::implementations::ord::OrdKeySpine as DefaultKeyTrace;
 
@@ -41,6 +42,7 @@ use trace::wrappers::enter_at::BatchEnter as BatchEnterAt;
 use trace::wrappers::filter::{TraceFilter, BatchFilter};
 
 use super::TraceAgent;
+use super::agent::accumulate_vals;
 
 /// An arranged collection of `(K,V)` values.
 ///
@@ -405,6 +407,109 @@ where
             }
         })
     }
+
+    /// Report values associated with ranges of keys at certain times.
+    ///
+    /// This method consumes a stream of (range, time) queries and reports the corresponding stream of
+    /// (range, key, value, time, diff) accumulations in the `self` trace, for each key in the range.
+    /// As keys are distributed among workers by hash, each query is broadcast to all workers, and each
+    /// reports the accumulations for the keys it holds.
+    pub fn lookup_range<Q>(&self, queries: &Stream<G, (Q, G::Timestamp)>) -> Stream<G, (Q, Tr::Key, Tr::Val, G::Timestamp, Tr::R)>
+    where
+        G::Timestamp: Data+Lattice+Ord+TotalOrder,
+        Q: ExchangeData+KeyRange<Tr::Key>,
+        Tr::Key: Data,
+        Tr::Val: Data,
+        Tr::R: Data+Semigroup,
+        Tr: 'static,
+    {
+        queries.broadcast().binary_frontier(&self.stream, Pipeline, Pipeline, "TraceRangeQuery", move |_capability, _info| {
+
+            let mut trace = Some(self.trace.clone());
+            // release `distinguish_since` capability.
+            trace.as_mut().unwrap().distinguish_since(Antichain::new().borrow());
+
+            let mut stash = Vec::new();
+            let mut capability: Option<Capability<G::Timestamp>> = None;
+
+            let mut active = Vec::new();
+            let mut retain = Vec::new();
+
+            move |input1, input2, output| {
+
+                input1.for_each(|time, data| {
+                    // if the minimum capability "improves" retain it.
+                    if capability.is_none() || time.time().less_than(capability.as_ref().unwrap().time()) {
+                        capability = Some(time.retain());
+                    }
+                    stash.extend(data.iter().cloned());
+                });
+
+                // drain input2; we will consult `trace` directly.
+                input2.for_each(|_time, _data| { });
+
+                assert_eq!(capability.is_none(), stash.is_empty());
+
+                let mut drained = false;
+                if let Some(capability) = capability.as_mut() {
+                    if !input2.frontier().less_equal(capability.time()) {
+                        for datum in stash.drain(..) {
+                            if !input2.frontier().less_equal(&datum.1) {
+                                active.push(datum);
+                            }
+                            else {
+                                retain.push(datum);
+                            }
+                        }
+                        drained = !active.is_empty();
+
+                        ::std::mem::swap(&mut stash, &mut retain);    // retain now the stashed queries.
+
+                        let (mut cursor, storage) = trace.as_mut().unwrap().cursor();
+                        let mut session = output.session(&capability);
+
+                        for (range, time) in active.drain(..) {
+                            cursor.rewind_keys(&storage);
+                            cursor.seek_range(&storage, &range);
+                            while cursor.key_in_range(&storage, &range) {
+                                let key = cursor.key(&storage);
+                                accumulate_vals(&mut cursor, &storage, &time, |val, diff| {
+                                    session.give((range.clone(), key.clone(), val.clone(), time.clone(), diff));
+                                });
+                                cursor.step_key(&storage);
+                            }
+                        }
+                    }
+                }
+
+                if drained {
+                    if stash.is_empty() { capability = None; }
+                    if let Some(capability) = capability.as_mut() {
+                        let mut min_time = stash[0].1.clone();
+                        for datum in stash[1..].iter() {
+                            if datum.1.less_than(&min_time) {
+                                min_time = datum.1.clone();
+                            }
+                        }
+                        capability.downgrade(&min_time);
+                    }
+                }
+
+                // Determine new frontier on queries that may be issued.
+                let frontier = [
+                    capability.as_ref().map(|c| c.time().clone()),
+                    input1.frontier().frontier().get(0).cloned(),
+                ].into_iter().cloned().filter_map(|t| t).min();
+
+                if let Some(frontier) = frontier {
+                    trace.as_mut().map(|t| t.advance_by(AntichainRef::new(&[frontier])));
+                }
+                else {
+                    trace = None;
+                }
+            }
+        })
+    }
 }
 
 impl<'a, G: Scope, Tr> Arranged<Child<'a, G, G::Timestamp>, Tr>
@@ -472,6 +577,25 @@ where
         self.arrange_core(exchange, name)
     }
 
+    /// As `arrange_named`, with a budget of heap bytes for the shared trace.
+    ///
+    /// While the trace holds more heap bytes than `budget`, it completes its merges in progress rather than
+    /// spreading their work across future batches, and the handle returned by its `memory_pressure` method
+    /// reports that it is over budget, so that input sources may slow their admission of updates.
+    fn arrange_with_budget<Tr>(&self, name: &str, budget: usize) -> Arranged<G, TraceAgent<Tr>>
+    where
+        K: ExchangeData+Hashable,
+        V: ExchangeData,
+        R: ExchangeData,
+        Tr: Trace+TraceReader<Key=K,Val=V,Time=G::Timestamp,R=R>+'static,
+        Tr::Batch: Batch<K, V, G::Timestamp, R>,
+        Tr::Cursor: Cursor<K, V, G::Timestamp, R>,
+    {
+        let mut arranged = self.arrange_named::<Tr>(name);
+        arranged.trace.set_memory_budget(Some(budget));
+        arranged
+    }
+
     /// Arranges a stream of `(Key, Val)` updates by `Key`. Accepts an empty instance of the trace type.
     ///
     /// This operator arranges a stream of values into a shared trace, whose contents it maintains.
@@ -681,6 +805,62 @@ where
     }
 }
 
+/// Arranges a collection of `(Key, Val)` pairs with every worker holding all updates.
+///
+/// Each update is sent to every worker, whose trace then holds the entire collection. Other collections
+/// can be joined against the result without exchanging their own updates, for example with
+/// `JoinCore::join_replicated`. Each worker maintains a complete copy, so this is best suited to small
+/// collections.
+///
+/// A replicated arrangement is not partitioned by key, and must not be supplied to operators that expect
+/// each key at a single worker, such as `reduce`, `count`, or `distinct`: each worker would produce output
+/// for every key, and the result would be counted once for each worker. For the same reason at most one
+/// input of a join may be replicated.
+pub trait ArrangeReplicated<G: Scope, K: Data, V: Data, R: Semigroup>
+where
+    G::Timestamp: Lattice,
+{
+    /// Arranges a stream of `(Key, Val)` updates by `Key` at every worker.
+    fn arrange_replicated<Tr>(&self) -> Arranged<G, TraceAgent<Tr>>
+    where
+        Tr: Trace+TraceReader<Key=K,Val=V,Time=G::Timestamp,R=R>+'static,
+        Tr::Batch: Batch<K, V, G::Timestamp, R>,
+        Tr::Cursor: Cursor<K, V, G::Timestamp, R>,
+    {
+        self.arrange_replicated_named("ArrangeReplicated")
+    }
+
+    /// As `arrange_replicated`, with a name for the arranging operator.
+    fn arrange_replicated_named<Tr>(&self, name: &str) -> Arranged<G, TraceAgent<Tr>>
+    where
+        Tr: Trace+TraceReader<Key=K,Val=V,Time=G::Timestamp,R=R>+'static,
+        Tr::Batch: Batch<K, V, G::Timestamp, R>,
+        Tr::Cursor: Cursor<K, V, G::Timestamp, R>,
+    ;
+}
+
+impl<G, K, V, R> ArrangeReplicated<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: Semigroup+ExchangeData,
+{
+    fn arrange_replicated_named<Tr>(&self, name: &str) -> Arranged<G, TraceAgent<Tr>>
+    where
+        Tr: Trace+TraceReader<Key=K,Val=V,Time=G::Timestamp,R=R>+'static,
+        Tr::Batch: Batch<K, V, G::Timestamp, R>,
+        Tr::Cursor: Cursor<K, V, G::Timestamp, R>,
+    {
+        // Every worker receives every update, and so need not exchange them further.
+        self.inner
+            .broadcast()
+            .as_collection()
+            .arrange_core(Pipeline, name)
+    }
+}
+
 /// Arranges something as `(Key,Val)` pairs according to a type `T` of trace.
 ///
 /// This arrangement requires `Key: Hashable`, and uses the `hashed()` method to place keys in a hashed
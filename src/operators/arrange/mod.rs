@@ -68,8 +68,9 @@ pub mod agent;
 pub mod arrangement;
 
 pub mod upsert;
+pub mod remote;
 
 pub use self::writer::TraceWriter;
 pub use self::agent::{TraceAgent, ShutdownButton};
 
-pub use self::arrangement::{Arranged, Arrange, ArrangeByKey, ArrangeBySelf};
\ No newline at end of file
+pub use self::arrangement::{Arranged, Arrange, ArrangeByKey, ArrangeBySelf, ArrangeReplicated};
\ No newline at end of file
@@ -0,0 +1,435 @@
+//! Sharing arrangements between processes.
+//!
+//! A `TraceAgent` can publish its batches to subscribers that connect over TCP, and a subscriber can
+//! import the published batches into a dataflow of its own, as an `Arranged` that it may use as it would
+//! an arrangement produced locally. Each subscriber first receives the batches the trace holds when it
+//! connects, compacted to the frontier the publisher has reached, and then each subsequent batch and
+//! frontier advance as the publisher receives them.
+//!
+//! Publishing and subscribing are per worker: each worker of the publishing computation publishes its own
+//! part of the arrangement, and each worker of a subscribing computation imports the part it connects to.
+//! Batches are sent in the encoding of `Persist`, and so publishers and subscribers must use the same batch
+//! and timestamp types, and be built from the same code for the same architecture.
+//!
+//! A connection carries a sequence of frames, each a one byte tag, an eight byte little-endian length, and
+//! that many bytes of payload. Batch frames carry the persisted batch, and frontier frames carry the
+//! abomonated elements of the frontier. Frames longer than the subscriber's limit, `DEFAULT_MAX_FRAME_BYTES`
+//! unless set with `subscribe_with_limit`, are refused, and payloads are read as they arrive rather than
+//! allocated in advance. As each batch is sent as one frame, the limit must exceed the largest batch the
+//! publisher's trace holds.
+//!
+//! Subscribing is `unsafe`, as only fully trusted publishers may be connected to. Payloads are decoded with
+//! `abomonation`, which checks that a payload is long enough for what it describes but cannot check that
+//! its contents are valid, and so a malicious or mismatched publisher can cause undefined behavior in the
+//! subscriber. Malformed frames that are detected end the subscription with an error, reported through its
+//! `Subscription`, and the imported arrangement then holds its frontier, as its contents are incomplete.
+
+use std::cell::RefCell;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver, TryRecvError};
+use std::thread;
+
+use abomonation::{Abomonation, encode, decode};
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::operators::CapabilitySet;
+use timely::dataflow::operators::generic::source;
+use timely::order::{PartialOrder, TotalOrder};
+use timely::progress::{Antichain, Timestamp};
+use timely::scheduling::SyncActivator;
+
+use lattice::Lattice;
+use trace::{Trace, TraceReader, Batch, BatchReader};
+use trace::implementations::checkpoint::Persist;
+
+use super::{TraceAgent, Arranged, TraceReplayInstruction, ShutdownButton};
+
+/// Tags frames carrying a batch.
+const BATCH: u8 = 0;
+/// Tags frames carrying a frontier.
+const FRONTIER: u8 = 1;
+/// The number of bytes preceding the payload of a frame.
+const HEADER: usize = 9;
+/// The greatest payload a subscriber accepts in one frame, unless set with `subscribe_with_limit`.
+pub const DEFAULT_MAX_FRAME_BYTES: u64 = 64 << 20;
+
+impl<Tr> TraceAgent<Tr>
+where
+    Tr: TraceReader+'static,
+    Tr::Time: Timestamp+Lattice+Abomonation,
+    Tr::Batch: Persist<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+{
+    /// Publishes the batches of the trace to subscribers that connect to `listener`.
+    ///
+    /// The batches are forwarded by an operator in `scope`, which holds a reference to the trace and
+    /// advances its frontiers as the trace's upper frontier advances. Each subscriber is served by its own
+    /// thread, so that slow subscribers do not stall the worker, and is dropped if its connection fails.
+    /// The operator, and with it the dataflow in `scope`, completes once the trace is complete, at which
+    /// point it stops accepting subscribers.
+    pub fn publish<G>(&mut self, scope: &G, name: &str, listener: TcpListener)
+    where
+        G: Scope<Timestamp=Tr::Time>,
+    {
+        let mut agent = self.clone();
+
+        let _stream: Stream<G, ()> = source(scope, name, move |capability, info| {
+
+            // The operator produces no output, but holds its capability until the trace is complete, as an
+            // operator with neither inputs nor capabilities is shut down.
+            let mut capability = Some(capability);
+
+            let activator = scope.activator_for(&info.address[..]);
+            let queue = self.new_listener(activator);
+
+            // Connections are accepted on a separate thread, which wakes the operator.
+            let (arrived, arrivals) = channel();
+            let activator = scope.sync_activator_for(&info.address[..]);
+            let mut acceptor = Some(Acceptor::spawn(listener, arrived, activator, info.global_id)
+                .expect("failed to start publishing thread"));
+
+            let mut subscribers: Vec<Sender<Vec<u8>>> = Vec::new();
+            let mut upper = Antichain::from_elem(<Tr::Time as Timestamp>::minimum());
+
+            move |_output| {
+
+                // Forward new batches and frontiers to current subscribers.
+                let mut borrow = queue.1.borrow_mut();
+                for instruction in borrow.drain(..) {
+                    let frame = match instruction {
+                        TraceReplayInstruction::Batch(batch, _hint) => batch_frame(&batch),
+                        TraceReplayInstruction::Frontier(frontier) => {
+                            agent.advance_by(frontier.borrow());
+                            agent.distinguish_since(frontier.borrow());
+                            upper = frontier;
+                            frontier_frame(&upper)
+                        },
+                    };
+                    subscribers.retain(|subscriber| subscriber.send(frame.clone()).is_ok());
+                }
+
+                // Introduce new subscribers to the current contents of the trace.
+                while let Ok(connection) = arrivals.try_recv() {
+                    let (subscriber, frames) = channel();
+                    agent.map_batches(|batch| { let _ = subscriber.send(batch_frame(batch)); });
+                    let _ = subscriber.send(frontier_frame(&upper));
+                    let spawned = thread::Builder::new()
+                        .name(format!("differential-subscriber-{}", info.global_id))
+                        .spawn(move || write_frames(connection, frames));
+                    if spawned.is_ok() {
+                        subscribers.push(subscriber);
+                    }
+                }
+
+                // Once the trace is complete, subscribers have all they will receive.
+                if upper.is_empty() {
+                    subscribers.clear();
+                    acceptor.take();
+                    capability.take();
+                }
+            }
+        });
+    }
+}
+
+/// Reports how a subscription ended, if it ended with an error, and abandons it.
+pub struct Subscription<T> {
+    error: Rc<RefCell<Option<io::Error>>>,
+    button: ShutdownButton<CapabilitySet<T>>,
+}
+
+impl<T> Subscription<T> {
+    /// Takes the error that ended the subscription, if any.
+    ///
+    /// Errors include failed connections before the publisher's trace is complete, and received frames
+    /// that are too long or could not be decoded.
+    pub fn take_error(&self) -> Option<io::Error> {
+        self.error.borrow_mut().take()
+    }
+    /// Stops applying received frames, and releases the frontier of the imported arrangement.
+    ///
+    /// The imported arrangement then completes, whether or not it holds all that was published. This allows
+    /// the dataflow of a failed subscription, whose arrangement otherwise holds its frontier, to complete.
+    pub fn abandon(&mut self) {
+        self.button.press();
+    }
+}
+
+/// Imports the batches published at `address` into `scope`.
+///
+/// The result behaves as an arrangement of the published collection, whose trace is maintained locally
+/// from the received batches. Frames are accepted up to `DEFAULT_MAX_FRAME_BYTES`, and the imported
+/// arrangement completes once the publisher's trace is complete.
+///
+/// If the subscription fails before then, the returned `Subscription` reports the error, and the imported
+/// arrangement stops advancing but holds its frontier, so that its partial contents do not appear complete.
+/// Its dataflow completes only once the subscription is abandoned.
+///
+/// # Safety
+///
+/// Received batches and frontiers are decoded with `abomonation`, which does not validate what it decodes.
+/// The publisher at `address` must be fully trusted, and built from the same code for the same architecture,
+/// as otherwise it can cause undefined behavior in the subscriber.
+pub unsafe fn subscribe<G, Tr, A>(scope: &G, name: &str, address: A) -> io::Result<(Arranged<G, TraceAgent<Tr>>, Subscription<Tr::Time>)>
+where
+    G: Scope<Timestamp=Tr::Time>,
+    Tr: Trace+TraceReader+'static,
+    Tr::Time: Timestamp+Lattice+TotalOrder+Abomonation,
+    Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>+Persist<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    A: ToSocketAddrs,
+{
+    subscribe_with_limit(scope, name, address, DEFAULT_MAX_FRAME_BYTES)
+}
+
+/// As `subscribe`, accepting frames of at most `max_frame_bytes` bytes.
+///
+/// # Safety
+///
+/// The publisher at `address` must be fully trusted, as for `subscribe`.
+pub unsafe fn subscribe_with_limit<G, Tr, A>(scope: &G, name: &str, address: A, max_frame_bytes: u64) -> io::Result<(Arranged<G, TraceAgent<Tr>>, Subscription<Tr::Time>)>
+where
+    G: Scope<Timestamp=Tr::Time>,
+    Tr: Trace+TraceReader+'static,
+    Tr::Time: Timestamp+Lattice+TotalOrder+Abomonation,
+    Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>+Persist<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    A: ToSocketAddrs,
+{
+    subscribe_core(scope, name, address, max_frame_bytes)
+}
+
+/// Imports the batches published at `address`, trusting the publisher as `subscribe_with_limit` requires.
+fn subscribe_core<G, Tr, A>(scope: &G, name: &str, address: A, max_frame_bytes: u64) -> io::Result<(Arranged<G, TraceAgent<Tr>>, Subscription<Tr::Time>)>
+where
+    G: Scope<Timestamp=Tr::Time>,
+    Tr: Trace+TraceReader+'static,
+    Tr::Time: Timestamp+Lattice+TotalOrder+Abomonation,
+    Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>+Persist<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    A: ToSocketAddrs,
+{
+    let connection = TcpStream::connect(address)?;
+
+    let error = Rc::new(RefCell::new(None));
+    let mut reader = None;
+    let mut button = None;
+
+    let stream = {
+
+        let reader = &mut reader;
+        let button = &mut button;
+        let failure = error.clone();
+        source(scope, name, move |capability, info| {
+
+            let logger = {
+                let register = scope.log_register();
+                register.get::<::logging::DifferentialEvent>("differential/arrange")
+            };
+
+            let trace = Tr::new(info.clone(), logger.clone(), None);
+            let (reader_local, mut writer) = TraceAgent::new(trace, info.clone(), logger);
+            *reader = Some(reader_local);
+
+            // Frames are received on a separate thread, which wakes the operator.
+            let (sender, frames) = channel();
+            let activator = scope.sync_activator_for(&info.address[..]);
+            thread::Builder::new()
+                .name(format!("differential-subscribe-{}", info.global_id))
+                .spawn(move || read_frames(connection, sender, activator, max_frame_bytes))
+                .expect("failed to start subscribing thread");
+
+            let capabilities = Rc::new(RefCell::new(Some(CapabilitySet::new())));
+            let activator = scope.activator_for(&info.address[..]);
+            *button = Some(ShutdownButton::new(capabilities.clone(), activator));
+            capabilities.borrow_mut().as_mut().unwrap().insert(capability);
+
+            // Once failed, the capabilities are held until the subscription is abandoned.
+            let mut failed = false;
+
+            move |output| {
+                let mut capabilities = capabilities.borrow_mut();
+                while let Some(caps) = capabilities.as_mut() {
+                    if failed { break; }
+                    let received = match frames.try_recv() {
+                        Ok(received) => received,
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            *failure.borrow_mut() = Some(io::Error::new(io::ErrorKind::UnexpectedEof, "remote: publisher disconnected before its trace completed"));
+                            failed = true;
+                            break;
+                        },
+                    };
+                    // Applies a frame, reporting whether the publisher's trace is complete.
+                    let applied = received.and_then(|(tag, mut bytes)| match tag {
+                        BATCH => {
+                            let batch = <Tr::Batch as Persist<Tr::Key, Tr::Val, Tr::Time, Tr::R>>::restore(bytes)?;
+                            // Times are totally ordered, so the lower frontier of a batch has one element.
+                            let time = batch.lower().elements().get(0).cloned().ok_or_else(|| malformed("batch with empty lower frontier"))?;
+                            if !caps.iter().any(|cap| cap.time().less_equal(&time)) {
+                                return Err(malformed("batch precedes the received frontier"));
+                            }
+                            if !batch.is_empty() {
+                                output.session(&caps.delayed(&time)).give(batch.clone());
+                            }
+                            writer.insert(batch, Some(time));
+                            Ok(false)
+                        },
+                        FRONTIER => {
+                            let frontier = unsafe { decode::<Vec<Tr::Time>>(&mut bytes[..]) }
+                                .map(|(frontier, _)| frontier.clone())
+                                .ok_or_else(|| malformed("failed to decode frontier"))?;
+                            if !frontier.iter().all(|time| caps.iter().any(|cap| cap.time().less_equal(time))) {
+                                return Err(malformed("frontier precedes the received frontier"));
+                            }
+                            caps.downgrade(&frontier[..]);
+                            Ok(frontier.is_empty())
+                        },
+                        tag => Err(malformed(&format!("unrecognized frame tag {}", tag))),
+                    });
+                    match applied {
+                        Ok(complete) => if complete { *capabilities = None; },
+                        Err(error) => {
+                            *failure.borrow_mut() = Some(error);
+                            failed = true;
+                        },
+                    }
+                }
+            }
+        })
+    };
+
+    let subscription = Subscription { error, button: button.unwrap() };
+    Ok((Arranged { stream, trace: reader.unwrap() }, subscription))
+}
+
+/// An error describing a malformed frame.
+fn malformed(description: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("remote: {}", description))
+}
+
+/// Frames `payload` with `tag` and its length.
+fn frame<F: FnOnce(&mut Vec<u8>) -> io::Result<()>>(tag: u8, payload: F) -> Vec<u8> {
+    let mut bytes = vec![0u8; HEADER];
+    bytes[0] = tag;
+    payload(&mut bytes).expect("remote: failed to encode frame");
+    let length = (bytes.len() - HEADER) as u64;
+    bytes[1 .. HEADER].copy_from_slice(&length.to_le_bytes());
+    bytes
+}
+
+/// Frames a batch.
+fn batch_frame<K, V, T, R, B: Persist<K, V, T, R>>(batch: &B) -> Vec<u8> {
+    frame(BATCH, |bytes| batch.persist(bytes))
+}
+
+/// Frames a frontier.
+fn frontier_frame<T: Abomonation+Clone>(frontier: &Antichain<T>) -> Vec<u8> {
+    frame(FRONTIER, |bytes| unsafe { encode(&frontier.elements().to_vec(), bytes) })
+}
+
+/// Accepts subscribers on a separate thread, until dropped.
+struct Acceptor {
+    shutdown: Arc<AtomicBool>,
+    address: SocketAddr,
+}
+
+impl Acceptor {
+    /// Starts a thread handing each connection accepted by `listener` to the publishing operator.
+    fn spawn(listener: TcpListener, arrived: Sender<TcpStream>, activator: SyncActivator, id: usize) -> io::Result<Self> {
+        // The address at which the thread can be woken, which is a loopback address if `listener` accepts
+        // connections on any address.
+        let mut address = listener.local_addr()?;
+        if address.ip().is_unspecified() {
+            if address.is_ipv4() { address.set_ip(Ipv4Addr::LOCALHOST.into()); }
+            else { address.set_ip(Ipv6Addr::LOCALHOST.into()); }
+        }
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let stopped = shutdown.clone();
+        thread::Builder::new()
+            .name(format!("differential-publish-{}", id))
+            .spawn(move || accept_subscribers(listener, arrived, activator, stopped))?;
+        Ok(Acceptor { shutdown, address })
+    }
+}
+
+impl Drop for Acceptor {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the thread, which may be waiting for a connection.
+        let _ = TcpStream::connect(self.address);
+    }
+}
+
+/// Hands each accepted connection to the publishing operator, until shut down or the operator is gone.
+fn accept_subscribers(listener: TcpListener, arrived: Sender<TcpStream>, activator: SyncActivator, shutdown: Arc<AtomicBool>) {
+    for connection in listener.incoming() {
+        if shutdown.load(Ordering::SeqCst) { return; }
+        if let Ok(connection) = connection {
+            if arrived.send(connection).is_err() { return; }
+            let _ = activator.activate();
+        }
+    }
+}
+
+/// Writes frames to a subscriber until the publisher or the connection hangs up.
+fn write_frames(connection: TcpStream, frames: Receiver<Vec<u8>>) {
+    let mut writer = BufWriter::new(connection);
+    while let Ok(frame) = frames.recv() {
+        if writer.write_all(&frame).is_err() { return; }
+        // Flush only once no further frames are ready.
+        while let Ok(frame) = frames.try_recv() {
+            if writer.write_all(&frame).is_err() { return; }
+        }
+        if writer.flush().is_err() { return; }
+    }
+}
+
+/// Reads frames from a publisher and hands them to the subscribing operator, until either hangs up.
+///
+/// A connection that closes between frames ends without an error; any other failure is handed on.
+fn read_frames(connection: TcpStream, sender: Sender<io::Result<(u8, Vec<u8>)>>, activator: SyncActivator, max_frame_bytes: u64) {
+    let mut reader = BufReader::new(connection);
+    loop {
+        let received = match read_frame(&mut reader, max_frame_bytes) {
+            Ok(Some(frame)) => Ok(frame),
+            Ok(None) => break,
+            Err(error) => Err(error),
+        };
+        let failed = received.is_err();
+        if sender.send(received).is_err() { return; }
+        let _ = activator.activate();
+        if failed { break; }
+    }
+    // Wake the operator to observe the disconnection.
+    drop(sender);
+    let _ = activator.activate();
+}
+
+/// Reads one frame of at most `max_frame_bytes` bytes, or `None` if the connection closed before the frame began.
+fn read_frame<R: Read>(reader: &mut R, max_frame_bytes: u64) -> io::Result<Option<(u8, Vec<u8>)>> {
+    let mut header = [0u8; HEADER];
+    let mut filled = 0;
+    while filled < HEADER {
+        match reader.read(&mut header[filled ..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "remote: connection closed within a frame")),
+            Ok(read) => filled += read,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => { },
+            Err(error) => return Err(error),
+        }
+    }
+    let mut length = [0u8; 8];
+    length.copy_from_slice(&header[1 .. HEADER]);
+    let length = u64::from_le_bytes(length);
+    if length > max_frame_bytes {
+        return Err(malformed(&format!("frame of {} bytes exceeds the limit of {} bytes", length, max_frame_bytes)));
+    }
+    // The payload grows as bytes arrive, rather than trusting the length with an allocation.
+    let mut payload = Vec::new();
+    reader.by_ref().take(length).read_to_end(&mut payload)?;
+    if (payload.len() as u64) < length {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "remote: connection closed within a frame"));
+    }
+    Ok(Some((header[0], payload)))
+}
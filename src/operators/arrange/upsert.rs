@@ -283,10 +283,12 @@ where
                                             prev_value = next;
                                         }
                                     }
-                                    // Must insert updates in (key, val, time) order.
+                                    // Must insert updates in (key, val, time) order. Times are distinct, and at
+                                    // each time the retracted and inserted values differ, so there is nothing to
+                                    // consolidate.
                                     updates.sort();
                                     for update in updates.drain(..) {
-                                        builder.push(update);
+                                        builder.push_ordered(update);
                                     }
                                 }
                                 let batch = builder.done(input_frontier.clone(), upper.clone(), Antichain::from_elem(G::Timestamp::minimum()));
@@ -7,7 +7,7 @@ use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 
 use lattice::Lattice;
-use trace::{Trace, Batch, BatchReader};
+use trace::{Trace, TraceReader, Batch, BatchReader, MemoryPressure};
 use timely::progress::{Antichain, Timestamp};
 
 use trace::wrappers::rc::TraceBox;
@@ -58,6 +58,11 @@ where
         }
     }
 
+    /// A handle reporting whether the shared trace exceeds its memory budget, if it has a budget.
+    pub fn memory_pressure(&self) -> Option<MemoryPressure> {
+        self.trace.upgrade().and_then(|trace| trace.borrow_mut().trace.memory_pressure())
+    }
+
     /// Advances the trace by `batch`.
     ///
     /// The `hint` argument is either `None` in the case of an empty batch,
@@ -0,0 +1,227 @@
+//! Joins records against the versions of a dimension that were valid at their data times.
+//!
+//! A versioned dimension associates with each key a history of values, each valid from a data time
+//! `valid_from` until the next version of the key. An as-of join matches each record `(key, (time, val))`
+//! with the version of its key that was valid at `time`, which is the version with the greatest
+//! `valid_from` less or equal to `time`.
+//!
+//! The versions of each key are first reduced to intervals `[valid_from, valid_until)`, which are then joined
+//! with the records. The records of a key are ordered by time and its intervals by `valid_from`, and so the
+//! join matches them in a single pass over both, rather than comparing each record with each interval.
+//! Corrections to the history of a key, including versions that are inserted before or removed from between
+//! other versions, change the intervals of the neighboring versions, and the join retracts and reissues the
+//! matches of the records whose versions changed.
+//!
+//! The intervals are maintained by a `reduce`, which recomputes the intervals of a key from all of its
+//! versions each time one of them changes. A change to a key of the dimension therefore costs time linear
+//! in the number of versions of that key, as does matching a changed record against the key's intervals.
+//! Dimensions whose keys accumulate long histories should retract versions that can no longer be matched,
+//! for example those superseded before the earliest data time of interest.
+//!
+//! The data times are unrelated to the timestamps of the dataflow, and may be of any ordered type.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::Mul;
+
+use timely::dataflow::Scope;
+
+use hashable::Hashable;
+use ::{Data, ExchangeData, Collection};
+use ::difference::Semigroup;
+use lattice::Lattice;
+use operators::ValueHistory;
+use operators::arrange::{Arranged, ArrangeByKey, TraceAgent};
+use operators::join::{JoinConfig, Matcher, join_traces};
+use operators::reduce::ReduceCore;
+use trace::{BatchReader, Cursor, TraceReader};
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+
+/// The validity intervals of the versions of a dimension.
+pub type Intervals<G, K, T, D> = Arranged<G, TraceAgent<DefaultValTrace<K, (T, Option<T>, D), <G as ::timely::dataflow::scopes::ScopeParent>::Timestamp, isize>>>;
+
+/// Extension trait for the `join_as_of` differential dataflow method.
+pub trait AsOfJoin<G: Scope, K: Data, T: Data, V: Data, R: Semigroup> where G::Timestamp: Lattice+Ord {
+    /// Matches each record `(key, (time, val))` with the version of `key` in `dimension` valid at `time`.
+    ///
+    /// The dimension holds records `(key, (valid_from, dim))`, and the record is matched with the `dim` whose
+    /// `valid_from` is greatest among those less or equal to `time`. Records with times before the first
+    /// version of their key are not matched. If a key has several versions with the same `valid_from`,
+    /// the greatest is used.
+    ///
+    /// Each change to a version of a key re-examines all versions of that key, as described in the
+    /// module documentation, and so keys should not accumulate unboundedly many versions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::arrange::ArrangeByKey;
+    /// use differential_dataflow::operators::asof::AsOfJoin;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         // exchange rates of currencies, from the day they were set.
+    ///         let rates = scope.new_collection_from(vec![(0, (1, 100)), (0, (5, 110)), (1, (2, 50))]).1
+    ///                          .arrange_by_key();
+    ///
+    ///         // transactions in currencies, on a day.
+    ///         let transactions = scope.new_collection_from(vec![(0, (3, 'a')), (0, (5, 'b')), (1, (1, 'c'))]).1;
+    ///
+    ///         transactions
+    ///             .join_as_of(&rates, |_currency, &day, &name, &rate| (name, day, rate))
+    ///             .assert_eq(&scope.new_collection_from(vec![('a', 3, 100), ('b', 5, 110)]).1);
+    ///     });
+    /// }
+    /// ```
+    fn join_as_of<Tr, D, O, L>(&self, dimension: &Arranged<G, Tr>, result: L) -> Collection<G, O, <R as Mul<isize>>::Output>
+    where
+        Tr: TraceReader<Key=K, Val=(T, D), Time=G::Timestamp>+Clone+'static,
+        Tr::Batch: BatchReader<K, (T, D), G::Timestamp, Tr::R>,
+        Tr::Cursor: Cursor<K, (T, D), G::Timestamp, Tr::R>,
+        Tr::R: Semigroup+Into<isize>,
+        D: Data,
+        O: Data,
+        R: Mul<isize>,
+        <R as Mul<isize>>::Output: Semigroup,
+        L: Fn(&K, &T, &V, &D)->O+'static,
+    {
+        self.join_intervals(&intervals(dimension), result)
+    }
+
+    /// Matches each record with the interval of `intervals` containing its time.
+    ///
+    /// The intervals are those produced by `intervals`, which can be shared by several as-of joins
+    /// against the same dimension.
+    fn join_intervals<D, O, L>(&self, intervals: &Intervals<G, K, T, D>, result: L) -> Collection<G, O, <R as Mul<isize>>::Output>
+    where
+        D: Data,
+        O: Data,
+        R: Mul<isize>,
+        <R as Mul<isize>>::Output: Semigroup,
+        L: Fn(&K, &T, &V, &D)->O+'static;
+}
+
+impl<G, K, T, V, R> AsOfJoin<G, K, T, V, R> for Collection<G, (K, (T, V)), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    T: ExchangeData,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+{
+    fn join_intervals<D, O, L>(&self, intervals: &Intervals<G, K, T, D>, result: L) -> Collection<G, O, <R as Mul<isize>>::Output>
+    where
+        D: Data,
+        O: Data,
+        R: Mul<isize>,
+        <R as Mul<isize>>::Output: Semigroup,
+        L: Fn(&K, &T, &V, &D)->O+'static,
+    {
+        self.arrange_by_key()
+            .join_intervals(intervals, result)
+    }
+}
+
+impl<G, K, T, V, Tr> AsOfJoin<G, K, T, V, Tr::R> for Arranged<G, Tr>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord+Debug,
+    K: Data,
+    T: Data,
+    V: Data,
+    Tr: TraceReader<Key=K, Val=(T, V), Time=G::Timestamp>+Clone+'static,
+    Tr::R: Semigroup,
+    Tr::Batch: BatchReader<K, (T, V), G::Timestamp, Tr::R>+'static,
+    Tr::Cursor: Cursor<K, (T, V), G::Timestamp, Tr::R>+'static,
+{
+    fn join_intervals<D, O, L>(&self, intervals: &Intervals<G, K, T, D>, result: L) -> Collection<G, O, <Tr::R as Mul<isize>>::Output>
+    where
+        D: Data,
+        O: Data,
+        Tr::R: Mul<isize>,
+        <Tr::R as Mul<isize>>::Output: Semigroup,
+        L: Fn(&K, &T, &V, &D)->O+'static,
+    {
+        join_traces(self, intervals, "AsOfJoin", JoinConfig::default(), AsOf, move |key, &(ref time, ref val), _, &(_, _, ref dim)| {
+            Some(result(key, time, val, dim))
+        })
+    }
+}
+
+/// Matches records `(time, val)` with the intervals `(valid_from, valid_until, dim)` of their key containing `time`.
+///
+/// Records are presented in order of time and intervals in order of `valid_from`, and so in one pass over
+/// the records the intervals that have started by each record's time can be opened in turn. The intervals
+/// of a key do not overlap at any one dataflow time, but the history of the key may hold several that
+/// contain a time, and all open intervals that have not ended are matched.
+struct AsOf;
+
+impl<K: Ord, T: Ord, V, D> Matcher<K, (T, V), K, (T, Option<T>, D)> for AsOf {
+    #[inline] fn compare(&self, key1: &K, key2: &K) -> Ordering { key1.cmp(key2) }
+    #[inline] fn disjoint(&self) -> bool { true }
+    #[inline] fn seek1<Tm, R, C: Cursor<K, (T, V), Tm, R>>(&self, cursor: &mut C, storage: &C::Storage, key2: &K) { cursor.seek_key(storage, key2); }
+    #[inline] fn seek2<Tm, R, C: Cursor<K, (T, Option<T>, D), Tm, R>>(&self, cursor: &mut C, storage: &C::Storage, key1: &K) { cursor.seek_key(storage, key1); }
+    fn think<'a, 'b, Tm, R1, R2, F>(&self, history1: &mut ValueHistory<'a, (T, V), Tm, R1>, history2: &mut ValueHistory<'b, (T, Option<T>, D), Tm, R2>, mut results: F)
+    where (T, V): Ord+Clone+Debug, (T, Option<T>, D): Ord+Clone+Debug, Tm: Lattice+Ord+Clone+Debug, R1: Semigroup, R2: Semigroup, F: FnMut(&(T, V), &(T, Option<T>, D), Tm, &R1, &R2) {
+
+        let intervals = history2.edits.value_edits().collect::<Vec<_>>();
+        let mut next = 0;
+        // positions of the intervals that have started, and had not ended by the previous record's time.
+        let mut open = Vec::new();
+
+        for (record, edits1) in history1.edits.value_edits() {
+            let time = &record.0;
+            while next < intervals.len() && &(intervals[next].0).0 <= time {
+                open.push(next);
+                next += 1;
+            }
+            // An interval that has ended by this record's time has ended for all later records too.
+            open.retain(|&index| (intervals[index].0).1.as_ref().map(|until| time < until).unwrap_or(true));
+            for &index in open.iter() {
+                let (interval, edits2) = intervals[index];
+                for &(ref time1, ref diff1) in edits1.iter() {
+                    for &(ref time2, ref diff2) in edits2.iter() {
+                        results(record, interval, time1.join(time2), diff1, diff2);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reduces the versions of each key of `dimension` to the intervals of data times for which they are valid.
+///
+/// Each version `(key, (valid_from, dim))` becomes `(key, (valid_from, valid_until, dim))`, where `valid_until`
+/// is the `valid_from` of the next version of the key, or `None` for the last version. Versions whose updates
+/// do not accumulate to a positive count are ignored.
+///
+/// Each change to the versions of a key recomputes the intervals of all versions of that key.
+pub fn intervals<G, K, T, D, Tr>(dimension: &Arranged<G, Tr>) -> Intervals<G, K, T, D>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: Data,
+    T: Data,
+    D: Data,
+    Tr: TraceReader<Key=K, Val=(T, D), Time=G::Timestamp>+Clone+'static,
+    Tr::Batch: BatchReader<K, (T, D), G::Timestamp, Tr::R>,
+    Tr::Cursor: Cursor<K, (T, D), G::Timestamp, Tr::R>,
+    Tr::R: Semigroup+Into<isize>,
+{
+    dimension.reduce_abelian::<_,DefaultValTrace<_,_,_,_>>("AsOfIntervals", |_key, input, output| {
+        // Versions are presented in order of `(valid_from, dim)`, and so the last of each `valid_from` is kept.
+        let versions = input.iter().filter(|&&(_, ref count)| Into::<isize>::into(count.clone()) > 0).map(|&(version, _)| version).collect::<Vec<_>>();
+        for (index, &&(ref from, ref dim)) in versions.iter().enumerate() {
+            let next = versions.get(index + 1).map(|&&(ref next, _)| next);
+            if next != Some(from) {
+                output.push(((from.clone(), next.cloned(), dim.clone()), 1));
+            }
+        }
+    })
+}
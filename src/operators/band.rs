@@ -0,0 +1,204 @@
+//! Match pairs of records whose keys are related by a range, rather than by equality.
+//!
+//! A band join matches each record `(key1, val1)` with the records `(key2, val2)` of another collection
+//! whose keys lie in a range `range(&key1)`, for example `key1 - lo .. key1 + hi + 1`. The matching keys
+//! are found by seeking the range with a cursor of the other input's ordered arrangement, rather than by
+//! exploding each record into the buckets of keys it might match.
+//!
+//! As the keys of a range may be held by any worker, the first input is replicated to all workers, and
+//! each worker matches it against its own part of the second input. The first input should be the smaller.
+//!
+//! Changes to the second input are matched against the records of the first input whose ranges contain
+//! their keys. For these records to be found by a range seek, the ranges must be monotone: neither end of
+//! `range(&key1)` may decrease as `key1` increases.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::Mul;
+
+use timely::dataflow::Scope;
+
+use hashable::Hashable;
+use ::{Data, ExchangeData, Collection};
+use ::difference::Semigroup;
+use lattice::Lattice;
+use operators::arrange::{Arranged, ArrangeByKey, ArrangeReplicated};
+use operators::join::{JoinConfig, Matcher, join_traces};
+use trace::{BatchReader, Cursor, TraceReader};
+use trace::cursor::range::KeyRange;
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+
+/// Extension trait for the `band_join` differential dataflow methods.
+pub trait BandJoin<G: Scope, K: 'static, V: 'static, R: Semigroup> where G::Timestamp: Lattice+Ord {
+    /// Matches each record `(key1, val1)` with the records `(key2, val2)` of `other` for which `key2` is in `range(&key1)`.
+    ///
+    /// The ends of `range(&key1)` must not decrease as `key1` increases.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::band::BandJoin;
+    /// use differential_dataflow::trace::cursor::range::Between;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         // events at times, and alerts at times.
+    ///         let events = scope.new_collection_from(vec![(10, 'a'), (20, 'b')]).1;
+    ///         let alerts = scope.new_collection_from(vec![(7, 'x'), (14, 'y'), (30, 'z')]).1;
+    ///
+    ///         // alerts from five before to five after each event.
+    ///         events
+    ///             .band_join(&alerts, |&time| Between { lower: time - 5, upper: time + 6 })
+    ///             .assert_eq(&scope.new_collection_from(vec![((10, 'a'), (7, 'x')), ((10, 'a'), (14, 'y'))]).1);
+    ///     });
+    /// }
+    /// ```
+    fn band_join<K2, V2, R2, Q, F>(&self, other: &Collection<G, (K2, V2), R2>, range: F) -> Collection<G, ((K, V), (K2, V2)), <R as Mul<R2>>::Output>
+    where
+        K: Data,
+        V: Data,
+        K2: ExchangeData+Hashable,
+        V2: ExchangeData,
+        R2: ExchangeData+Semigroup,
+        R: Mul<R2>,
+        <R as Mul<R2>>::Output: Semigroup,
+        Q: KeyRange<K2>,
+        F: Fn(&K)->Q+'static,
+    {
+        self.band_join_core(&other.arrange_by_key(), range, |k1, v1, k2, v2| Some(((k1.clone(), v1.clone()), (k2.clone(), v2.clone()))))
+    }
+
+    /// Matches each record with the records of the arrangement `other` whose keys are in `range(&key1)`, and applies a function.
+    ///
+    /// Each matching pair of records `(key1, val1)` and `(key2, val2)` is subjected to `result`, which produces
+    /// something implementing `IntoIterator`. The ends of `range(&key1)` must not decrease as `key1` increases.
+    ///
+    /// When implemented for an arrangement, the arrangement must be replicated to all workers, as by
+    /// `ArrangeReplicated::arrange_replicated`. The arrangement `other` may be partitioned arbitrarily.
+    fn band_join_core<Tr2, Q, F, I, L>(&self, other: &Arranged<G, Tr2>, range: F, result: L) -> Collection<G, I::Item, <R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Time=G::Timestamp>+Clone+'static,
+        Tr2::Key: Ord+Clone+Debug+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        Tr2::Batch: BatchReader<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        R: Mul<Tr2::R>,
+        <R as Mul<Tr2::R>>::Output: Semigroup,
+        Q: KeyRange<Tr2::Key>,
+        F: Fn(&K)->Q+'static,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K, &V, &Tr2::Key, &Tr2::Val)->I+'static,
+    {
+        self.band_join_core_with(other, range, JoinConfig::default(), result)
+    }
+
+    /// As `band_join_core`, but performing deferred work as configured by `config`.
+    fn band_join_core_with<Tr2, Q, F, I, L>(&self, other: &Arranged<G, Tr2>, range: F, config: JoinConfig, result: L) -> Collection<G, I::Item, <R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Time=G::Timestamp>+Clone+'static,
+        Tr2::Key: Ord+Clone+Debug+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        Tr2::Batch: BatchReader<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        R: Mul<Tr2::R>,
+        <R as Mul<Tr2::R>>::Output: Semigroup,
+        Q: KeyRange<Tr2::Key>,
+        F: Fn(&K)->Q+'static,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K, &V, &Tr2::Key, &Tr2::Val)->I+'static;
+}
+
+impl<G, K, V, R> BandJoin<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord+Debug,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+{
+    fn band_join_core_with<Tr2, Q, F, I, L>(&self, other: &Arranged<G, Tr2>, range: F, config: JoinConfig, result: L) -> Collection<G, I::Item, <R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Time=G::Timestamp>+Clone+'static,
+        Tr2::Key: Ord+Clone+Debug+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        Tr2::Batch: BatchReader<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        R: Mul<Tr2::R>,
+        <R as Mul<Tr2::R>>::Output: Semigroup,
+        Q: KeyRange<Tr2::Key>,
+        F: Fn(&K)->Q+'static,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K, &V, &Tr2::Key, &Tr2::Val)->I+'static,
+    {
+        self.arrange_replicated::<DefaultValTrace<K, V, G::Timestamp, R>>()
+            .band_join_core_with(other, range, config, result)
+    }
+}
+
+impl<G, T1> BandJoin<G, T1::Key, T1::Val, T1::R> for Arranged<G, T1>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord+Debug,
+    T1: TraceReader<Time=G::Timestamp>+Clone+'static,
+    T1::Key: Ord+Clone+Debug+'static,
+    T1::Val: Ord+Clone+Debug+'static,
+    T1::R: Semigroup,
+    T1::Batch: BatchReader<T1::Key, T1::Val, G::Timestamp, T1::R>+'static,
+    T1::Cursor: Cursor<T1::Key, T1::Val, G::Timestamp, T1::R>+'static,
+{
+    fn band_join_core_with<Tr2, Q, F, I, L>(&self, other: &Arranged<G, Tr2>, range: F, config: JoinConfig, result: L) -> Collection<G, I::Item, <T1::R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Time=G::Timestamp>+Clone+'static,
+        Tr2::Key: Ord+Clone+Debug+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        Tr2::Batch: BatchReader<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<Tr2::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        T1::R: Mul<Tr2::R>,
+        <T1::R as Mul<Tr2::R>>::Output: Semigroup,
+        Q: KeyRange<Tr2::Key>,
+        F: Fn(&T1::Key)->Q+'static,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&T1::Key, &T1::Val, &Tr2::Key, &Tr2::Val)->I+'static,
+    {
+        join_traces(self, other, "BandJoin", config, Band { range }, result)
+    }
+}
+
+/// Matches the keys of the second input in `range(&key1)` with `key1`.
+///
+/// Batches of the first input seek their ranges in the second input, and batches of the second input
+/// seek the keys of the first input whose ranges contain their keys. These keys are contiguous because
+/// neither end of `range` decreases as its argument increases: the keys whose ranges end at or before a
+/// key come first, and the keys whose ranges start after it come last.
+struct Band<F> {
+    range: F,
+}
+
+impl<K1, V1, K2, V2, Q, F> Matcher<K1, V1, K2, V2> for Band<F>
+where
+    Q: KeyRange<K2>,
+    F: Fn(&K1)->Q,
+{
+    #[inline]
+    fn compare(&self, key1: &K1, key2: &K2) -> Ordering {
+        let range = (self.range)(key1);
+        if range.after(key2) { Ordering::Less }
+        else if range.before(key2) { Ordering::Greater }
+        else { Ordering::Equal }
+    }
+    // The ranges of distinct keys may overlap, and are sought by the default `seek1` and `seek2`.
+    #[inline] fn disjoint(&self) -> bool { false }
+}
@@ -19,7 +19,10 @@ use hashable::Hashable;
 use ::{Data, ExchangeData, Collection, AsCollection};
 use ::difference::{Semigroup, Abelian};
 use lattice::Lattice;
-use operators::arrange::{Arranged, ArrangeByKey, ArrangeBySelf};
+use operators::arrange::{Arranged, Arrange, ArrangeByKey, ArrangeBySelf};
+use operators::reduce::ReduceCore;
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+use trace::implementations::ord::OrdKeySpine as DefaultKeyTrace;
 use trace::{BatchReader, Cursor};
 use operators::ValueHistory;
 
@@ -150,6 +153,48 @@ pub trait Join<G: Scope, K: Data, V: Data, R: Semigroup> {
     /// ```
     fn antijoin<R2>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
     where K: ExchangeData, R2: ExchangeData+Semigroup, R: Mul<R2, Output = R>, R: Abelian;
+
+    /// Matches pairs `(key,val1)` and `(key,val2)`, and also reports records of `self` with no match in `other`.
+    ///
+    /// Matched records are reported as `(key, Some(val1), Some(val2))`, and unmatched records of `self` as
+    /// `(key, Some(val1), None)`. As matches appear and disappear, the unmatched records are retracted and
+    /// reintroduced. A key is matched if its records in `other` accumulate to anything other than zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Join;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let x = scope.new_collection_from(vec![(0, 1), (1, 3)]).1;
+    ///         let y = scope.new_collection_from(vec![(0, 'a'), (2, 'b')]).1;
+    ///         let z = scope.new_collection_from(vec![(0, Some(1), Some('a')), (1, Some(3), None)]).1;
+    ///
+    ///         x.left_join(&y)
+    ///          .assert_eq(&z);
+    ///     });
+    /// }
+    /// ```
+    fn left_join<V2>(&self, other: &Collection<G, (K, V2), R>) -> Collection<G, (K, Option<V>, Option<V2>), R>
+    where K: ExchangeData, V2: ExchangeData, R: ExchangeData+Abelian+Mul<R, Output=R>+From<i8>;
+
+    /// Matches pairs `(key,val1)` and `(key,val2)`, and also reports records of `other` with no match in `self`.
+    ///
+    /// Unmatched records of `other` are reported as `(key, None, Some(val2))`. See `left_join` for details.
+    fn right_join<V2>(&self, other: &Collection<G, (K, V2), R>) -> Collection<G, (K, Option<V>, Option<V2>), R>
+    where K: ExchangeData, V2: ExchangeData, R: ExchangeData+Abelian+Mul<R, Output=R>+From<i8>;
+
+    /// Matches pairs `(key,val1)` and `(key,val2)`, and also reports the unmatched records of both inputs.
+    ///
+    /// See `left_join` and `right_join` for details.
+    fn full_join<V2>(&self, other: &Collection<G, (K, V2), R>) -> Collection<G, (K, Option<V>, Option<V2>), R>
+    where K: ExchangeData, V2: ExchangeData, R: ExchangeData+Abelian+Mul<R, Output=R>+From<i8>;
 }
 
 impl<G, K, V, R> Join<G, K, V, R> for Collection<G, (K, V), R>
@@ -178,6 +223,24 @@ where
     where R: Mul<R2, Output=R>, R: Abelian {
         self.concat(&self.semijoin(other).negate())
     }
+
+    fn left_join<V2: ExchangeData>(&self, other: &Collection<G, (K, V2), R>) -> Collection<G, (K, Option<V>, Option<V2>), R>
+    where R: Abelian+Mul<R, Output=R>+From<i8> {
+        self.arrange_by_key()
+            .left_join_core(&other.arrange_by_key())
+    }
+
+    fn right_join<V2: ExchangeData>(&self, other: &Collection<G, (K, V2), R>) -> Collection<G, (K, Option<V>, Option<V2>), R>
+    where R: Abelian+Mul<R, Output=R>+From<i8> {
+        self.arrange_by_key()
+            .right_join_core(&other.arrange_by_key())
+    }
+
+    fn full_join<V2: ExchangeData>(&self, other: &Collection<G, (K, V2), R>) -> Collection<G, (K, Option<V>, Option<V2>), R>
+    where R: Abelian+Mul<R, Output=R>+From<i8> {
+        self.arrange_by_key()
+            .full_join_core(&other.arrange_by_key())
+    }
 }
 
 impl<G, Tr> Join<G, Tr::Key, Tr::Val, Tr::R> for Arranged<G, Tr>
@@ -208,6 +271,131 @@ where
         self.as_collection(|k,v| (k.clone(), v.clone()))
             .concat(&self.semijoin(other).negate())
     }
+
+    fn left_join<V2: ExchangeData>(&self, other: &Collection<G, (Tr::Key, V2), Tr::R>) -> Collection<G, (Tr::Key, Option<Tr::Val>, Option<V2>), Tr::R>
+    where Tr::Key: ExchangeData, Tr::R: ExchangeData+Abelian+Mul<Tr::R, Output=Tr::R>+From<i8> {
+        self.left_join_core(&other.arrange_by_key())
+    }
+
+    fn right_join<V2: ExchangeData>(&self, other: &Collection<G, (Tr::Key, V2), Tr::R>) -> Collection<G, (Tr::Key, Option<Tr::Val>, Option<V2>), Tr::R>
+    where Tr::Key: ExchangeData, Tr::R: ExchangeData+Abelian+Mul<Tr::R, Output=Tr::R>+From<i8> {
+        self.right_join_core(&other.arrange_by_key())
+    }
+
+    fn full_join<V2: ExchangeData>(&self, other: &Collection<G, (Tr::Key, V2), Tr::R>) -> Collection<G, (Tr::Key, Option<Tr::Val>, Option<V2>), Tr::R>
+    where Tr::Key: ExchangeData, Tr::R: ExchangeData+Abelian+Mul<Tr::R, Output=Tr::R>+From<i8> {
+        self.full_join_core(&other.arrange_by_key())
+    }
+}
+
+/// Controls how a join performs its deferred work.
+///
+/// Each time the join operator is scheduled, it performs deferred work for each of its inputs until it
+/// has produced `fuel` output updates, or until the yield policy indicates it should yield to other
+/// operators. Work that remains is resumed the next time the operator is scheduled.
+#[derive(Clone, Debug)]
+pub struct JoinConfig {
+    fuel: usize,
+    yield_policy: YieldPolicy,
+    order: WorkOrder,
+}
+
+impl JoinConfig {
+    /// A configuration with the default fuel, yield policy, and work order.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets the number of output updates each input may produce each time the operator is scheduled.
+    pub fn fuel(mut self, fuel: usize) -> Self {
+        assert!(fuel > 0, "Join fuel must be positive");
+        self.fuel = fuel;
+        self
+    }
+    /// Sets when the operator yields before its fuel is spent.
+    pub fn yield_policy(mut self, yield_policy: YieldPolicy) -> Self {
+        self.yield_policy = yield_policy;
+        self
+    }
+    /// Sets the order in which deferred work is performed.
+    pub fn order(mut self, order: WorkOrder) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+impl Default for JoinConfig {
+    fn default() -> Self {
+        JoinConfig {
+            fuel: 1_000_000,
+            yield_policy: YieldPolicy::Fuel,
+            order: WorkOrder::Fifo,
+        }
+    }
+}
+
+/// When a join yields to other operators.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum YieldPolicy {
+    /// Yields once the fuel is spent.
+    Fuel,
+    /// Yields once the fuel is spent, or after completing the work for a batch.
+    Batch,
+    /// Yields once the fuel is spent, or once the duration has elapsed.
+    Elapsed(::std::time::Duration),
+}
+
+impl YieldPolicy {
+    /// Indicates whether to yield after work started at `started`, having just `completed` a batch or not.
+    fn should_yield(&self, started: ::std::time::Instant, completed: bool) -> bool {
+        match *self {
+            YieldPolicy::Fuel => false,
+            YieldPolicy::Batch => completed,
+            YieldPolicy::Elapsed(duration) => started.elapsed() >= duration,
+        }
+    }
+    /// The instant by which work started at `started` should stop, even within a batch.
+    fn deadline(&self, started: ::std::time::Instant) -> Option<::std::time::Instant> {
+        match *self {
+            YieldPolicy::Elapsed(duration) => Some(started + duration),
+            _ => None,
+        }
+    }
+}
+
+/// The order in which a join performs the deferred work of the batches of each input.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WorkOrder {
+    /// Batches are worked on in the order they were received.
+    Fifo,
+    /// The batch with the fewest updates is worked on first, so that small batches are not held up by large ones.
+    ///
+    /// So that a steady arrival of small batches cannot starve a large one, a batch passed over
+    /// `STARVATION_LIMIT` times is worked on ahead of smaller batches.
+    SmallestFirst,
+}
+
+/// The number of times `WorkOrder::SmallestFirst` may pass over a batch before working on it regardless of size.
+const STARVATION_LIMIT: usize = 16;
+
+impl WorkOrder {
+    /// Selects the position of the next batch to work on, from the sizes of the batches in order of receipt
+    /// and the number of times each has been passed over.
+    fn select<I: Iterator<Item=(usize, usize)>>(&self, batches: I) -> usize {
+        match *self {
+            WorkOrder::Fifo => 0,
+            WorkOrder::SmallestFirst => {
+                let mut smallest: Option<(usize, usize)> = None;
+                for (index, (size, passed)) in batches.enumerate() {
+                    // Batches are in order of receipt, and so this is the oldest starved batch.
+                    if passed >= STARVATION_LIMIT { return index; }
+                    if smallest.map(|(_, least)| size < least).unwrap_or(true) {
+                        smallest = Some((index, size));
+                    }
+                }
+                smallest.map(|(index, _)| index).unwrap_or(0)
+            },
+        }
+    }
 }
 
 /// Matches the elements of two arranged traces.
@@ -265,6 +453,118 @@ pub trait JoinCore<G: Scope, K: 'static, V: 'static, R: Semigroup> where G::Time
         I::Item: Data,
         L: FnMut(&K,&V,&Tr2::Val)->I+'static,
         ;
+
+    /// As `join_core`, with control over how the operator performs its work.
+    ///
+    /// The join defers the work of matching each received batch against the other input, and performs
+    /// it a little at a time. The `config` determines how much work is performed each time the operator
+    /// is scheduled, when it yields to other operators, and which deferred work it performs first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::arrange::ArrangeByKey;
+    /// use differential_dataflow::operators::join::{JoinCore, JoinConfig, WorkOrder, YieldPolicy};
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         let x = scope.new_collection_from(vec![(0u32, 1), (1, 3)]).1
+    ///                      .arrange_by_key();
+    ///         let y = scope.new_collection_from(vec![(0, 'a'), (1, 'b')]).1
+    ///                      .arrange_by_key();
+    ///
+    ///         let z = scope.new_collection_from(vec![(1, 'a'), (3, 'b')]).1;
+    ///
+    ///         let config = JoinConfig::new()
+    ///             .fuel(1_000)
+    ///             .yield_policy(YieldPolicy::Batch)
+    ///             .order(WorkOrder::SmallestFirst);
+    ///
+    ///         x.join_core_with(&y, config, |_key, &a, &b| Some((a, b)))
+    ///          .assert_eq(&z);
+    ///     });
+    /// }
+    /// ```
+    fn join_core_with<Tr2,I,L> (&self, stream2: &Arranged<G,Tr2>, config: JoinConfig, result: L) -> Collection<G,I::Item,<R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        R: Mul<Tr2::R>,
+        <R as Mul<Tr2::R>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K,&V,&Tr2::Val)->I+'static,
+        ;
+
+    /// Joins against an arrangement replicated at every worker, without exchanging `self`.
+    ///
+    /// The `replicated` arrangement must hold the entire collection at each worker, as produced by
+    /// `ArrangeReplicated::arrange_replicated`. Each worker then joins whichever records of `self` it
+    /// happens to hold, and neither input is exchanged between workers. The results are incorrect if
+    /// `replicated` is instead partitioned among workers.
+    fn join_replicated<Tr2,I,L> (&self, replicated: &Arranged<G,Tr2>, result: L) -> Collection<G,I::Item,<R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        R: Mul<Tr2::R>,
+        <R as Mul<Tr2::R>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K,&V,&Tr2::Val)->I+'static,
+        ;
+
+    /// Matches two arranged collections, and also reports records of `self` with no match in `other`.
+    ///
+    /// Matched records are reported as `(key, Some(val1), Some(val2))`, and unmatched records of `self` as
+    /// `(key, Some(val1), None)`. The keys present in `other` are determined from its arrangement, and
+    /// `self` is used in its arranged form, so neither input is arranged again.
+    fn left_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(K,Option<V>,Option<Tr2::Val>),R>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp, R=R>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Val: Data,
+        K: Data,
+        V: Data,
+        R: Abelian+Mul<R, Output=R>+From<i8>,
+        ;
+
+    /// Matches two arranged collections, and also reports records of `other` with no match in `self`.
+    ///
+    /// Unmatched records of `other` are reported as `(key, None, Some(val2))`.
+    fn right_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(K,Option<V>,Option<Tr2::Val>),R>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp, R=R>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Val: Data,
+        K: Data,
+        V: Data,
+        R: Abelian+Mul<R, Output=R>+From<i8>,
+        ;
+
+    /// Matches two arranged collections, and also reports the unmatched records of both.
+    fn full_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(K,Option<V>,Option<Tr2::Val>),R>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp, R=R>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Val: Data,
+        K: Data,
+        V: Data,
+        R: Abelian+Mul<R, Output=R>+From<i8>,
+        ;
 }
 
 
@@ -292,6 +592,77 @@ where
         self.arrange_by_key()
             .join_core(stream2, result)
     }
+
+    fn join_core_with<Tr2,I,L> (&self, stream2: &Arranged<G,Tr2>, config: JoinConfig, result: L) -> Collection<G,I::Item,<R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        R: Mul<Tr2::R>,
+        <R as Mul<Tr2::R>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K,&V,&Tr2::Val)->I+'static,
+    {
+        self.arrange_by_key()
+            .join_core_with(stream2, config, result)
+    }
+
+    fn join_replicated<Tr2,I,L> (&self, replicated: &Arranged<G,Tr2>, result: L) -> Collection<G,I::Item,<R as Mul<Tr2::R>>::Output>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2::R: Semigroup,
+        R: Mul<Tr2::R>,
+        <R as Mul<Tr2::R>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&K,&V,&Tr2::Val)->I+'static,
+    {
+        // Arrange the records where they are, as `replicated` holds all matches at every worker.
+        self.arrange_core::<_, DefaultValTrace<K,V,G::Timestamp,R>>(Pipeline, "ArrangeLocal")
+            .join_core(replicated, result)
+    }
+
+    fn left_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(K,Option<V>,Option<Tr2::Val>),R>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp, R=R>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Val: Data,
+        R: Abelian+Mul<R, Output=R>+From<i8>,
+    {
+        self.arrange_by_key()
+            .left_join_core(other)
+    }
+
+    fn right_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(K,Option<V>,Option<Tr2::Val>),R>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp, R=R>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Val: Data,
+        R: Abelian+Mul<R, Output=R>+From<i8>,
+    {
+        self.arrange_by_key()
+            .right_join_core(other)
+    }
+
+    fn full_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(K,Option<V>,Option<Tr2::Val>),R>
+    where
+        Tr2: TraceReader<Key=K, Time=G::Timestamp, R=R>+Clone+'static,
+        Tr2::Batch: BatchReader<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Cursor: Cursor<K, Tr2::Val, G::Timestamp, R>+'static,
+        Tr2::Val: Data,
+        R: Abelian+Mul<R, Output=R>+From<i8>,
+    {
+        self.arrange_by_key()
+            .full_join_core(other)
+    }
 }
 
 impl<G, T1> JoinCore<G, T1::Key, T1::Val, T1::R> for Arranged<G,T1>
@@ -305,7 +676,46 @@ impl<G, T1> JoinCore<G, T1::Key, T1::Val, T1::R> for Arranged<G,T1>
         T1::Batch: BatchReader<T1::Key,T1::Val,G::Timestamp,T1::R>+'static,
         T1::Cursor: Cursor<T1::Key,T1::Val,G::Timestamp,T1::R>+'static,
 {
-    fn join_core<Tr2,I,L>(&self, other: &Arranged<G,Tr2>, mut result: L) -> Collection<G,I::Item,<T1::R as Mul<Tr2::R>>::Output>
+    fn left_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(T1::Key,Option<T1::Val>,Option<Tr2::Val>),T1::R>
+    where
+        Tr2: TraceReader<Key=T1::Key, Time=G::Timestamp, R=T1::R>+Clone+'static,
+        Tr2::Batch: BatchReader<T1::Key, Tr2::Val, G::Timestamp, T1::R>+'static,
+        Tr2::Cursor: Cursor<T1::Key, Tr2::Val, G::Timestamp, T1::R>+'static,
+        Tr2::Val: Data,
+        T1::Key: Data,
+        T1::Val: Data,
+        T1::R: Abelian+Mul<T1::R, Output=T1::R>+From<i8>,
+    {
+        outer_join(self, other, true, false)
+    }
+
+    fn right_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(T1::Key,Option<T1::Val>,Option<Tr2::Val>),T1::R>
+    where
+        Tr2: TraceReader<Key=T1::Key, Time=G::Timestamp, R=T1::R>+Clone+'static,
+        Tr2::Batch: BatchReader<T1::Key, Tr2::Val, G::Timestamp, T1::R>+'static,
+        Tr2::Cursor: Cursor<T1::Key, Tr2::Val, G::Timestamp, T1::R>+'static,
+        Tr2::Val: Data,
+        T1::Key: Data,
+        T1::Val: Data,
+        T1::R: Abelian+Mul<T1::R, Output=T1::R>+From<i8>,
+    {
+        outer_join(self, other, false, true)
+    }
+
+    fn full_join_core<Tr2>(&self, other: &Arranged<G,Tr2>) -> Collection<G,(T1::Key,Option<T1::Val>,Option<Tr2::Val>),T1::R>
+    where
+        Tr2: TraceReader<Key=T1::Key, Time=G::Timestamp, R=T1::R>+Clone+'static,
+        Tr2::Batch: BatchReader<T1::Key, Tr2::Val, G::Timestamp, T1::R>+'static,
+        Tr2::Cursor: Cursor<T1::Key, Tr2::Val, G::Timestamp, T1::R>+'static,
+        Tr2::Val: Data,
+        T1::Key: Data,
+        T1::Val: Data,
+        T1::R: Abelian+Mul<T1::R, Output=T1::R>+From<i8>,
+    {
+        outer_join(self, other, true, true)
+    }
+
+    fn join_replicated<Tr2,I,L>(&self, replicated: &Arranged<G,Tr2>, result: L) -> Collection<G,I::Item,<T1::R as Mul<Tr2::R>>::Output>
     where
         Tr2::Val: Ord+Clone+Debug+'static,
         Tr2: TraceReader<Key=T1::Key,Time=G::Timestamp>+Clone+'static,
@@ -317,222 +727,425 @@ impl<G, T1> JoinCore<G, T1::Key, T1::Val, T1::R> for Arranged<G,T1>
         I: IntoIterator,
         I::Item: Data,
         L: FnMut(&T1::Key,&T1::Val,&Tr2::Val)->I+'static {
+        // However `self` is partitioned, each worker holds all matches for its keys in `replicated`.
+        self.join_core(replicated, result)
+    }
 
-        // handles to shared trace data structures.
-        let mut trace1 = Some(self.trace.clone());
-        let mut trace2 = Some(other.trace.clone());
-
-        // acknowledged frontier for each input.
-        use timely::progress::frontier::Antichain;
-        let mut acknowledged1: Option<Antichain<G::Timestamp>> = None;
-        let mut acknowledged2: Option<Antichain<G::Timestamp>> = None;
-
-        // deferred work of batches from each input.
-        let mut todo1 = std::collections::VecDeque::new();
-        let mut todo2 = std::collections::VecDeque::new();
-
-        let mut input1_buffer = Vec::new();
-        let mut input2_buffer = Vec::new();
-
-        self.stream.binary_frontier(&other.stream, Pipeline, Pipeline, "Join", move |_cap, info| {
-
-            use timely::scheduling::Activator;
-            let activations = self.stream.scope().activations().clone();
-            let activator = Activator::new(&info.address[..], activations);
-
-            move |input1, input2, output| {
-
-                // The join computation repeatedly accepts batches of updates from each of its inputs.
-                //
-                // For each accepted batch, it prepares a work-item to join the batch against previously "accepted"
-                // updates from its other input. It is important to track which updates have been accepted, through
-                // a combination of the input's frontier and the most recently received batch's upper bound, because
-                // we use a shared trace and there may be updates present that are in advance of this accepted bound.
-
-                // drain input 1, prepare work.
-                input1.for_each(|capability, data| {
-                    if let Some(ref mut trace2) = trace2 {
-                        let capability = capability.retain();
-                        data.swap(&mut input1_buffer);
-                        for batch1 in input1_buffer.drain(..) {
-                            if !batch1.is_empty() {
-                                if let Some(acknowledged2) = &acknowledged2 {
-                                    // TODO : cursor_through may be problematic for pre-merged traces.
-                                    // A trace should provide the contract that whatever its `distinguish_since` capability,
-                                    // it is safe (and reasonable) to await delivery of batches up through that frontier.
-                                    // In this case, we should be able to await (not block on) the arrival of these batches.
-                                    let (trace2_cursor, trace2_storage) = trace2.cursor_through(acknowledged2.borrow()).unwrap();
-                                    let batch1_cursor = batch1.cursor();
-                                    todo1.push_back(Deferred::new(trace2_cursor, trace2_storage, batch1_cursor, batch1.clone(), capability.clone(), |r2,r1| (r1.clone()) * (r2.clone())));
-                                }
+    fn join_core<Tr2,I,L>(&self, other: &Arranged<G,Tr2>, result: L) -> Collection<G,I::Item,<T1::R as Mul<Tr2::R>>::Output>
+    where
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2: TraceReader<Key=T1::Key,Time=G::Timestamp>+Clone+'static,
+        Tr2::Batch: BatchReader<T1::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<T1::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::R: Semigroup,
+        T1::R: Mul<Tr2::R>,
+        <T1::R as Mul<Tr2::R>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&T1::Key,&T1::Val,&Tr2::Val)->I+'static {
+        self.join_core_with(other, JoinConfig::default(), result)
+    }
+
+    fn join_core_with<Tr2,I,L>(&self, other: &Arranged<G,Tr2>, config: JoinConfig, mut result: L) -> Collection<G,I::Item,<T1::R as Mul<Tr2::R>>::Output>
+    where
+        Tr2::Val: Ord+Clone+Debug+'static,
+        Tr2: TraceReader<Key=T1::Key,Time=G::Timestamp>+Clone+'static,
+        Tr2::Batch: BatchReader<T1::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::Cursor: Cursor<T1::Key, Tr2::Val, G::Timestamp, Tr2::R>+'static,
+        Tr2::R: Semigroup,
+        T1::R: Mul<Tr2::R>,
+        <T1::R as Mul<Tr2::R>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: FnMut(&T1::Key,&T1::Val,&Tr2::Val)->I+'static {
+        join_traces(self, other, "Join", config, EqualKeys, move |k,v1,_,v2| result(k,v1,v2))
+    }
+}
+
+/// Matches the records of two arrangements, as determined by `matcher`, and applies `result` to each match.
+///
+/// This is the operator behind `join_core`, and behind the joins of other modules that match keys other than
+/// by equality, or values other than all with all.
+pub(super) fn join_traces<G, T1, T2, M, I, L>(arranged1: &Arranged<G,T1>, arranged2: &Arranged<G,T2>, name: &str, config: JoinConfig, matcher: M, mut result: L) -> Collection<G,I::Item,<T1::R as Mul<T2::R>>::Output>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord+Debug,
+    T1: TraceReader<Time=G::Timestamp>+Clone+'static,
+    T1::Key: Eq+'static,
+    T1::Val: Ord+Clone+Debug+'static,
+    T1::R: Semigroup,
+    T1::Batch: BatchReader<T1::Key,T1::Val,G::Timestamp,T1::R>+'static,
+    T1::Cursor: Cursor<T1::Key,T1::Val,G::Timestamp,T1::R>+'static,
+    T2: TraceReader<Time=G::Timestamp>+Clone+'static,
+    T2::Key: Eq+'static,
+    T2::Val: Ord+Clone+Debug+'static,
+    T2::R: Semigroup,
+    T2::Batch: BatchReader<T2::Key,T2::Val,G::Timestamp,T2::R>+'static,
+    T2::Cursor: Cursor<T2::Key,T2::Val,G::Timestamp,T2::R>+'static,
+    T1::R: Mul<T2::R>,
+    <T1::R as Mul<T2::R>>::Output: Semigroup,
+    M: Matcher<T1::Key,T1::Val,T2::Key,T2::Val>+'static,
+    I: IntoIterator,
+    I::Item: Data,
+    L: FnMut(&T1::Key,&T1::Val,&T2::Key,&T2::Val)->I+'static,
+{
+    // handles to shared trace data structures.
+    let mut trace1 = Some(arranged1.trace.clone());
+    let mut trace2 = Some(arranged2.trace.clone());
+
+    // acknowledged frontier for each input.
+    use timely::progress::frontier::Antichain;
+    let mut acknowledged1: Option<Antichain<G::Timestamp>> = None;
+    let mut acknowledged2: Option<Antichain<G::Timestamp>> = None;
+
+    // deferred work of batches from each input.
+    let mut todo1 = std::collections::VecDeque::new();
+    let mut todo2 = std::collections::VecDeque::new();
+
+    let mut input1_buffer = Vec::new();
+    let mut input2_buffer = Vec::new();
+
+    arranged1.stream.binary_frontier(&arranged2.stream, Pipeline, Pipeline, name, move |_cap, info| {
+
+        use timely::scheduling::Activator;
+        let activations = arranged1.stream.scope().activations().clone();
+        let activator = Activator::new(&info.address[..], activations);
+
+        let operator = info.global_id;
+        let logger = {
+            let scope = arranged1.stream.scope();
+            let register = scope.log_register();
+            register.get::<::logging::DifferentialEvent>("differential/arrange")
+        };
+
+        // whether outstanding work was last reported as non-zero.
+        let mut reported = false;
+
+        move |input1, input2, output| {
+
+            // The join computation repeatedly accepts batches of updates from each of its inputs.
+            //
+            // For each accepted batch, it prepares a work-item to join the batch against previously "accepted"
+            // updates from its other input. It is important to track which updates have been accepted, through
+            // a combination of the input's frontier and the most recently received batch's upper bound, because
+            // we use a shared trace and there may be updates present that are in advance of this accepted bound.
+
+            // drain input 1, prepare work.
+            input1.for_each(|capability, data| {
+                if let Some(ref mut trace2) = trace2 {
+                    let capability = capability.retain();
+                    data.swap(&mut input1_buffer);
+                    for batch1 in input1_buffer.drain(..) {
+                        if !batch1.is_empty() {
+                            if let Some(acknowledged2) = &acknowledged2 {
+                                // TODO : cursor_through may be problematic for pre-merged traces.
+                                // A trace should provide the contract that whatever its `distinguish_since` capability,
+                                // it is safe (and reasonable) to await delivery of batches up through that frontier.
+                                // In this case, we should be able to await (not block on) the arrival of these batches.
+                                let (trace2_cursor, trace2_storage) = trace2.cursor_through(acknowledged2.borrow()).unwrap();
+                                let batch1_cursor = batch1.cursor();
+                                todo1.push_back(Deferred::new(trace2_cursor, trace2_storage, batch1_cursor, batch1.clone(), batch1.len(), capability.clone(), |r2: &T2::R, r1: &T1::R| (r1.clone()) * (r2.clone())));
                             }
+                        }
 
-                            // It would be alarming (incorrect) to receieve a non-empty batch that does not advance the
-                            // acknowledged frontier, as each batch must be greater than previous batches, and the input.
-                            // Empty batches may be received as information races between frontier forwarding of the trace
-                            // and the empty batches themselves (which can be sent as part of trace importing).
-                            if acknowledged1.is_none() { acknowledged1 = Some(timely::progress::frontier::Antichain::from_elem(<G::Timestamp>::minimum())); }
-                            if let Some(acknowledged1) = &mut acknowledged1 {
-                                if !PartialOrder::less_equal(&*acknowledged1, batch1.upper()) {
-                                        if !batch1.is_empty() {
-                                        panic!("Non-empty batch1 upper not beyond acknowledged frontier: {:?}, {:?}", batch1.upper(), acknowledged1);
-                                    }
+                        // It would be alarming (incorrect) to receieve a non-empty batch that does not advance the
+                        // acknowledged frontier, as each batch must be greater than previous batches, and the input.
+                        // Empty batches may be received as information races between frontier forwarding of the trace
+                        // and the empty batches themselves (which can be sent as part of trace importing).
+                        if acknowledged1.is_none() { acknowledged1 = Some(timely::progress::frontier::Antichain::from_elem(<G::Timestamp>::minimum())); }
+                        if let Some(acknowledged1) = &mut acknowledged1 {
+                            if !PartialOrder::less_equal(&*acknowledged1, batch1.upper()) {
+                                    if !batch1.is_empty() {
+                                    panic!("Non-empty batch1 upper not beyond acknowledged frontier: {:?}, {:?}", batch1.upper(), acknowledged1);
                                 }
-                                acknowledged1.clone_from(batch1.upper());
                             }
+                            acknowledged1.clone_from(batch1.upper());
                         }
                     }
-                });
-
-                // drain input 2, prepare work.
-                input2.for_each(|capability, data| {
-                    if let Some(ref mut trace1) = trace1 {
-                        let capability = capability.retain();
-                        data.swap(&mut input2_buffer);
-                        for batch2 in input2_buffer.drain(..) {
-                            if !batch2.is_empty() {
-                                if let Some(acknowledged1) = &acknowledged1 {
-                                    // TODO : cursor_through may be problematic for pre-merged traces.
-                                    // A trace should provide the contract that whatever its `distinguish_since` capability,
-                                    // it is safe (and reasonable) to await delivery of batches up through that frontier.
-                                    // In this case, we should be able to await (not block on) the arrival of these batches.
-                                    let (trace1_cursor, trace1_storage) = trace1.cursor_through(acknowledged1.borrow()).unwrap();
-                                    let batch2_cursor = batch2.cursor();
-                                    todo2.push_back(Deferred::new(trace1_cursor, trace1_storage, batch2_cursor, batch2.clone(), capability.clone(), |r1,r2| (r1.clone()) * (r2.clone())));
-                                }
+                }
+            });
+
+            // drain input 2, prepare work.
+            input2.for_each(|capability, data| {
+                if let Some(ref mut trace1) = trace1 {
+                    let capability = capability.retain();
+                    data.swap(&mut input2_buffer);
+                    for batch2 in input2_buffer.drain(..) {
+                        if !batch2.is_empty() {
+                            if let Some(acknowledged1) = &acknowledged1 {
+                                // TODO : cursor_through may be problematic for pre-merged traces.
+                                // A trace should provide the contract that whatever its `distinguish_since` capability,
+                                // it is safe (and reasonable) to await delivery of batches up through that frontier.
+                                // In this case, we should be able to await (not block on) the arrival of these batches.
+                                let (trace1_cursor, trace1_storage) = trace1.cursor_through(acknowledged1.borrow()).unwrap();
+                                let batch2_cursor = batch2.cursor();
+                                todo2.push_back(Deferred::new(trace1_cursor, trace1_storage, batch2_cursor, batch2.clone(), batch2.len(), capability.clone(), |r1: &T1::R, r2: &T2::R| (r1.clone()) * (r2.clone())));
                             }
-                            // It would be alarming (incorrect) to receieve a non-empty batch that does not advance the
-                            // acknowledged frontier, as each batch must be greater than previous batches, and the input.
-                            // Empty batches may be received as information races between frontier forwarding of the trace
-                            // and the empty batches themselves (which can be sent as part of trace importing).
-                            if acknowledged2.is_none() { acknowledged2 = Some(timely::progress::frontier::Antichain::from_elem(<G::Timestamp>::minimum())); }
-                            if let Some(acknowledged2) = &mut acknowledged2 {
-                                if !PartialOrder::less_equal(&*acknowledged2, batch2.upper()) {
-                                    if !batch2.is_empty() {
-                                        panic!("Non-empty batch2 upper not beyond acknowledged frontier: {:?}, {:?}", batch2.upper(), acknowledged2);
-                                    }
+                        }
+                        // It would be alarming (incorrect) to receieve a non-empty batch that does not advance the
+                        // acknowledged frontier, as each batch must be greater than previous batches, and the input.
+                        // Empty batches may be received as information races between frontier forwarding of the trace
+                        // and the empty batches themselves (which can be sent as part of trace importing).
+                        if acknowledged2.is_none() { acknowledged2 = Some(timely::progress::frontier::Antichain::from_elem(<G::Timestamp>::minimum())); }
+                        if let Some(acknowledged2) = &mut acknowledged2 {
+                            if !PartialOrder::less_equal(&*acknowledged2, batch2.upper()) {
+                                if !batch2.is_empty() {
+                                    panic!("Non-empty batch2 upper not beyond acknowledged frontier: {:?}, {:?}", batch2.upper(), acknowledged2);
                                 }
-                                acknowledged2.clone_from(batch2.upper());
                             }
+                            acknowledged2.clone_from(batch2.upper());
                         }
                     }
-                });
-
-                // For each of the inputs, we do some amount of work (measured in terms of number
-                // of output records produced). This is meant to yield control to allow downstream
-                // operators to consume and reduce the output, but it it also means to provide some
-                // degree of responsiveness. There is a potential risk here that if we fall behind
-                // then the increasing queues hold back physical compaction of the underlying traces
-                // which results in unintentionally quadratic processing time (each batch of either
-                // input must scan all batches from the other input).
-
-                // perform some amount of outstanding work.
-                let mut fuel = 1_000_000;
-                while !todo1.is_empty() && fuel > 0 {
-                    todo1.front_mut().unwrap().work(output, &mut |k,v2,v1| result(k,v1,v2), &mut fuel);
-                    if !todo1.front().unwrap().work_remains() { todo1.pop_front(); }
                 }
+            });
+
+            // For each of the inputs, we do some amount of work (measured in terms of number
+            // of output records produced). This is meant to yield control to allow downstream
+            // operators to consume and reduce the output, but it it also means to provide some
+            // degree of responsiveness. There is a potential risk here that if we fall behind
+            // then the increasing queues hold back physical compaction of the underlying traces
+            // which results in unintentionally quadratic processing time (each batch of either
+            // input must scan all batches from the other input).
 
-                // perform some amount of outstanding work.
-                let mut fuel = 1_000_000;
-                while !todo2.is_empty() && fuel > 0 {
-                    todo2.front_mut().unwrap().work(output, &mut |k,v1,v2| result(k,v1,v2), &mut fuel);
-                    if !todo2.front().unwrap().work_remains() { todo2.pop_front(); }
+            // perform some amount of outstanding work.
+            let started = ::std::time::Instant::now();
+            let deadline = config.yield_policy.deadline(started);
+            let mut fuel = config.fuel;
+            while !todo1.is_empty() && fuel > 0 {
+                let index = config.order.select(todo1.iter().map(|deferred| (deferred.size, deferred.passed)));
+                for (position, deferred) in todo1.iter_mut().enumerate() {
+                    if position != index { deferred.passed += 1; }
                 }
+                todo1[index].work(output, &Flip(&matcher), &mut |k2,v2,k1,v1| result(k1,v1,k2,v2), &mut fuel, deadline);
+                let completed = !todo1[index].work_remains();
+                if completed { todo1.remove(index); }
+                if config.yield_policy.should_yield(started, completed) { break; }
+            }
+            let mut effort = config.fuel - fuel;
 
-                // Re-activate operator if work remains.
-                if !todo1.is_empty() || !todo2.is_empty() {
-                    activator.activate();
+            // perform some amount of outstanding work.
+            let started = ::std::time::Instant::now();
+            let deadline = config.yield_policy.deadline(started);
+            let mut fuel = config.fuel;
+            while !todo2.is_empty() && fuel > 0 {
+                let index = config.order.select(todo2.iter().map(|deferred| (deferred.size, deferred.passed)));
+                for (position, deferred) in todo2.iter_mut().enumerate() {
+                    if position != index { deferred.passed += 1; }
                 }
+                todo2[index].work(output, &matcher, &mut |k1,v1,k2,v2| result(k1,v1,k2,v2), &mut fuel, deadline);
+                let completed = !todo2[index].work_remains();
+                if completed { todo2.remove(index); }
+                if config.yield_policy.should_yield(started, completed) { break; }
+            }
+            effort += config.fuel - fuel;
 
-                // shut down or advance trace2.
-                if trace2.is_some() && input1.frontier().is_empty() { trace2 = None; }
-                if let Some(ref mut trace2) = trace2 {
-                    trace2.advance_by(input1.frontier().frontier());
-                    // At this point, if we haven't seen any input batches we should establish a frontier anyhow.
-                    if acknowledged2.is_none() {
-                        acknowledged2 = Some(Antichain::from_elem(<G::Timestamp>::minimum()));
-                    }
-                    if let Some(acknowledged2) = &mut acknowledged2 {
-                        trace2.advance_upper(acknowledged2);
-                        trace2.distinguish_since(acknowledged2.borrow());
-                    }
+            // Report outstanding work, including once when none remains.
+            if let Some(logger) = &logger {
+                let batches = todo1.len() + todo2.len();
+                if batches > 0 || reported {
+                    let updates = todo1.iter().map(|deferred| deferred.size).sum::<usize>()
+                                + todo2.iter().map(|deferred| deferred.size).sum::<usize>();
+                    logger.log(::logging::JoinWork {
+                        operator,
+                        effort,
+                        batches,
+                        updates,
+                    });
                 }
+                reported = batches > 0;
+            }
 
-                // shut down or advance trace1.
-                if trace1.is_some() && input2.frontier().is_empty() { trace1 = None; }
-                if let Some(ref mut trace1) = trace1 {
-                    trace1.advance_by(input2.frontier().frontier());
-                    // At this point, if we haven't seen any input batches we should establish a frontier anyhow.
-                    if acknowledged1.is_none() {
-                        acknowledged1 = Some(Antichain::from_elem(<G::Timestamp>::minimum()));
-                    }
-                    if let Some(acknowledged1) = &mut acknowledged1 {
-                        trace1.advance_upper(acknowledged1);
-                        trace1.distinguish_since(acknowledged1.borrow());
-                    }
+            // Re-activate operator if work remains.
+            if !todo1.is_empty() || !todo2.is_empty() {
+                activator.activate();
+            }
+
+            // shut down or advance trace2.
+            if trace2.is_some() && input1.frontier().is_empty() { trace2 = None; }
+            if let Some(ref mut trace2) = trace2 {
+                trace2.advance_by(input1.frontier().frontier());
+                // At this point, if we haven't seen any input batches we should establish a frontier anyhow.
+                if acknowledged2.is_none() {
+                    acknowledged2 = Some(Antichain::from_elem(<G::Timestamp>::minimum()));
+                }
+                if let Some(acknowledged2) = &mut acknowledged2 {
+                    trace2.advance_upper(acknowledged2);
+                    trace2.distinguish_since(acknowledged2.borrow());
                 }
             }
-        })
-        .as_collection()
+
+            // shut down or advance trace1.
+            if trace1.is_some() && input2.frontier().is_empty() { trace1 = None; }
+            if let Some(ref mut trace1) = trace1 {
+                trace1.advance_by(input2.frontier().frontier());
+                // At this point, if we haven't seen any input batches we should establish a frontier anyhow.
+                if acknowledged1.is_none() {
+                    acknowledged1 = Some(Antichain::from_elem(<G::Timestamp>::minimum()));
+                }
+                if let Some(acknowledged1) = &mut acknowledged1 {
+                    trace1.advance_upper(acknowledged1);
+                    trace1.distinguish_since(acknowledged1.borrow());
+                }
+            }
+        }
+    })
+    .as_collection()
+}
+
+/// Determines which records of the two inputs of a join match.
+///
+/// Keys are compared by `compare`, which must be monotone: as `key2` increases, `compare(key1, key2)` may
+/// only change from `Greater` to `Equal` to `Less`, and as `key1` increases it may only change the other way.
+/// The keys of each input matching a key of the other are then contiguous, and are found by seeking.
+/// The values of matched keys are matched all with all, unless `think` is overridden.
+pub(super) trait Matcher<K1, V1, K2, V2> {
+    /// Compares a key of the first input with a key of the second.
+    ///
+    /// The result is `Equal` if the keys match, `Less` if `key1` precedes the keys matching `key2`, and
+    /// `Greater` if `key1` follows them.
+    fn compare(&self, key1: &K1, key2: &K2) -> Ordering;
+    /// Indicates whether distinct keys match disjoint sets of keys, so that cursors need never revisit keys.
+    fn disjoint(&self) -> bool;
+    /// Advances a cursor of the first input to the first key that does not precede the keys matching `key2`.
+    ///
+    /// The default implementation seeks by `compare`, which cursors can only do by galloping. Matchers
+    /// that compare keys by equality should seek the key itself, which some cursors find more directly.
+    fn seek1<T, R, C: Cursor<K1, V1, T, R>>(&self, cursor: &mut C, storage: &C::Storage, key2: &K2) {
+        cursor.seek_key_with(storage, |key1| self.compare(key1, key2) == Ordering::Less);
+    }
+    /// Advances a cursor of the second input to the first key that does not precede the keys matching `key1`.
+    fn seek2<T, R, C: Cursor<K2, V2, T, R>>(&self, cursor: &mut C, storage: &C::Storage, key1: &K1) {
+        cursor.seek_key_with(storage, |key2| self.compare(key1, key2) == Ordering::Greater);
+    }
+    /// Reports the matching pairs of edits to the values of two matched keys.
+    fn think<'a, 'b, T, R1, R2, F>(&self, history1: &mut ValueHistory<'a, V1, T, R1>, history2: &mut ValueHistory<'b, V2, T, R2>, results: F)
+    where V1: Ord+Clone+Debug, V2: Ord+Clone+Debug, T: Lattice+Ord+Clone+Debug, R1: Semigroup, R2: Semigroup, F: FnMut(&V1,&V2,T,&R1,&R2) {
+        think(history1, history2, results)
+    }
+}
+
+/// Matches keys by equality, as `join_core` does.
+struct EqualKeys;
+
+impl<K: Ord, V1, V2> Matcher<K, V1, K, V2> for EqualKeys {
+    #[inline] fn compare(&self, key1: &K, key2: &K) -> Ordering { key1.cmp(key2) }
+    #[inline] fn disjoint(&self) -> bool { true }
+    #[inline] fn seek1<T, R, C: Cursor<K, V1, T, R>>(&self, cursor: &mut C, storage: &C::Storage, key2: &K) { cursor.seek_key(storage, key2); }
+    #[inline] fn seek2<T, R, C: Cursor<K, V2, T, R>>(&self, cursor: &mut C, storage: &C::Storage, key1: &K) { cursor.seek_key(storage, key1); }
+}
+
+/// A matcher with the roles of its inputs exchanged, for the batches of the first input.
+struct Flip<'a, M: 'a>(&'a M);
+
+impl<'a, K1, V1, K2, V2, M: Matcher<K1, V1, K2, V2>> Matcher<K2, V2, K1, V1> for Flip<'a, M> {
+    #[inline] fn compare(&self, key2: &K2, key1: &K1) -> Ordering { self.0.compare(key1, key2).reverse() }
+    #[inline] fn disjoint(&self) -> bool { self.0.disjoint() }
+    #[inline] fn seek1<T, R, C: Cursor<K2, V2, T, R>>(&self, cursor: &mut C, storage: &C::Storage, key1: &K1) { self.0.seek2(cursor, storage, key1); }
+    #[inline] fn seek2<T, R, C: Cursor<K1, V1, T, R>>(&self, cursor: &mut C, storage: &C::Storage, key2: &K2) { self.0.seek1(cursor, storage, key2); }
+    fn think<'b, 'c, T, R2, R1, F>(&self, history2: &mut ValueHistory<'b, V2, T, R2>, history1: &mut ValueHistory<'c, V1, T, R1>, mut results: F)
+    where V2: Ord+Clone+Debug, V1: Ord+Clone+Debug, T: Lattice+Ord+Clone+Debug, R2: Semigroup, R1: Semigroup, F: FnMut(&V2,&V1,T,&R2,&R1) {
+        self.0.think(history1, history2, |v1, v2, t, r1, r2| results(v2, v1, t, r2, r1))
     }
 }
 
+/// Matches two arrangements, and reports the unmatched records of the first and second if `left` and `right`.
+///
+/// Unmatched records are the records of an input less those whose keys are present in the other input, where
+/// the present keys are determined by a reduction of that input's arrangement.
+fn outer_join<G, K, V1, V2, R, T1, T2>(arranged1: &Arranged<G,T1>, arranged2: &Arranged<G,T2>, left: bool, right: bool) -> Collection<G,(K,Option<V1>,Option<V2>),R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord+Debug,
+    K: Data,
+    V1: Data,
+    V2: Data,
+    R: Abelian+Mul<R, Output=R>+From<i8>,
+    T1: TraceReader<Key=K, Val=V1, Time=G::Timestamp, R=R>+Clone+'static,
+    T1::Batch: BatchReader<K, V1, G::Timestamp, R>+'static,
+    T1::Cursor: Cursor<K, V1, G::Timestamp, R>+'static,
+    T2: TraceReader<Key=K, Val=V2, Time=G::Timestamp, R=R>+Clone+'static,
+    T2::Batch: BatchReader<K, V2, G::Timestamp, R>+'static,
+    T2::Cursor: Cursor<K, V2, G::Timestamp, R>+'static,
+{
+    let mut result = arranged1.join_core(arranged2, |k,v1,v2| Some((k.clone(), Some(v1.clone()), Some(v2.clone()))));
+
+    if left {
+        let keys2 = arranged2.reduce_abelian::<_,DefaultKeyTrace<K,G::Timestamp,R>>("OuterJoinKeys", |_k,_s,t| t.push(((), R::from(1))));
+        let matched = arranged1.join_core(&keys2, |k,v1,_| Some((k.clone(), Some(v1.clone()), None::<V2>)));
+        result = result.concat(&arranged1.as_collection(|k,v1| (k.clone(), Some(v1.clone()), None)))
+                       .concat(&matched.negate());
+    }
+
+    if right {
+        let keys1 = arranged1.reduce_abelian::<_,DefaultKeyTrace<K,G::Timestamp,R>>("OuterJoinKeys", |_k,_s,t| t.push(((), R::from(1))));
+        let matched = arranged2.join_core(&keys1, |k,v2,_| Some((k.clone(), None::<V1>, Some(v2.clone()))));
+        result = result.concat(&arranged2.as_collection(|k,v2| (k.clone(), None, Some(v2.clone()))))
+                       .concat(&matched.negate());
+    }
+
+    result
+}
+
 /// Deferred join computation.
 ///
 /// The structure wraps cursors which allow us to play out join computation at whatever rate we like.
 /// This allows us to avoid producing and buffering massive amounts of data, without giving the timely
 /// dataflow system a chance to run operators that can consume and aggregate the data.
-struct Deferred<K, V1, V2, T, R1, R2, R3, C1, C2, M, D>
+struct Deferred<K1, V1, K2, V2, T, R1, R2, R3, C1, C2, M, D>
 where
     V1: Ord+Clone,
     V2: Ord+Clone,
     T: Timestamp+Lattice+Ord+Debug,
     R1: Semigroup,
     R2: Semigroup,
-    C1: Cursor<K, V1, T, R1>,
-    C2: Cursor<K, V2, T, R2>,
+    C1: Cursor<K1, V1, T, R1>,
+    C2: Cursor<K2, V2, T, R2>,
     M: FnMut(&R1,&R2)->R3,
     D: Ord+Clone+Data,
 {
-    phant: ::std::marker::PhantomData<(K, V1, V2, R1, R2)>,
+    phant: ::std::marker::PhantomData<(K1, V1, K2, V2, R1, R2)>,
     trace: C1,
     trace_storage: C1::Storage,
     batch: C2,
     batch_storage: C2::Storage,
+    /// The number of updates in the batch.
+    size: usize,
+    /// The number of times other batches were worked on ahead of this one.
+    passed: usize,
     capability: Capability<T>,
     mult: M,
     done: bool,
     temp: Vec<((D, T), R3)>,
-    // thinker: JoinThinker<V1, V2, T, R1, R2>,
 }
 
-impl<K, V1, V2, T, R1, R2, R3, C1, C2, M, D> Deferred<K, V1, V2, T, R1, R2, R3, C1, C2, M, D>
+impl<K1, V1, K2, V2, T, R1, R2, R3, C1, C2, M, D> Deferred<K1, V1, K2, V2, T, R1, R2, R3, C1, C2, M, D>
 where
-    K: Ord+Debug+Eq,
+    K1: Eq,
     V1: Ord+Clone+Debug,
+    K2: Eq,
     V2: Ord+Clone+Debug,
     T: Timestamp+Lattice+Ord+Debug,
     R1: Semigroup,
     R2: Semigroup,
     R3: Semigroup,
-    C1: Cursor<K, V1, T, R1>,
-    C2: Cursor<K, V2, T, R2>,
+    C1: Cursor<K1, V1, T, R1>,
+    C2: Cursor<K2, V2, T, R2>,
     M: FnMut(&R1,&R2)->R3,
     D: Ord+Clone+Data,
 {
-    fn new(trace: C1, trace_storage: C1::Storage, batch: C2, batch_storage: C2::Storage, capability: Capability<T>, mult: M) -> Self {
+    fn new(trace: C1, trace_storage: C1::Storage, batch: C2, batch_storage: C2::Storage, size: usize, capability: Capability<T>, mult: M) -> Self {
         Deferred {
             phant: ::std::marker::PhantomData,
             trace,
             trace_storage,
             batch,
             batch_storage,
+            size,
+            passed: 0,
             capability,
             mult,
             done: false,
             temp: Vec::new(),
-            // thinker: JoinThinker::new(),
         }
     }
 
@@ -540,10 +1153,10 @@ where
         !self.done
     }
 
-    /// Process keys until at least `limit` output tuples produced, or the work is exhausted.
+    /// Process batch keys until at least `limit` output tuples produced, `deadline` passes, or the work is exhausted.
     #[inline(never)]
-    fn work<L, I>(&mut self, output: &mut OutputHandle<T, (D, T, R3), Tee<T, (D, T, R3)>>, logic: &mut L, fuel: &mut usize)
-    where I: IntoIterator<Item=D>, L: FnMut(&K, &V1, &V2)->I {
+    fn work<MA, L, I>(&mut self, output: &mut OutputHandle<T, (D, T, R3), Tee<T, (D, T, R3)>>, matcher: &MA, logic: &mut L, fuel: &mut usize, deadline: Option<::std::time::Instant>)
+    where MA: Matcher<K1, V1, K2, V2>, I: IntoIterator<Item=D>, L: FnMut(&K1, &V1, &K2, &V2)->I {
 
         let meet = self.capability.time();
 
@@ -558,113 +1171,107 @@ where
         let mult = &mut self.mult;
 
         let temp = &mut self.temp;
-        // let thinker = &mut self.thinker;
-        let mut thinker = JoinThinker::new();
+        let mut history1 = ValueHistory::new();
+        let mut history2 = ValueHistory::new();
+
+        // Whether no key of the trace matches the current or any later key of the batch.
+        let mut exhausted = false;
+
+        while batch.key_valid(batch_storage) && effort < *fuel {
+
+            let key2 = batch.key(batch_storage);
+            if !matcher.disjoint() { trace.rewind_keys(trace_storage); }
+            matcher.seek1(trace, trace_storage, key2);
+
+            match trace.get_key(trace_storage) {
+                None => {
+                    exhausted = true;
+                    break;
+                },
+                Some(key1) if matcher.compare(key1, key2) == Ordering::Greater => {
+                    // No key of the trace matches `key2`, or any later key of the batch before those `key1` matches.
+                    matcher.seek2(batch, batch_storage, key1);
+                },
+                Some(_) => {
+
+                    history2.load(batch, batch_storage, |time| time.clone());
 
-        while batch.key_valid(batch_storage) && trace.key_valid(trace_storage) && effort < *fuel {
+                    while trace.get_key(trace_storage).map(|key1| matcher.compare(key1, key2) == Ordering::Equal).unwrap_or(false) {
 
-            match trace.key(trace_storage).cmp(batch.key(batch_storage)) {
-                Ordering::Less => trace.seek_key(trace_storage, batch.key(batch_storage)),
-                Ordering::Greater => batch.seek_key(batch_storage, trace.key(trace_storage)),
-                Ordering::Equal => {
+                        let key1 = trace.key(trace_storage);
+                        history1.load(trace, trace_storage, |time| time.join(&meet));
 
-                    thinker.history1.edits.load(trace, trace_storage, |time| time.join(&meet));
-                    thinker.history2.edits.load(batch, batch_storage, |time| time.clone());
+                        assert_eq!(temp.len(), 0);
 
-                    assert_eq!(temp.len(), 0);
+                        // populate `temp` with the results in the best way we know how.
+                        matcher.think(&mut history1, &mut history2, |v1,v2,t,r1,r2|
+                            for result in logic(key1, v1, key2, v2) {
+                                temp.push(((result, t.clone()), mult(r1, r2)));
+                            }
+                        );
+
+                        // TODO: This consolidation is optional, and it may not be very
+                        //       helpful. We might try harder to understand whether we
+                        //       should do this work here, or downstream at consumers.
+                        crate::consolidation::consolidate(temp);
 
-                    // populate `temp` with the results in the best way we know how.
-                    thinker.think(|v1,v2,t,r1,r2|
-                        for result in logic(batch.key(batch_storage), v1, v2) {
-                            temp.push(((result, t.clone()), mult(r1, r2)));
+                        effort += temp.len();
+                        for ((d, t), r) in temp.drain(..) {
+                            session.give((d, t, r));
                         }
-                    );
-
-                    // TODO: This consolidation is optional, and it may not be very
-                    //       helpful. We might try harder to understand whether we
-                    //       should do this work here, or downstream at consumers.
-                    // TODO: Perhaps `thinker` should have the buffer, do smarter
-                    //       consolidation, and then deposit results in `session`.
-                    crate::consolidation::consolidate(temp);
-
-                    effort += temp.len();
-                    for ((d, t), r) in temp.drain(..) {
-                        session.give((d, t, r));
+
+                        trace.step_key(trace_storage);
+                        history1.clear();
                     }
 
                     batch.step_key(batch_storage);
-                    trace.step_key(trace_storage);
+                    history2.clear();
 
-                    thinker.history1.clear();
-                    thinker.history2.clear();
-                }
+                    // Checked after each matched key, so that every call makes progress.
+                    if deadline.map(|deadline| ::std::time::Instant::now() >= deadline).unwrap_or(false) { break; }
+                },
             }
         }
 
-        self.done = !batch.key_valid(batch_storage) || !trace.key_valid(trace_storage);
+        self.done = exhausted || !batch.key_valid(batch_storage);
 
         if effort > *fuel { *fuel = 0; }
         else              { *fuel -= effort; }
     }
 }
 
-struct JoinThinker<'a, V1: Ord+Clone+'a, V2: Ord+Clone+'a, T: Lattice+Ord+Clone, R1: Semigroup, R2: Semigroup> {
-    pub history1: ValueHistory<'a, V1, T, R1>,
-    pub history2: ValueHistory<'a, V2, T, R2>,
-}
-
-impl<'a, V1: Ord+Clone, V2: Ord+Clone, T: Lattice+Ord+Clone, R1: Semigroup, R2: Semigroup> JoinThinker<'a, V1, V2, T, R1, R2>
-where V1: Debug, V2: Debug, T: Debug
+/// Reports each pair of edits to the values of two matched keys.
+fn think<'a, 'b, V1, V2, T, R1, R2, F>(history1: &mut ValueHistory<'a, V1, T, R1>, history2: &mut ValueHistory<'b, V2, T, R2>, mut results: F)
+where
+    V1: Ord+Clone+Debug,
+    V2: Ord+Clone+Debug,
+    T: Lattice+Ord+Clone+Debug,
+    R1: Semigroup,
+    R2: Semigroup,
+    F: FnMut(&V1,&V2,T,&R1,&R2),
 {
-    fn new() -> Self {
-        JoinThinker {
-            history1: ValueHistory::new(),
-            history2: ValueHistory::new(),
-        }
-    }
-
-    fn think<F: FnMut(&V1,&V2,T,&R1,&R2)>(&mut self, mut results: F) {
-
-        // for reasonably sized edits, do the dead-simple thing.
-        if self.history1.edits.len() < 10 || self.history2.edits.len() < 10 {
-            self.history1.edits.map(|v1, t1, d1| {
-                self.history2.edits.map(|v2, t2, d2| {
-                    results(v1, v2, t1.join(t2), &d1, &d2);
-                })
+    // for reasonably sized edits, do the dead-simple thing.
+    if history1.edits.len() < 10 || history2.edits.len() < 10 {
+        history1.edits.map(|v1, t1, d1| {
+            history2.edits.map(|v2, t2, d2| {
+                results(v1, v2, t1.join(t2), &d1, &d2);
             })
-        }
-        else {
+        })
+    }
+    else {
 
-            let mut replay1 = self.history1.replay();
-            let mut replay2 = self.history2.replay();
+        let mut replay1 = history1.replay();
+        let mut replay2 = history2.replay();
 
-            // TODO: It seems like there is probably a good deal of redundant `advance_buffer_by`
-            //       in here. If a time is ever repeated, for example, the call will be identical
-            //       and accomplish nothing. If only a single record has been added, it may not
-            //       be worth the time to collapse (advance, re-sort) the data when a linear scan
-            //       is sufficient.
+        // TODO: It seems like there is probably a good deal of redundant `advance_buffer_by`
+        //       in here. If a time is ever repeated, for example, the call will be identical
+        //       and accomplish nothing. If only a single record has been added, it may not
+        //       be worth the time to collapse (advance, re-sort) the data when a linear scan
+        //       is sufficient.
 
-            while !replay1.is_done() && !replay2.is_done() {
+        while !replay1.is_done() && !replay2.is_done() {
 
-                if replay1.time().unwrap().cmp(&replay2.time().unwrap()) == ::std::cmp::Ordering::Less {
-                    replay2.advance_buffer_by(replay1.meet().unwrap());
-                    for &((ref val2, ref time2), ref diff2) in replay2.buffer().iter() {
-                        let (val1, time1, ref diff1) = replay1.edit().unwrap();
-                        results(val1, val2, time1.join(time2), diff1, diff2);
-                    }
-                    replay1.step();
-                }
-                else {
-                    replay1.advance_buffer_by(replay2.meet().unwrap());
-                    for &((ref val1, ref time1), ref diff1) in replay1.buffer().iter() {
-                        let (val2, time2, ref diff2) = replay2.edit().unwrap();
-                        results(val1, val2, time1.join(time2), diff1, diff2);
-                    }
-                    replay2.step();
-                }
-            }
-
-            while !replay1.is_done() {
+            if replay1.time().unwrap().cmp(&replay2.time().unwrap()) == ::std::cmp::Ordering::Less {
                 replay2.advance_buffer_by(replay1.meet().unwrap());
                 for &((ref val2, ref time2), ref diff2) in replay2.buffer().iter() {
                     let (val1, time1, ref diff1) = replay1.edit().unwrap();
@@ -672,7 +1279,7 @@ where V1: Debug, V2: Debug, T: Debug
                 }
                 replay1.step();
             }
-            while !replay2.is_done() {
+            else {
                 replay1.advance_buffer_by(replay2.meet().unwrap());
                 for &((ref val1, ref time1), ref diff1) in replay1.buffer().iter() {
                     let (val2, time2, ref diff2) = replay2.edit().unwrap();
@@ -681,5 +1288,22 @@ where V1: Debug, V2: Debug, T: Debug
                 replay2.step();
             }
         }
+
+        while !replay1.is_done() {
+            replay2.advance_buffer_by(replay1.meet().unwrap());
+            for &((ref val2, ref time2), ref diff2) in replay2.buffer().iter() {
+                let (val1, time1, ref diff1) = replay1.edit().unwrap();
+                results(val1, val2, time1.join(time2), diff1, diff2);
+            }
+            replay1.step();
+        }
+        while !replay2.is_done() {
+            replay1.advance_buffer_by(replay2.meet().unwrap());
+            for &((ref val1, ref time1), ref diff1) in replay1.buffer().iter() {
+                let (val2, time2, ref diff2) = replay2.edit().unwrap();
+                results(val1, val2, time1.join(time2), diff1, diff2);
+            }
+            replay2.step();
+        }
     }
 }
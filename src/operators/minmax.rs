@@ -0,0 +1,99 @@
+//! Maintains the least and greatest values of each key.
+//!
+//! These operators are top-k computations with a limit of one, and so reduce each key through the
+//! hierarchy of buckets that `TopK` uses. Each bucket retains a single value, and the retraction of a
+//! value re-examines only the buckets on its path, rather than the whole group.
+
+use std::hash::Hash;
+
+use timely::dataflow::Scope;
+
+use ::{ExchangeData, Collection};
+use lattice::Lattice;
+use operators::TopK;
+
+/// Extension trait for the `min`, `max`, `argmin`, and `argmax` differential dataflow methods.
+pub trait MinMax<G: Scope, K: ExchangeData, V: ExchangeData> where G::Timestamp: Lattice+Ord {
+    /// Reports the least value of each key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::minmax::MinMax;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         scope.new_collection_from(1 .. 10).1
+    ///              .map(|x| (x % 3, x))
+    ///              .min()
+    ///              .assert_eq(&scope.new_collection_from(vec![(0, 3), (1, 1), (2, 2)]).1);
+    ///     });
+    /// }
+    /// ```
+    fn min(&self) -> Collection<G, (K, V), isize>;
+
+    /// Reports the greatest value of each key.
+    fn max(&self) -> Collection<G, (K, V), isize>;
+
+    /// Reports a value of each key for which `measure` is least.
+    ///
+    /// Among values with the same least measure, the least value is reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::minmax::MinMax;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // report the identifier with the least score
+    ///         scope.new_collection_from(vec![(10, 3), (11, 1), (12, 2)]).1
+    ///              .map(|pair| ((), pair))
+    ///              .argmin(|&(_id, score)| score)
+    ///              .assert_eq(&scope.new_collection_from(vec![((), (11, 1))]).1);
+    ///     });
+    /// }
+    /// ```
+    fn argmin<O, F>(&self, measure: F) -> Collection<G, (K, V), isize>
+    where O: Ord, F: Fn(&V)->O+'static;
+
+    /// Reports a value of each key for which `measure` is greatest.
+    ///
+    /// Among values with the same greatest measure, the least value is reported.
+    fn argmax<O, F>(&self, measure: F) -> Collection<G, (K, V), isize>
+    where O: Ord, F: Fn(&V)->O+'static;
+}
+
+impl<G, K, V> MinMax<G, K, V> for Collection<G, (K, V), isize>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hash,
+    V: ExchangeData+Hash,
+{
+    fn min(&self) -> Collection<G, (K, V), isize> {
+        self.top_k_by(0, 1, |x: &V, y: &V| x.cmp(y))
+    }
+
+    fn max(&self) -> Collection<G, (K, V), isize> {
+        self.top_k_by(0, 1, |x: &V, y: &V| y.cmp(x))
+    }
+
+    fn argmin<O, F>(&self, measure: F) -> Collection<G, (K, V), isize>
+    where O: Ord, F: Fn(&V)->O+'static {
+        self.top_k_by(0, 1, move |x: &V, y: &V| measure(x).cmp(&measure(y)))
+    }
+
+    fn argmax<O, F>(&self, measure: F) -> Collection<G, (K, V), isize>
+    where O: Ord, F: Fn(&V)->O+'static {
+        self.top_k_by(0, 1, move |x: &V, y: &V| measure(y).cmp(&measure(x)))
+    }
+}
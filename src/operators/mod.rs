@@ -10,14 +10,24 @@ pub use self::iterate::Iterate;
 pub use self::join::{Join, JoinCore};
 pub use self::count::CountTotal;
 pub use self::threshold::ThresholdTotal;
+pub use self::topk::TopK;
+pub use self::minmax::MinMax;
+pub use self::aggregate::Aggregate;
 
 pub mod arrange;
 pub mod reduce;
 pub mod consolidate;
 pub mod iterate;
 pub mod join;
+pub mod asof;
+pub mod band;
+pub mod skew;
 pub mod count;
+pub mod aggregate;
 pub mod threshold;
+pub mod topk;
+pub mod minmax;
+pub mod partitioned;
 
 use ::difference::Semigroup;
 use lattice::Lattice;
@@ -70,6 +80,13 @@ impl<'a, V:'a, T, R> EditList<'a, V, T, R> where T: Ord+Clone, R: Semigroup {
             self.values.push((value, self.edits.len()));
         }
     }
+    /// The values with edits, in order, each with its edits.
+    fn value_edits<'b>(&'b self) -> impl Iterator<Item=(&'a V, &'b [(T, R)])>+'b {
+        (0 .. self.values.len()).map(move |index| {
+            let lower = if index == 0 { 0 } else { self.values[index-1].1 };
+            (self.values[index].0, &self.edits[lower .. self.values[index].1])
+        })
+    }
     fn map<F: FnMut(&V, &T, R)>(&self, mut logic: F) {
         for index in 0 .. self.values.len() {
             let lower = if index == 0 { 0 } else { self.values[index-1].1 };
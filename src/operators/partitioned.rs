@@ -0,0 +1,214 @@
+//! Collections whose records are already partitioned among workers by key.
+//!
+//! Operators that group records by key, such as `arrange`, `consolidate`, and `reduce`, first exchange
+//! their inputs so that each key resides at the worker its hash selects. When a collection is known to be
+//! partitioned this way already, for example because it is the output of a `reduce` or was produced from
+//! an arrangement, this exchange moves no records between workers but still costs a pass over the data.
+//!
+//! A `Partitioned` collection records that its `(key, val)` records reside at the workers selected by the
+//! hashes of their keys, and its implementations of `Arrange`, `Consolidate`, and `Reduce` skip the exchange.
+//! Partitioning is preserved by operators that leave keys in place, which `Partitioned` provides directly.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate timely;
+//! extern crate differential_dataflow;
+//!
+//! use differential_dataflow::input::Input;
+//! use differential_dataflow::operators::Reduce;
+//! use differential_dataflow::operators::partitioned::{Partitioned, PartitionByKey};
+//!
+//! fn main() {
+//!     ::timely::example(|scope| {
+//!
+//!         let counts = scope.new_collection_from(1 .. 10u32).1
+//!                           .map(|x| (x % 3, x))
+//!                           .partition_by_key()
+//!                           .reduce(|_key, input, output| output.push((input.len(), 1)));
+//!
+//!         // The output of `reduce` is partitioned by its keys, and so need not be exchanged again.
+//!         Partitioned::new_unchecked(counts)
+//!             .reduce(|_key, input, output| output.push((*input[0].0, 1)))
+//!             .assert_eq(&scope.new_collection_from(vec![(0, 3), (1, 3), (2, 3)]).1);
+//!     });
+//! }
+//! ```
+
+use timely::dataflow::Scope;
+use timely::dataflow::operators::Exchange;
+use timely::dataflow::channels::pact::{ParallelizationContract, Pipeline};
+use timely_sort::Unsigned;
+
+use hashable::Hashable;
+use ::{Data, ExchangeData, Collection, AsCollection};
+use ::difference::{Semigroup, Abelian};
+use lattice::Lattice;
+use operators::arrange::{Arranged, Arrange, ArrangeByKey, ArrangeBySelf, TraceAgent};
+use operators::{Consolidate, Reduce};
+use trace::{Trace, TraceReader, Batch, Cursor};
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+use trace::implementations::ord::OrdKeySpine as DefaultKeyTrace;
+
+/// A collection of `(key, val)` records, each at the worker selected by the hash of its key.
+///
+/// The records are placed as `arrange` would place them, and operators that group records by key
+/// may use them in place rather than exchanging them.
+pub struct Partitioned<G: Scope, K, V, R: Semigroup = isize> {
+    /// The underlying collection, whose records are partitioned by key.
+    pub collection: Collection<G, (K, V), R>,
+}
+
+impl<G: Scope, K, V, R: Semigroup> Clone for Partitioned<G, K, V, R> {
+    fn clone(&self) -> Self {
+        Partitioned { collection: self.collection.clone() }
+    }
+}
+
+impl<G: Scope, K: Data, V: Data, R: Semigroup> Partitioned<G, K, V, R> {
+    /// Wraps a collection that is already partitioned by the hashes of its keys.
+    ///
+    /// No records are moved, and the partitioning is not checked. The caller must ensure that each record
+    /// resides at the worker `arrange` would send it to, for example because `collection` is the output of
+    /// a `reduce`, or the contents of an arrangement. If this is not the case, the results of operators
+    /// applied to the `Partitioned` collection are incorrect.
+    pub fn new_unchecked(collection: Collection<G, (K, V), R>) -> Self {
+        Partitioned { collection }
+    }
+
+    /// Retains only the records satisfying `logic`, which preserves their partitioning.
+    pub fn filter<L>(&self, mut logic: L) -> Self
+    where L: FnMut(&K, &V) -> bool + 'static {
+        Partitioned::new_unchecked(self.collection.filter(move |&(ref key, ref val)| logic(key, val)))
+    }
+
+    /// Replaces each value with the result of `logic`, which preserves the partitioning by key.
+    pub fn map_values<V2: Data, L>(&self, mut logic: L) -> Partitioned<G, K, V2, R>
+    where L: FnMut(&K, V) -> V2 + 'static {
+        Partitioned::new_unchecked(self.collection.map(move |(key, val)| { let val = logic(&key, val); (key, val) }))
+    }
+
+    /// Merges the records of two partitioned collections.
+    pub fn concat(&self, other: &Self) -> Self {
+        Partitioned::new_unchecked(self.collection.concat(&other.collection))
+    }
+
+    /// Negates the updates of the collection.
+    pub fn negate(&self) -> Self where R: Abelian {
+        Partitioned::new_unchecked(self.collection.negate())
+    }
+}
+
+/// Partitions a collection of `(key, val)` records by the hashes of their keys.
+pub trait PartitionByKey<G: Scope, K: Data, V: Data, R: Semigroup> {
+    /// Exchanges records so that each resides at the worker selected by the hash of its key.
+    ///
+    /// This performs the exchange `arrange` would otherwise perform, once, so that the result can
+    /// be used by several operators that group by key without further exchanges.
+    fn partition_by_key(&self) -> Partitioned<G, K, V, R>;
+}
+
+impl<G, K, V, R> PartitionByKey<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+{
+    fn partition_by_key(&self) -> Partitioned<G, K, V, R> {
+        let collection =
+        self.inner
+            .exchange(|update: &((K,V),G::Timestamp,R)| (update.0).0.hashed().as_u64())
+            .as_collection();
+        Partitioned::new_unchecked(collection)
+    }
+}
+
+impl<G, K, V, R> Arrange<G, K, V, R> for Partitioned<G, K, V, R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: Semigroup+ExchangeData,
+{
+    fn arrange_named<Tr>(&self, name: &str) -> Arranged<G, TraceAgent<Tr>>
+    where
+        Tr: Trace+TraceReader<Key=K,Val=V,Time=G::Timestamp,R=R>+'static,
+        Tr::Batch: Batch<K, V, G::Timestamp, R>,
+        Tr::Cursor: Cursor<K, V, G::Timestamp, R>,
+    {
+        // Each key already resides at the worker its hash selects.
+        self.arrange_core(Pipeline, name)
+    }
+
+    fn arrange_core<P, Tr>(&self, pact: P, name: &str) -> Arranged<G, TraceAgent<Tr>>
+    where
+        P: ParallelizationContract<G::Timestamp, ((K,V),G::Timestamp,R)>,
+        Tr: Trace+TraceReader<Key=K,Val=V,Time=G::Timestamp,R=R>+'static,
+        Tr::Batch: Batch<K, V, G::Timestamp, R>,
+        Tr::Cursor: Cursor<K, V, G::Timestamp, R>,
+    {
+        self.collection.arrange_core(pact, name)
+    }
+}
+
+impl<G, K, V, R> ArrangeByKey<G, K, V, R> for Partitioned<G, K, V, R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+{
+    fn arrange_by_key(&self) -> Arranged<G, TraceAgent<DefaultValTrace<K, V, G::Timestamp, R>>> {
+        self.arrange()
+    }
+}
+
+impl<G, K, R> ArrangeBySelf<G, K, R> for Partitioned<G, K, (), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    R: ExchangeData+Semigroup,
+{
+    fn arrange_by_self(&self) -> Arranged<G, TraceAgent<DefaultKeyTrace<K, G::Timestamp, R>>> {
+        self.arrange()
+    }
+}
+
+impl<G, K, V, R> Consolidate<(K, V)> for Partitioned<G, K, V, R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+    (K, V): Hashable,
+{
+    fn consolidate_named(&self, name: &str) -> Self {
+        // Equal records have equal keys, and so already reside at the same worker.
+        let collection =
+        self.collection
+            .map(|x| (x, ()))
+            .arrange_core::<_, DefaultKeyTrace<_,_,_>>(Pipeline, name)
+            .as_collection(|x: &(K, V), _| x.clone());
+        Partitioned::new_unchecked(collection)
+    }
+}
+
+impl<G, K, V, R> Reduce<G, K, V, R> for Partitioned<G, K, V, R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+{
+    fn reduce_named<L, V2: Data, R2: Abelian>(&self, name: &str, logic: L) -> Collection<G, (K, V2), R2>
+        where L: FnMut(&K, &[(&V, R)], &mut Vec<(V2, R2)>)+'static {
+        self.arrange_by_key()
+            .reduce_named(name, logic)
+    }
+}
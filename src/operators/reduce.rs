@@ -550,14 +550,12 @@ where
                                     interesting.push((key.clone(), time));
                                 }
 
-                                // Sort each buffer by value and move into the corresponding builder.
-                                // TODO: This makes assumptions about at least one of (i) the stability of `sort_by`,
-                                //       (ii) that the buffers are time-ordered, and (iii) that the builders accept
-                                //       arbitrarily ordered times.
+                                // Sort and consolidate each buffer by (value, time), and move into the corresponding
+                                // builder. Keys are visited in order, so the builders receive ordered updates.
                                 for index in 0 .. buffers.len() {
-                                    buffers[index].1.sort_by(|x,y| x.0.cmp(&y.0));
+                                    crate::consolidation::consolidate_updates(&mut buffers[index].1);
                                     for (val, time, diff) in buffers[index].1.drain(..) {
-                                        builders[index].push((key.clone(), val, time, diff));
+                                        builders[index].push_ordered((key.clone(), val, time, diff));
                                     }
                                 }
                             }
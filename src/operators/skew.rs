@@ -0,0 +1,122 @@
+//! Joins that spread the work of heavily loaded keys across workers.
+//!
+//! A join partitions its inputs by the hashes of their keys, and so all work for one key happens at one
+//! worker. When a few keys carry a large share of the records, their workers fall behind the others.
+//!
+//! The skewed join accepts a list of such heavy keys. The records of the first input with heavy keys are
+//! spread across workers by the hashes of the entire records, and the records of the second input with
+//! heavy keys are replicated to all workers, so that each worker matches its share of the first input
+//! against all of them. The records of all other keys are joined as by `join`. The list is fixed for the
+//! lifetime of the dataflow, and may come for example from the key counts of a previous computation.
+
+use std::collections::BTreeSet;
+use std::ops::Mul;
+use std::rc::Rc;
+
+use timely::dataflow::Scope;
+use timely::dataflow::channels::pact::Exchange;
+use timely_sort::Unsigned;
+
+use hashable::Hashable;
+use ::{Data, ExchangeData, Collection};
+use ::difference::Semigroup;
+use lattice::Lattice;
+use operators::arrange::{Arrange, ArrangeByKey, ArrangeReplicated};
+use operators::join::JoinCore;
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+
+/// Extension trait for the `join_skewed` differential dataflow methods.
+pub trait JoinSkewed<G: Scope, K: Data, V: Data, R: Semigroup> where G::Timestamp: Lattice+Ord {
+    /// Matches pairs `(key,val1)` and `(key,val2)` based on `key`, spreading the work for the keys of `heavy`.
+    ///
+    /// The records of `self` with heavy keys are spread across workers, and the records of `other` with
+    /// heavy keys are replicated to all workers. Heavy keys should have few records in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::Join;
+    /// use differential_dataflow::operators::skew::JoinSkewed;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///
+    ///         // many followers of node 0, and one of each other node.
+    ///         let follows = scope.new_collection_from((0 .. 100).map(|x| (if x < 90 { 0 } else { x }, x))).1;
+    ///         let names = scope.new_collection_from(vec![(0, 'a'), (95, 'b')]).1;
+    ///
+    ///         follows
+    ///             .join_skewed(&names, vec![0])
+    ///             .assert_eq(&follows.join(&names));
+    ///     });
+    /// }
+    /// ```
+    fn join_skewed<V2, R2>(&self, other: &Collection<G, (K, V2), R2>, heavy: Vec<K>) -> Collection<G, (K, (V, V2)), <R as Mul<R2>>::Output>
+    where
+        V2: ExchangeData,
+        R2: ExchangeData+Semigroup,
+        R: Mul<R2>,
+        <R as Mul<R2>>::Output: Semigroup,
+    {
+        self.join_skewed_core(other, heavy, |k, v, v2| Some((k.clone(), (v.clone(), v2.clone()))))
+    }
+
+    /// Matches pairs `(key,val1)` and `(key,val2)` based on `key`, spreading the work for the keys of `heavy`,
+    /// and then applies a function.
+    fn join_skewed_core<V2, R2, I, L>(&self, other: &Collection<G, (K, V2), R2>, heavy: Vec<K>, result: L) -> Collection<G, I::Item, <R as Mul<R2>>::Output>
+    where
+        V2: ExchangeData,
+        R2: ExchangeData+Semigroup,
+        R: Mul<R2>,
+        <R as Mul<R2>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: Fn(&K, &V, &V2)->I+'static;
+}
+
+impl<G, K, V, R> JoinSkewed<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hashable,
+    V: ExchangeData,
+    R: ExchangeData+Semigroup,
+    (K, V): Hashable,
+{
+    fn join_skewed_core<V2, R2, I, L>(&self, other: &Collection<G, (K, V2), R2>, heavy: Vec<K>, result: L) -> Collection<G, I::Item, <R as Mul<R2>>::Output>
+    where
+        V2: ExchangeData,
+        R2: ExchangeData+Semigroup,
+        R: Mul<R2>,
+        <R as Mul<R2>>::Output: Semigroup,
+        I: IntoIterator,
+        I::Item: Data,
+        L: Fn(&K, &V, &V2)->I+'static,
+    {
+        let heavy = Rc::new(heavy.into_iter().collect::<BTreeSet<K>>());
+        let result = Rc::new(result);
+
+        // Records of light keys are joined as by `join`.
+        let light1 = { let heavy = heavy.clone(); self.filter(move |&(ref key, _)| !heavy.contains(key)) };
+        let light2 = { let heavy = heavy.clone(); other.filter(move |&(ref key, _)| !heavy.contains(key)) };
+        let light = {
+            let result = result.clone();
+            light1.arrange_by_key()
+                  .join_core(&light2.arrange_by_key(), move |k, v, v2| result(k, v, v2))
+        };
+
+        // Records of heavy keys are spread by the hashes of the records, and matched with replicated records.
+        let heavy1 = { let heavy = heavy.clone(); self.filter(move |&(ref key, _)| heavy.contains(key)) };
+        let heavy2 = other.filter(move |&(ref key, _)| heavy.contains(key));
+        let exchange = Exchange::new(move |update: &((K,V),G::Timestamp,R)| update.0.hashed().as_u64());
+        let spread =
+        heavy1.arrange_core::<_,DefaultValTrace<K,V,G::Timestamp,R>>(exchange, "ArrangeSkewed")
+              .join_core(&heavy2.arrange_replicated::<DefaultValTrace<K,V2,G::Timestamp,R2>>(), move |k, v, v2| result(k, v, v2));
+
+        light.concat(&spread)
+    }
+}
@@ -0,0 +1,158 @@
+//! Maintains the first few values of each key, in a configurable order.
+//!
+//! A `reduce` that sorts its values and retains the first `limit` reconsiders the whole group each time
+//! the group changes, which is expensive for large groups. The `TopK` operators instead reduce each key
+//! through a hierarchy of buckets, formed from increasingly short prefixes of the hashes of the values.
+//! Each bucket retains only the first `offset + limit` values of the buckets beneath it, and so a change
+//! to a value re-examines only the buckets on its path, each of which holds a bounded number of values.
+//!
+//! Global top-k computations can use the unit type `()` as the key. The hierarchy then also spreads the
+//! lower levels of the reduction across workers, rather than placing the entire collection at one worker.
+
+use std::cmp::Ordering;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use timely::dataflow::Scope;
+use timely_sort::Unsigned;
+
+use hashable::Hashable;
+use ::{ExchangeData, Collection};
+use lattice::Lattice;
+use operators::Reduce;
+
+/// The widths in bits of the hash prefixes of the buckets, from the first level of the hierarchy to the last.
+///
+/// Each level is a separate reduction, and so the first level is only as wide as is needed to make
+/// its buckets small; with 24 bits, groups of up to millions of values hold few values per bucket.
+const BUCKET_BITS: &[u32] = &[24, 16, 8];
+
+/// Extension trait for the `top_k` differential dataflow methods.
+///
+/// The methods are implemented for collections with `isize` differences, as each value occupies as
+/// many places as its count. Collections with other difference types should first be converted, for
+/// example with `explode`, to counts of their values.
+pub trait TopK<G: Scope, K: ExchangeData, V: ExchangeData> where G::Timestamp: Lattice+Ord {
+    /// Retains the `limit` least values for each key.
+    ///
+    /// Values with multiplicity greater than one occupy as many places as their multiplicity, and are
+    /// reported with the multiplicity that fits within the limit. Values whose multiplicity is not
+    /// positive are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::topk::TopK;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // report the two least values for each key
+    ///         scope.new_collection_from(1 .. 10).1
+    ///              .map(|x| (x % 3, x))
+    ///              .top_k(2)
+    ///              .assert_eq(&scope.new_collection_from(vec![(0, 3), (0, 6), (1, 1), (1, 4), (2, 2), (2, 5)]).1);
+    ///     });
+    /// }
+    /// ```
+    fn top_k(&self, limit: usize) -> Collection<G, (K, V), isize> {
+        self.top_k_by(0, limit, |x: &V, y: &V| x.cmp(y))
+    }
+
+    /// Retains the `limit` values for each key that follow the first `offset` values in the order `order`.
+    ///
+    /// Values that `order` considers equal are ordered by their `Ord` implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate timely;
+    /// extern crate differential_dataflow;
+    ///
+    /// use differential_dataflow::input::Input;
+    /// use differential_dataflow::operators::topk::TopK;
+    ///
+    /// fn main() {
+    ///     ::timely::example(|scope| {
+    ///         // report the third and fourth greatest values overall
+    ///         scope.new_collection_from(1 .. 10).1
+    ///              .map(|x| ((), x))
+    ///              .top_k_by(2, 2, |x, y| y.cmp(x))
+    ///              .map(|((), x)| x)
+    ///              .assert_eq(&scope.new_collection_from(vec![7, 6]).1);
+    ///     });
+    /// }
+    /// ```
+    fn top_k_by<F>(&self, offset: usize, limit: usize, order: F) -> Collection<G, (K, V), isize>
+    where F: Fn(&V, &V)->Ordering+'static;
+}
+
+impl<G, K, V> TopK<G, K, V> for Collection<G, (K, V), isize>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: ExchangeData+Hash,
+    V: ExchangeData+Hash,
+{
+    fn top_k_by<F>(&self, offset: usize, limit: usize, order: F) -> Collection<G, (K, V), isize>
+    where F: Fn(&V, &V)->Ordering+'static {
+
+        let order = Rc::new(order);
+
+        // Each record starts in the bucket of the widest prefix of the hash of its value.
+        let mut buckets =
+        self.map(|(key, val)| {
+            let bucket = val.hashed().as_u64() >> (64 - BUCKET_BITS[0]);
+            ((key, bucket), val)
+        });
+
+        // Each level retains the first `offset + limit` values of each bucket, and moves them to the
+        // bucket of the next shorter prefix.
+        for (level, &bits) in BUCKET_BITS.iter().enumerate() {
+            let shift = BUCKET_BITS.get(level + 1).map(|next| bits - next).unwrap_or(bits);
+            let order = order.clone();
+            buckets =
+            buckets
+                .reduce_named("TopKBucket", move |_bucket, input, output| {
+                    retain_first(input, 0, offset + limit, &*order, output);
+                })
+                .map(move |((key, bucket), val)| ((key, bucket >> shift), val));
+        }
+
+        buckets
+            .map(|((key, _bucket), val)| (key, val))
+            .reduce_named("TopK", move |_key, input, output| {
+                retain_first(input, offset, limit, &*order, output);
+            })
+    }
+}
+
+/// Populates `output` with the `limit` values of `input` that follow the first `offset`, in the order `order`.
+///
+/// Values occupy as many places as their accumulated counts, and values whose counts are not positive
+/// are ignored.
+fn retain_first<V, F>(input: &[(&V, isize)], offset: usize, limit: usize, order: &F, output: &mut Vec<(V, isize)>)
+where
+    V: Clone,
+    F: Fn(&V, &V)->Ordering+?Sized,
+{
+    let mut sorted = input.iter().filter(|&&(_, count)| count > 0).collect::<Vec<_>>();
+    // The sort is stable, and so values `order` considers equal remain in the order of `input`.
+    sorted.sort_by(|x, y| order(x.0, y.0));
+
+    let mut skip = offset as isize;
+    let mut remaining = limit as isize;
+    for &&(val, count) in sorted.iter() {
+        if remaining == 0 { break; }
+        let skipped = ::std::cmp::min(skip, count);
+        skip -= skipped;
+        let taken = ::std::cmp::min(remaining, count - skipped);
+        if taken > 0 {
+            output.push((val.clone(), taken));
+            remaining -= taken;
+        }
+    }
+}
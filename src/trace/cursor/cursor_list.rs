@@ -136,6 +136,13 @@ where
         }
         self.minimize_keys(storage);
     }
+    #[inline]
+    fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) {
+        for index in 0 .. self.cursors.len() {
+            self.cursors[index].seek_key_with(&storage[index], &before);
+        }
+        self.minimize_keys(storage);
+    }
 
     // value methods
     #[inline]
@@ -89,6 +89,17 @@ where
             (true, true) => self.cursor1.key(&storage.0).cmp(self.cursor2.key(&storage.1)),
         };
     }
+    fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) {
+
+        self.cursor1.seek_key_with(&storage.0, &before);
+        self.cursor2.seek_key_with(&storage.1, &before);
+
+        self.key_order = match (self.cursor1.key_valid(&storage.0), self.cursor2.key_valid(&storage.1)) {
+            (false, _) => Ordering::Greater,
+            (_, false) => Ordering::Less,
+            (true, true) => self.cursor1.key(&storage.0).cmp(self.cursor2.key(&storage.1)),
+        };
+    }
 
     // value methods
     fn step_val(&mut self, storage: &Self::Storage) {
@@ -3,13 +3,16 @@
 //! The `Cursor` trait contains several methods for efficiently navigating ordered collections
 //! of tuples of the form `(key, val, time, diff)`. The cursor is different from an iterator
 //! both because it allows navigation on multiple levels (key and val), but also because it
-//! supports efficient seeking (via the `seek_key` and `seek_val` methods).
+//! supports efficient seeking (via the `seek_key` and `seek_val` methods), and scans over contiguous
+//! ranges of keys (via the `seek_range` and `key_in_range` methods).
 
 // pub mod cursor_list;
 pub mod cursor_pair;
 pub mod cursor_list;
+pub mod range;
 
 pub use self::cursor_list::CursorList;
+pub use self::range::{KeyRange, Between, Prefix};
 
 /// A cursor for navigating ordered `(key, val, time, diff)` updates.
 pub trait Cursor<K, V, T, R> {
@@ -48,6 +51,28 @@ pub trait Cursor<K, V, T, R> {
 	fn step_key(&mut self, storage: &Self::Storage);
 	/// Advances the cursor to the specified key.
 	fn seek_key(&mut self, storage: &Self::Storage, key: &K);
+	/// Advances the cursor to the first key for which `before` returns false.
+	///
+	/// The function `before` should hold for some prefix of the keys and fail for all keys after it, as
+	/// `|k| k < key` does. The default implementation steps through keys one at a time, and cursors able
+	/// to seek more efficiently should override it.
+	fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) {
+		while self.key_valid(storage) && before(self.key(storage)) {
+			self.step_key(storage);
+		}
+	}
+	/// Advances the cursor to the first key not before `range`, and indicates if that key is within it.
+	fn seek_range<Q: KeyRange<K>>(&mut self, storage: &Self::Storage, range: &Q) -> bool {
+		self.seek_key_with(storage, |key| range.before(key));
+		self.key_in_range(storage, range)
+	}
+	/// Indicates if the current key is valid and not after `range`.
+	///
+	/// After `seek_range`, the keys of the range can be visited by calling `step_key` for as long as
+	/// this method returns true.
+	fn key_in_range<Q: KeyRange<K>>(&self, storage: &Self::Storage, range: &Q) -> bool {
+		self.get_key(storage).map(|key| !range.after(key)).unwrap_or(false)
+	}
 
 	/// Advances the cursor to the next value.
 	fn step_val(&mut self, storage: &Self::Storage);
@@ -0,0 +1,67 @@
+//! Descriptions of contiguous ranges of keys, for range scans with cursors.
+//!
+//! A `KeyRange` describes a range by the keys that come before and after it. Ranges are implemented for
+//! `std::ops::Range` (keys from an inclusive lower bound up to an exclusive upper bound), for
+//! `std::ops::RangeFrom`, for `Between`, which is as `Range` but may be exchanged between workers, and for
+//! `Prefix`, which contains the tuple keys whose first coordinate equals a supplied value.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! cursor.seek_range(&storage, &(lower .. upper));
+//! while cursor.key_in_range(&storage, &(lower .. upper)) {
+//!     // visit values of `cursor.key(&storage)`
+//!     cursor.step_key(&storage);
+//! }
+//! ```
+
+use std::ops::{Range, RangeFrom};
+
+/// A contiguous range of keys.
+///
+/// For any key order, the keys before the range, in the range, and after the range must each be
+/// contiguous and in that order.
+pub trait KeyRange<K> {
+    /// Indicates whether `key` precedes all keys in the range.
+    fn before(&self, key: &K) -> bool;
+    /// Indicates whether `key` follows all keys in the range.
+    fn after(&self, key: &K) -> bool;
+}
+
+impl<K: Ord> KeyRange<K> for Range<K> {
+    #[inline] fn before(&self, key: &K) -> bool { key < &self.start }
+    #[inline] fn after(&self, key: &K) -> bool { key >= &self.end }
+}
+
+impl<K: Ord> KeyRange<K> for RangeFrom<K> {
+    #[inline] fn before(&self, key: &K) -> bool { key < &self.start }
+    #[inline] fn after(&self, _key: &K) -> bool { false }
+}
+
+/// The keys greater or equal to `lower` and less than `upper`.
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Abomonation, Serialize, Deserialize)]
+pub struct Between<K> {
+    /// The least key in the range.
+    pub lower: K,
+    /// The least key after the range.
+    pub upper: K,
+}
+
+impl<K: Ord> KeyRange<K> for Between<K> {
+    #[inline] fn before(&self, key: &K) -> bool { key < &self.lower }
+    #[inline] fn after(&self, key: &K) -> bool { key >= &self.upper }
+}
+
+/// The tuple keys whose first coordinate equals the wrapped value.
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Abomonation, Serialize, Deserialize)]
+pub struct Prefix<P>(pub P);
+
+impl<A: Ord, B> KeyRange<(A, B)> for Prefix<A> {
+    #[inline] fn before(&self, key: &(A, B)) -> bool { key.0 < self.0 }
+    #[inline] fn after(&self, key: &(A, B)) -> bool { key.0 > self.0 }
+}
+
+impl<A: Ord, B, C> KeyRange<(A, B, C)> for Prefix<A> {
+    #[inline] fn before(&self, key: &(A, B, C)) -> bool { key.0 < self.0 }
+    #[inline] fn after(&self, key: &(A, B, C)) -> bool { key.0 > self.0 }
+}
@@ -0,0 +1,401 @@
+//! Batches whose merges are performed by helper threads.
+//!
+//! A `BackgroundBatch<B>` wraps a batch type `B` and behaves exactly as `B` does, except that merges of
+//! large batches are handed to a pool of helper threads rather than performed by the worker as the spine
+//! applies fuel. The helper threads use `B`'s own merger, so the merged batches are identical to those
+//! the worker would have produced.
+//!
+//! A background merge absorbs the fuel the spine applies to it without work, and remains in progress until
+//! the helper thread delivers its result, which is swapped in the next time the spine applies fuel to it.
+//! The worker does not wait on the helper thread, unless the spine must complete the merge at once, as
+//! when it needs the merge's layer for another batch.
+//!
+//! Each worker thread starts its own helper threads the first time it hands off a merge, and joins them
+//! when it exits, once they have finished the merges they are performing. A worker's
+//! `BackgroundConfig`, installed with `configure`, sets the number of its helper threads and the number
+//! of updates below which merges are performed by the worker. A worker that installs no configuration
+//! reads it once from the environment variables `DIFFERENTIAL_MERGE_THREADS`, which defaults to one, and
+//! `DIFFERENTIAL_BACKGROUND_THRESHOLD`, which defaults to 100,000.
+
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver, TryRecvError};
+use std::thread::JoinHandle;
+
+use timely::progress::{Antichain, frontier::AntichainRef};
+
+use trace::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description, Statistics};
+
+/// The default number of updates below which merges are performed by the worker.
+const DEFAULT_THRESHOLD: usize = 100_000;
+
+/// Work handed to a helper thread.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The configuration of background merges for a worker thread.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BackgroundConfig {
+    /// The number of helper threads the worker starts when it first hands off a merge.
+    pub threads: usize,
+    /// The number of updates below which merges are performed by the worker.
+    pub threshold: usize,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        BackgroundConfig {
+            threads: 1,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+}
+
+impl BackgroundConfig {
+    /// Reads the configuration from the environment, using defaults for unset variables.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        BackgroundConfig {
+            threads: read_env("DIFFERENTIAL_MERGE_THREADS").unwrap_or(default.threads),
+            threshold: read_env("DIFFERENTIAL_BACKGROUND_THRESHOLD").unwrap_or(default.threshold),
+        }
+    }
+}
+
+/// Reads an integer from an environment variable, if it is set.
+fn read_env(name: &str) -> Option<usize> {
+    ::std::env::var(name).ok().and_then(|x| x.parse::<usize>().ok())
+}
+
+thread_local! {
+    /// The configuration of the current worker, read from the environment unless installed.
+    static CONFIG: RefCell<Option<BackgroundConfig>> = RefCell::new(None);
+    /// The helper threads of the current worker, started on first use.
+    static HELPERS: RefCell<Option<Helpers>> = RefCell::new(None);
+}
+
+/// Installs the configuration of background merges started by the current worker thread.
+///
+/// Merges already handed off are unaffected, and the number of helper threads only takes effect if the
+/// worker has not yet started them.
+pub fn configure(config: BackgroundConfig) {
+    CONFIG.with(|current| *current.borrow_mut() = Some(config));
+}
+
+/// The configuration of background merges started by the current worker thread.
+pub fn configuration() -> BackgroundConfig {
+    CONFIG.with(|current| current.borrow_mut().get_or_insert_with(BackgroundConfig::from_env).clone())
+}
+
+/// Hands `job` to one of the current worker's helper threads.
+fn spawn(job: Job) {
+    HELPERS.with(|helpers| {
+        let mut helpers = helpers.borrow_mut();
+        let helpers = helpers.get_or_insert_with(Helpers::start);
+        let queue = helpers.queue.as_ref().expect("helper queue closed");
+        if let Err(error) = queue.send(job) {
+            // Helper threads only exit by panicking; do the work ourselves.
+            (error.0)();
+        }
+    })
+}
+
+/// The helper threads of a worker, and the queue of jobs they share.
+struct Helpers {
+    /// The queue of jobs, which the helper threads leave once it is closed and empty.
+    queue: Option<Sender<Job>>,
+    /// The helper threads.
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Helpers {
+    /// Starts helper threads that share a queue of jobs.
+    fn start() -> Self {
+        let threads = configuration().threads;
+        let (sender, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let threads = (0 .. ::std::cmp::max(threads, 1)).map(|index| {
+            let receiver = receiver.clone();
+            ::std::thread::Builder::new()
+                .name(format!("differential-merge-{}", index))
+                .spawn(move || {
+                    loop {
+                        let job = receiver.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    }
+                })
+                .expect("failed to start merge thread")
+        }).collect();
+        Helpers { queue: Some(sender), threads }
+    }
+}
+
+impl Drop for Helpers {
+    fn drop(&mut self) {
+        // Closing the queue lets each thread finish the jobs left in it, of which those of dropped merges
+        // return at once, and then exit.
+        self.queue.take();
+        for thread in self.threads.drain(..) {
+            // A helper thread that panicked has reported it, and merges waiting on it fail.
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A batch that may be shared with helper threads.
+pub struct BackgroundBatch<B> {
+    batch: Arc<B>,
+}
+
+impl<B> BackgroundBatch<B> {
+    /// Wraps a batch.
+    pub fn new(batch: B) -> Self { BackgroundBatch { batch: Arc::new(batch) } }
+    /// The wrapped batch.
+    #[inline]
+    pub fn inner(&self) -> &B { &*self.batch }
+}
+
+impl<B> Clone for BackgroundBatch<B> {
+    fn clone(&self) -> Self { BackgroundBatch { batch: self.batch.clone() } }
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> BatchReader<K, V, T, R> for BackgroundBatch<B> {
+
+    /// The type used to enumerate the batch's contents.
+    type Cursor = BackgroundCursor<K, V, T, R, B>;
+    /// Acquires a cursor to the batch's contents.
+    fn cursor(&self) -> Self::Cursor {
+        BackgroundCursor::new(self.inner().cursor())
+    }
+
+    /// The number of updates in the batch.
+    fn len(&self) -> usize { self.inner().len() }
+    /// Describes the times of the updates in the batch.
+    fn description(&self) -> &Description<T> { self.inner().description() }
+    /// Reports the size of the wrapped batch.
+    fn statistics(&self) -> Statistics { self.inner().statistics() }
+}
+
+/// A cursor over a batch that may be shared with helper threads.
+pub struct BackgroundCursor<K, V, T, R, B: BatchReader<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+    cursor: B::Cursor,
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> BackgroundCursor<K, V, T, R, B> {
+    fn new(cursor: B::Cursor) -> Self {
+        BackgroundCursor {
+            cursor,
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> Cursor<K, V, T, R> for BackgroundCursor<K, V, T, R, B> {
+
+    type Storage = BackgroundBatch<B>;
+
+    #[inline] fn key_valid(&self, storage: &Self::Storage) -> bool { self.cursor.key_valid(storage.inner()) }
+    #[inline] fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.val_valid(storage.inner()) }
+
+    #[inline] fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.cursor.key(storage.inner()) }
+    #[inline] fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.cursor.val(storage.inner()) }
+
+    #[inline]
+    fn map_times<L: FnMut(&T, &R)>(&mut self, storage: &Self::Storage, logic: L) {
+        self.cursor.map_times(storage.inner(), logic)
+    }
+
+    #[inline] fn step_key(&mut self, storage: &Self::Storage) { self.cursor.step_key(storage.inner()) }
+    #[inline] fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek_key(storage.inner(), key) }
+    #[inline] fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_key_with(storage.inner(), before) }
+
+    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(storage.inner()) }
+    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(storage.inner(), val) }
+
+    #[inline] fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind_keys(storage.inner()) }
+    #[inline] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(storage.inner()) }
+}
+
+/// An immutable collection of updates.
+impl<K, V, T, R, B> Batch<K, V, T, R> for BackgroundBatch<B>
+where
+    K: 'static,
+    V: 'static,
+    T: Send+'static,
+    R: 'static,
+    B: Batch<K, V, T, R>+Send+Sync+'static,
+{
+    type Batcher = BackgroundBatcher<K, V, T, R, B>;
+    type Builder = BackgroundBuilder<K, V, T, R, B>;
+    type Merger = BackgroundMerger<K, V, T, R, B>;
+}
+
+/// Wrapper type for batching batches that may be shared with helper threads.
+pub struct BackgroundBatcher<K, V, T, R, B: Batch<K, V, T, R>> { batcher: B::Batcher }
+
+/// Functionality for collecting and batching updates.
+impl<K, V, T, R, B> Batcher<K, V, T, R, BackgroundBatch<B>> for BackgroundBatcher<K, V, T, R, B>
+where
+    K: 'static,
+    V: 'static,
+    T: Send+'static,
+    R: 'static,
+    B: Batch<K, V, T, R>+Send+Sync+'static,
+{
+    fn new() -> Self { BackgroundBatcher { batcher: <B::Batcher as Batcher<K, V, T, R, B>>::new() } }
+    fn push_batch(&mut self, batch: &mut Vec<((K, V), T, R)>) { self.batcher.push_batch(batch) }
+    fn seal(&mut self, upper: Antichain<T>) -> BackgroundBatch<B> { BackgroundBatch::new(self.batcher.seal(upper)) }
+    fn frontier(&mut self) -> AntichainRef<T> { self.batcher.frontier() }
+}
+
+/// Wrapper type for building batches that may be shared with helper threads.
+pub struct BackgroundBuilder<K, V, T, R, B: Batch<K, V, T, R>> { builder: B::Builder }
+
+/// Functionality for building batches from ordered update sequences.
+impl<K, V, T, R, B> Builder<K, V, T, R, BackgroundBatch<B>> for BackgroundBuilder<K, V, T, R, B>
+where
+    K: 'static,
+    V: 'static,
+    T: Send+'static,
+    R: 'static,
+    B: Batch<K, V, T, R>+Send+Sync+'static,
+{
+    fn new() -> Self { BackgroundBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::new() } }
+    fn with_capacity(cap: usize) -> Self { BackgroundBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::with_capacity(cap) } }
+    fn push(&mut self, element: (K, V, T, R)) { self.builder.push(element) }
+    fn push_ordered(&mut self, element: (K, V, T, R)) { self.builder.push_ordered(element) }
+    fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> BackgroundBatch<B> {
+        BackgroundBatch::new(self.builder.done(lower, upper, since))
+    }
+    fn from_ordered<I: IntoIterator<Item=(K, V, T, R)>>(updates: I, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> BackgroundBatch<B> {
+        BackgroundBatch::new(<B::Builder as Builder<K, V, T, R, B>>::from_ordered(updates, lower, upper, since))
+    }
+}
+
+/// Work handed to a helper thread by a merger, which completes when a merge by the worker would.
+pub struct HelperMerge<O> {
+    /// Delivers the result once the helper thread completes it.
+    receiver: Receiver<O>,
+    /// The result, if it has been delivered.
+    result: Option<O>,
+    /// The fuel received so far.
+    received: isize,
+    /// The fuel after which a merge by the worker would have completed.
+    required: isize,
+    /// Set once the merge is dropped, so that a helper thread yet to start it need not.
+    dropped: Arc<AtomicBool>,
+}
+
+impl<O: Send+'static> HelperMerge<O> {
+    /// Hands `job` to a helper thread of the current worker, as a merge of `updates` updates.
+    pub(crate) fn start<F: FnOnce()->O+Send+'static>(updates: usize, job: F) -> Self {
+        let (sender, receiver) = channel();
+        let dropped = Arc::new(AtomicBool::new(false));
+        let skip = dropped.clone();
+        spawn(Box::new(move || {
+            // The worker may have dropped the merge, in which case no one needs the result.
+            if !skip.load(Ordering::SeqCst) {
+                let _ = sender.send(job());
+            }
+        }));
+        HelperMerge {
+            receiver,
+            result: None,
+            received: 0,
+            required: updates as isize,
+            dropped,
+        }
+    }
+
+    /// Absorbs fuel, and leaves fuel once the helper thread has delivered the result.
+    ///
+    /// The merge remains in progress until the result is delivered, however much fuel it has received. Once
+    /// delivered, the fuel left is that beyond what a merge by the worker would have consumed, if any.
+    pub(crate) fn work(&mut self, fuel: &mut isize) {
+        self.received = self.received.saturating_add(*fuel);
+        if self.result.is_none() {
+            match self.receiver.try_recv() {
+                Ok(result) => { self.result = Some(result); },
+                Err(TryRecvError::Empty) => { },
+                Err(TryRecvError::Disconnected) => panic!("background merge failed"),
+            }
+        }
+        if self.result.is_none() {
+            *fuel = 0;
+        }
+        else if self.received >= self.required {
+            *fuel = ::std::cmp::max(self.received - self.required, 1);
+        }
+    }
+
+    /// Blocks until the helper thread delivers the result, and returns it.
+    pub(crate) fn done(mut self) -> O {
+        self.wait();
+        self.result.take().unwrap()
+    }
+
+    /// Blocks until the helper thread delivers the result.
+    fn wait(&mut self) {
+        if self.result.is_none() {
+            self.result = Some(self.receiver.recv().expect("background merge failed"));
+        }
+    }
+}
+
+impl<O> Drop for HelperMerge<O> {
+    fn drop(&mut self) {
+        self.dropped.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Wrapper type for merging batches, possibly on a helper thread.
+pub enum BackgroundMerger<K, V, T, R, B: Batch<K, V, T, R>> {
+    /// A merge performed by the worker.
+    Foreground(B::Merger),
+    /// A merge performed by a helper thread.
+    Background(HelperMerge<B>),
+}
+
+/// Represents a merge in progress.
+impl<K, V, T, R, B> Merger<K, V, T, R, BackgroundBatch<B>> for BackgroundMerger<K, V, T, R, B>
+where
+    K: 'static,
+    V: 'static,
+    T: Send+'static,
+    R: 'static,
+    B: Batch<K, V, T, R>+Send+Sync+'static,
+{
+    fn new(source1: &BackgroundBatch<B>, source2: &BackgroundBatch<B>, compaction_frontier: Option<AntichainRef<T>>) -> Self {
+        let updates = source1.len() + source2.len();
+        if updates < configuration().threshold {
+            return BackgroundMerger::Foreground(<B::Merger as Merger<K, V, T, R, B>>::new(source1.inner(), source2.inner(), compaction_frontier));
+        }
+
+        let source1 = source1.batch.clone();
+        let source2 = source2.batch.clone();
+        let frontier = compaction_frontier.map(|frontier| frontier.to_owned());
+        BackgroundMerger::Background(HelperMerge::start(updates, move || {
+            let frontier = frontier.as_ref().map(|frontier| frontier.borrow());
+            let mut merger = <B::Merger as Merger<K, V, T, R, B>>::new(&source1, &source2, frontier);
+            let mut fuel = isize::max_value();
+            merger.work(&source1, &source2, &mut fuel);
+            merger.done()
+        }))
+    }
+    fn work(&mut self, source1: &BackgroundBatch<B>, source2: &BackgroundBatch<B>, fuel: &mut isize) {
+        match self {
+            BackgroundMerger::Foreground(merger) => merger.work(source1.inner(), source2.inner(), fuel),
+            BackgroundMerger::Background(merge) => merge.work(fuel),
+        }
+    }
+    fn done(self) -> BackgroundBatch<B> {
+        match self {
+            BackgroundMerger::Foreground(merger) => BackgroundBatch::new(merger.done()),
+            BackgroundMerger::Background(merge) => BackgroundBatch::new(merge.done()),
+        }
+    }
+}
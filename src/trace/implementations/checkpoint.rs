@@ -0,0 +1,255 @@
+//! Durable checkpoints of trace contents.
+//!
+//! A checkpoint is a directory holding one file per batch of a trace, each containing the abomonated
+//! encoding of the batch (which includes its `Description`), a file holding the trace's logical
+//! compaction frontiers (those set by `advance_by` and `distinguish_since`), and a `MANIFEST` file
+//! listing the batch files in order. A trace can be rebuilt from a checkpoint by re-inserting the
+//! batches and re-applying the frontiers, which resumes the trace from the upper frontier of its last
+//! batch without allowing it to distinguish times it could not distinguish when written.
+//!
+//! The encoding is abomonation's, which means that checkpoints are only meaningful to the same binary
+//! (or at least the same types on the same architecture) that wrote them. Checkpoints are written to
+//! fresh files, and the manifest is replaced atomically once all batches are durable, so an interrupted
+//! checkpoint leaves the previous checkpoint intact.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use abomonation::{Abomonation, encode, decode};
+use abomonation::abomonated::Abomonated;
+
+use timely::dataflow::operators::generic::OperatorInfo;
+use timely::progress::frontier::AntichainRef;
+use timely::scheduling::Activator;
+
+use trace::{Trace, TraceReader, Batch, BatchReader, Builder, Cursor};
+
+/// The name of the file listing the batches of a checkpoint.
+const MANIFEST: &str = "MANIFEST";
+
+/// A batch that can be written to and read back from bytes.
+pub trait Persist<K, V, T, R> : BatchReader<K, V, T, R>+Sized {
+    /// Writes the contents and description of the batch to `writer`.
+    fn persist<W: Write>(&self, writer: &mut W) -> io::Result<()>;
+    /// Reconstructs a batch from bytes produced by `persist`.
+    fn restore(bytes: Vec<u8>) -> io::Result<Self>;
+}
+
+impl<K, V, T, R, B> Persist<K, V, T, R> for Rc<B>
+where
+    K: Clone,
+    V: Clone,
+    T: Clone,
+    R: Clone,
+    B: Batch<K, V, T, R>+Abomonation,
+{
+    fn persist<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        unsafe { encode(&**self, writer) }
+    }
+    fn restore(bytes: Vec<u8>) -> io::Result<Self> {
+        // The decoded batch borrows `bytes`, so we rebuild an owned batch from its contents.
+        let decoded = <Abomonated<B, Vec<u8>> as Persist<K, V, T, R>>::restore(bytes)?;
+        let mut builder = <B::Builder as Builder<K, V, T, R, B>>::with_capacity(decoded.len());
+        let mut cursor = decoded.cursor();
+        while cursor.key_valid(&decoded) {
+            while cursor.val_valid(&decoded) {
+                let key = cursor.key(&decoded);
+                let val = cursor.val(&decoded);
+                cursor.map_times(&decoded, |time, diff| {
+                    builder.push((key.clone(), val.clone(), time.clone(), diff.clone()));
+                });
+                cursor.step_val(&decoded);
+            }
+            cursor.step_key(&decoded);
+        }
+        let description = decoded.description();
+        Ok(Rc::new(builder.done(
+            description.lower().clone(),
+            description.upper().clone(),
+            description.since().clone(),
+        )))
+    }
+}
+
+impl<K, V, T, R, B> Persist<K, V, T, R> for Abomonated<B, Vec<u8>>
+where
+    B: BatchReader<K, V, T, R>+Abomonation,
+{
+    fn persist<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        unsafe { encode(&**self, writer) }
+    }
+    fn restore(bytes: Vec<u8>) -> io::Result<Self> {
+        unsafe { Abomonated::<B, _>::new(bytes) }
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "checkpoint: failed to decode batch"))
+    }
+}
+
+/// Writes the batches and compaction frontiers of `trace` to the directory `path`.
+///
+/// The directory is created if it does not exist. Files of any previous checkpoint in the
+/// directory are removed once the new manifest is in place.
+pub fn write<Tr, P>(trace: &mut Tr, path: P) -> io::Result<()>
+where
+    Tr: TraceReader,
+    Tr::Time: Abomonation,
+    Tr::Batch: Persist<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    fs::create_dir_all(path)?;
+
+    // Each checkpoint uses fresh file names, so that the previous checkpoint remains valid until
+    // the manifest is replaced. Only a missing manifest indicates that there is no previous checkpoint.
+    let generation = match read_manifest(path) {
+        Ok((generation, _)) => generation + 1,
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => 0,
+        Err(error) => return Err(error),
+    };
+
+    let frontiers = (trace.advance_frontier().to_vec(), trace.distinguish_frontier().to_vec());
+    write_frontiers(&frontiers, &path.join(frontiers_name(generation)))?;
+
+    let mut names = Vec::new();
+    let mut result: io::Result<()> = Ok(());
+    trace.map_batches(|batch| {
+        if result.is_ok() {
+            let name = format!("batch-{}-{}", generation, names.len());
+            result = write_batch(batch, &path.join(&name));
+            names.push(name);
+        }
+    });
+    result?;
+
+    let mut manifest = format!("{}\n", generation);
+    for name in names.iter() {
+        manifest.push_str(name);
+        manifest.push('\n');
+    }
+    let temporary = path.join(format!("{}.tmp", MANIFEST));
+    {
+        let mut file = File::create(&temporary)?;
+        file.write_all(manifest.as_bytes())?;
+        file.sync_all()?;
+    }
+    fs::rename(&temporary, path.join(MANIFEST))?;
+
+    // Remove batch and frontier files not referenced by the new manifest.
+    let current = frontiers_name(generation);
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let stale_batch = name.starts_with("batch-") && !names.contains(&name);
+        let stale_frontiers = name.starts_with("frontiers-") && name != current;
+        if stale_batch || stale_frontiers {
+            fs::remove_file(entry.path())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the batches of the checkpoint in directory `path`, in the order they were written.
+///
+/// The batches are checked to form a contiguous sequence, in that the `lower` of each batch is
+/// the `upper` of the batch before it.
+pub fn read<K, V, T, R, B, P>(path: P) -> io::Result<Vec<B>>
+where
+    T: PartialEq,
+    B: Persist<K, V, T, R>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let (_generation, names) = read_manifest(path)?;
+
+    let mut batches: Vec<B> = Vec::with_capacity(names.len());
+    for name in names.iter() {
+        let batch = B::restore(fs::read(path.join(name))?)?;
+        if let Some(prior) = batches.last() {
+            if prior.upper() != batch.lower() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "checkpoint: batches are not contiguous"));
+            }
+        }
+        batches.push(batch);
+    }
+
+    Ok(batches)
+}
+
+/// Reads the `advance_by` and `distinguish_since` frontiers of the checkpoint in directory `path`.
+pub fn read_frontiers<T, P>(path: P) -> io::Result<(Vec<T>, Vec<T>)>
+where
+    T: Abomonation+Clone,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let (generation, _names) = read_manifest(path)?;
+    let mut bytes = fs::read(path.join(frontiers_name(generation)))?;
+    unsafe { decode::<(Vec<T>, Vec<T>)>(&mut bytes[..]) }
+        .filter(|(_, remaining)| remaining.is_empty())
+        .map(|(frontiers, _)| frontiers.clone())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "checkpoint: failed to decode frontiers"))
+}
+
+/// Rebuilds a trace from the checkpoint in directory `path`.
+///
+/// The arguments other than `path` are those of `Trace::new`. The resulting trace has an upper
+/// frontier equal to the upper frontier of the last batch in the checkpoint, and the compaction
+/// frontiers the trace had when the checkpoint was written.
+pub fn restore<Tr, P>(
+    path: P,
+    info: OperatorInfo,
+    logging: Option<::logging::Logger>,
+    activator: Option<Activator>,
+) -> io::Result<Tr>
+where
+    Tr: Trace,
+    Tr::Time: Abomonation,
+    Tr::Batch: Batch<Tr::Key, Tr::Val, Tr::Time, Tr::R>+Persist<Tr::Key, Tr::Val, Tr::Time, Tr::R>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let batches = read::<Tr::Key, Tr::Val, Tr::Time, Tr::R, Tr::Batch, _>(path)?;
+    let (advance, through) = read_frontiers::<Tr::Time, _>(path)?;
+    let mut trace = Tr::new(info, logging, activator);
+    for batch in batches {
+        trace.insert(batch);
+    }
+    trace.advance_by(AntichainRef::new(&advance[..]));
+    trace.distinguish_since(AntichainRef::new(&through[..]));
+    Ok(trace)
+}
+
+/// Writes `batch` to a new file at `path`, and syncs the file.
+fn write_batch<K, V, T, R, B: Persist<K, V, T, R>>(batch: &B, path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    batch.persist(&mut writer)?;
+    writer.flush()?;
+    writer.get_ref().sync_all()
+}
+
+/// Writes the compaction `frontiers` to a new file at `path`, and syncs the file.
+fn write_frontiers<T: Abomonation>(frontiers: &(Vec<T>, Vec<T>), path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    unsafe { encode(frontiers, &mut writer)?; }
+    writer.flush()?;
+    writer.get_ref().sync_all()
+}
+
+/// The name of the file holding the compaction frontiers of checkpoint `generation`.
+fn frontiers_name(generation: usize) -> String {
+    format!("frontiers-{}", generation)
+}
+
+/// Reads the generation and batch file names from the manifest in `path`.
+fn read_manifest(path: &Path) -> io::Result<(usize, Vec<String>)> {
+    let contents = fs::read_to_string(path.join(MANIFEST))?;
+    let mut lines = contents.lines();
+    let generation = lines
+        .next()
+        .and_then(|line| line.parse::<usize>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "checkpoint: malformed manifest"))?;
+    let names = lines.filter(|line| !line.is_empty()).map(|line| line.to_string()).collect();
+    Ok((generation, names))
+}
@@ -1,31 +1,40 @@
 //! Trace and batch implementations based on Robin Hood hashing.
 //!
-//! The types and type aliases in this module start with either 
-//! 
+//! The types and type aliases in this module start with either
+//!
 //! * `HashVal`: Collections whose data have the form `(key, val)` where `key` is hash-ordered.
 //! * `HashKey`: Collections whose data have the form `key` where `key` is hash-ordered.
 //!
 //! Although `HashVal` is more general than `HashKey`, the latter has a simpler representation
 //! and should consume fewer resources (computation and memory) when it applies.
+//!
+//! Keys must implement `HashOrdered`, for example by wrapping them in `OrdWrapper`, and should have
+//! well-distributed `hashed()` values. Under these conditions `seek_key` finds keys in expected
+//! constant time, rather than by galloping through the keys in between. Batches are formed by a
+//! `RadixBatcher`, and so keys must also implement `RadixKey`, as the wrappers of `hashable` do.
 
 use std::rc::Rc;
+use std::mem::size_of;
+
+use timely::progress::{Antichain, frontier::AntichainRef};
 
-use ::Diff;
+use ::difference::Semigroup;
 use hashable::HashOrdered;
+use lattice::Lattice;
 
 use trace::layers::{Trie, TupleBuilder};
 use trace::layers::Builder as TrieBuilder;
 use trace::layers::Cursor as TrieCursor;
+use trace::layers::MergeBuilder;
 use trace::layers::hashed::{HashedLayer, HashedBuilder, HashedCursor};
 use trace::layers::ordered::{OrderedLayer, OrderedBuilder};
 use trace::layers::ordered_leaf::{OrderedLeaf, OrderedLeafBuilder};
-
-use lattice::Lattice;
-use trace::{Batch, BatchReader, Builder, Cursor};
+use trace::{Batch, BatchReader, Builder, Merger, Cursor, Statistics};
 use trace::description::Description;
 
-use super::spine::Spine;
-use super::radix_batcher::RadixBatcher;
+use super::spine_fueled::Spine;
+use super::radix_batcher::{RadixBatcher, RadixKey};
+use super::ord::{OrdValBatch, OrdKeyBatch};
 
 /// A trace implementation using a spine of hash-map batches.
 pub type HashValSpine<K, V, T, R> = Spine<K, V, T, R, Rc<HashValBatch<K, V, T, R>>>;
@@ -34,69 +43,193 @@ pub type HashKeySpine<K, T, R> = Spine<K, (), T, R, Rc<HashKeyBatch<K, T, R>>>;
 
 
 /// An immutable collection of update tuples, from a contiguous interval of logical times.
-#[derive(Debug)]
-pub struct HashValBatch<K: HashOrdered, V: Ord, T: Lattice, R: Diff> {
+#[derive(Debug, Abomonation)]
+pub struct HashValBatch<K, V, T, R>
+where
+	V: Ord,
+	T: Lattice,
+{
 	/// Where all the dataz is.
 	pub layer: HashedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>>,
 	/// Description of the update times this layer represents.
 	pub desc: Description<T>,
 }
 
-impl<K, V, T, R> BatchReader<K, V, T, R> for Rc<HashValBatch<K, V, T, R>>
-where K: Clone+Default+HashOrdered+'static, V: Clone+Ord+'static, T: Lattice+Ord+Clone+Default+'static, R: Diff {
+impl<K, V, T, R> BatchReader<K, V, T, R> for HashValBatch<K, V, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	V: Ord+Clone+'static,
+	T: Lattice+Ord+Clone+'static,
+	R: Semigroup,
+{
 	type Cursor = HashValCursor<V, T, R>;
-	fn cursor(&self) -> (Self::Cursor, <Self::Cursor as Cursor<K, V, T, R>>::Storage) { 
-		let cursor = HashValCursor {
-			cursor: self.layer.cursor()
-		};
-
-		(cursor, self.clone())
-	}
+	fn cursor(&self) -> Self::Cursor { HashValCursor { cursor: self.layer.cursor() } }
 	fn len(&self) -> usize { <HashedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn statistics(&self) -> Statistics {
+		let vals = &self.layer.vals;
+		Statistics {
+			batches: if self.is_empty() { 0 } else { 1 },
+			// Empty slots of the table repeat a neighbouring key, but have empty ranges.
+			batch_keys: self.layer.offs.windows(2).filter(|range| range[0] < range[1]).count(),
+			batch_vals: vals.keys.len(),
+			updates: vals.vals.vals.len(),
+			heap_bytes:
+				self.layer.keys.capacity() * size_of::<K>() +
+				self.layer.offs.capacity() * size_of::<usize>() +
+				vals.keys.capacity() * size_of::<V>() +
+				vals.offs.capacity() * size_of::<usize>() +
+				vals.vals.vals.capacity() * size_of::<(T, R)>(),
+		}
+	}
 }
 
-impl<K, V, T, R> Batch<K, V, T, R> for Rc<HashValBatch<K, V, T, R>>
-where K: Clone+Default+HashOrdered+'static, V: Clone+Ord+'static, T: Lattice+Ord+Clone+Default+'static, R: Diff {
+impl<K, V, T, R> Batch<K, V, T, R> for HashValBatch<K, V, T, R>
+where
+	K: HashOrdered+RadixKey+Clone+'static,
+	V: Ord+Clone+'static,
+	T: Lattice+timely::progress::Timestamp+Ord+Clone+::std::fmt::Debug+'static,
+	R: Semigroup,
+{
 	type Batcher = RadixBatcher<K, V, T, R, Self>;
 	type Builder = HashValBuilder<K, V, T, R>;
-	fn merge(&self, other: &Self) -> Self {
+	type Merger = HashValMerger<K, V, T, R>;
+
+	fn begin_merge(&self, other: &Self, compaction_frontier: Option<AntichainRef<T>>) -> Self::Merger {
+		HashValMerger::new(self, other, compaction_frontier)
+	}
+}
+
+/// State for an in-progress merge.
+pub struct HashValMerger<K, V, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	V: Ord+Clone+'static,
+	T: Lattice+Ord+Clone+::std::fmt::Debug+'static,
+	R: Semigroup,
+{
+	// first batch, and position therein.
+	lower1: usize,
+	upper1: usize,
+	// second batch, and position therein.
+	lower2: usize,
+	upper2: usize,
+	// result that we are currently assembling.
+	result: <HashedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::MergeBuilder,
+	description: Description<T>,
+	should_compact: bool,
+}
+
+impl<K, V, T, R> Merger<K, V, T, R, HashValBatch<K, V, T, R>> for HashValMerger<K, V, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	V: Ord+Clone+'static,
+	T: Lattice+timely::progress::Timestamp+Ord+Clone+::std::fmt::Debug+'static,
+	R: Semigroup,
+{
+	fn new(batch1: &HashValBatch<K, V, T, R>, batch2: &HashValBatch<K, V, T, R>, compaction_frontier: Option<AntichainRef<T>>) -> Self {
+
+		assert!(batch1.upper() == batch2.lower());
+
+		let mut since = batch1.description().since().join(batch2.description().since());
+		if let Some(compaction_frontier) = compaction_frontier {
+			since = since.join(&compaction_frontier.to_owned());
+		}
+
+		let description = Description::new(batch1.lower().clone(), batch2.upper().clone(), since);
+
+		HashValMerger {
+			lower1: 0,
+			upper1: batch1.layer.keys(),
+			lower2: 0,
+			upper2: batch2.layer.keys(),
+			result: <<HashedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::MergeBuilder as MergeBuilder>::with_capacity(&batch1.layer, &batch2.layer),
+			description: description,
+			should_compact: compaction_frontier.is_some(),
+		}
+	}
+	fn done(self) -> HashValBatch<K, V, T, R> {
 
-		// Things are horribly wrong if this is not true.
-		assert!(self.desc.upper() == other.desc.lower());
+		assert!(self.lower1 == self.upper1);
+		assert!(self.lower2 == self.upper2);
 
-		// one of self.desc.since or other.desc.since needs to be not behind the other...
-		let since = if self.desc.since().iter().all(|t1| other.desc.since().iter().any(|t2| t2.less_equal(t1))) {
-			other.desc.since()
+		HashValBatch {
+			layer: self.result.done(),
+			desc: self.description,
 		}
-		else {
-			self.desc.since()
-		};
-		
-		Rc::new(HashValBatch {
-			layer: <HashedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>> as Trie>::merge(&self.layer, &other.layer),
-			desc: Description::new(self.desc.lower(), other.desc.upper(), since),
-		})
+	}
+	fn work(&mut self, source1: &HashValBatch<K,V,T,R>, source2: &HashValBatch<K,V,T,R>, fuel: &mut isize) {
+
+		let starting_updates = self.result.builder.vals.vals.vals.len();
+		let mut effort = 0isize;
+
+		let initial_key_pos = self.result.builder.keys.len();
+
+		// while both mergees are still active
+		while self.lower1 < self.upper1 && self.lower2 < self.upper2 && effort < *fuel {
+			self.result.merge_step((&source1.layer, &mut self.lower1, self.upper1), (&source2.layer, &mut self.lower2, self.upper2));
+			effort = (self.result.builder.vals.vals.vals.len() - starting_updates) as isize;
+		}
+
+		// Merging is complete; only copying remains.
+		if self.lower1 == self.upper1 || self.lower2 == self.upper2 {
+			// Limit merging by remaining fuel.
+			let remaining_fuel = *fuel - effort;
+			if remaining_fuel > 0 {
+				if self.lower1 < self.upper1 {
+					let mut to_copy = remaining_fuel as usize;
+					if to_copy < 1_000 { to_copy = 1_000; }
+					if to_copy > (self.upper1 - self.lower1) { to_copy = self.upper1 - self.lower1; }
+					self.result.copy_range(&source1.layer, self.lower1, self.lower1 + to_copy);
+					self.lower1 += to_copy;
+				}
+				if self.lower2 < self.upper2 {
+					let mut to_copy = remaining_fuel as usize;
+					if to_copy < 1_000 { to_copy = 1_000; }
+					if to_copy > (self.upper2 - self.lower2) { to_copy = self.upper2 - self.lower2; }
+					self.result.copy_range(&source2.layer, self.lower2, self.lower2 + to_copy);
+					self.lower2 += to_copy;
+				}
+			}
+		}
+
+		effort = (self.result.builder.vals.vals.vals.len() - starting_updates) as isize;
+
+		// if we are supplied a frontier, we should compact. The builder is dense, as for ordered batches.
+		if self.should_compact {
+			OrdValBatch::<K, V, T, R>::advance_builder_from(&mut self.result.builder, self.description.since().borrow(), initial_key_pos);
+		}
+
+		*fuel -= effort;
 	}
 }
 
 /// A cursor for navigating a single layer.
 #[derive(Debug)]
-pub struct HashValCursor<V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff> {
+pub struct HashValCursor<V, T, R>
+where
+	V: Ord+Clone,
+	T: Lattice+Ord+Clone,
+	R: Semigroup,
+{
 	cursor: HashedCursor<OrderedLayer<V, OrderedLeaf<T, R>>>,
 }
 
-impl<K, V, T, R> Cursor<K, V, T, R> for HashValCursor<V, T, R> 
-where K: Clone+HashOrdered, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
-
-	type Storage = Rc<HashValBatch<K, V, T, R>>;//OrderedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>>>;
+impl<K, V, T, R> Cursor<K, V, T, R> for HashValCursor<V, T, R>
+where
+	K: HashOrdered+Clone,
+	V: Ord+Clone,
+	T: Lattice+Ord+Clone,
+	R: Semigroup,
+{
+	type Storage = HashValBatch<K, V, T, R>;
 
 	fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { &self.cursor.key(&storage.layer) }
 	fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { &self.cursor.child.key(&storage.layer.vals) }
-	fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, mut logic: L) {
+	fn map_times<L: FnMut(&T, &R)>(&mut self, storage: &Self::Storage, mut logic: L) {
 		self.cursor.child.child.rewind(&storage.layer.vals.vals);
 		while self.cursor.child.child.valid(&storage.layer.vals.vals) {
-			logic(&self.cursor.child.child.key(&storage.layer.vals.vals).0, self.cursor.child.child.key(&storage.layer.vals.vals).1);
+			logic(&self.cursor.child.child.key(&storage.layer.vals.vals).0, &self.cursor.child.child.key(&storage.layer.vals.vals).1);
 			self.cursor.child.child.step(&storage.layer.vals.vals);
 		}
 	}
@@ -104,6 +237,7 @@ where K: Clone+HashOrdered, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
 	fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.child.valid(&storage.layer.vals) }
 	fn step_key(&mut self, storage: &Self::Storage){ self.cursor.step(&storage.layer); }
 	fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek(&storage.layer, key); }
+	fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_where(&storage.layer, before); }
 	fn step_val(&mut self, storage: &Self::Storage) { self.cursor.child.step(&storage.layer.vals); }
 	fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.child.seek(&storage.layer.vals, val); }
 	fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind(&storage.layer); }
@@ -111,22 +245,32 @@ where K: Clone+HashOrdered, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
 }
 
 /// A builder for creating layers from unsorted update tuples.
-pub struct HashValBuilder<K: HashOrdered, V: Ord, T: Ord+Lattice, R: Diff> {
+pub struct HashValBuilder<K, V, T, R>
+where
+	K: Ord,
+	V: Ord,
+	T: Ord+Lattice,
+	R: Semigroup,
+{
 	builder: HashedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>>,
 }
 
-impl<K, V, T, R> Builder<K, V, T, R, Rc<HashValBatch<K, V, T, R>>> for HashValBuilder<K, V, T, R> 
-where K: Clone+Default+HashOrdered+'static, V: Ord+Clone+'static, T: Lattice+Ord+Clone+Default+'static, R: Diff {
-
-	fn new() -> Self { 
-		HashValBuilder { 
-			builder: HashedBuilder::<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>>::new() 
-		} 
+impl<K, V, T, R> Builder<K, V, T, R, HashValBatch<K, V, T, R>> for HashValBuilder<K, V, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	V: Ord+Clone+'static,
+	T: Lattice+timely::progress::Timestamp+Ord+Clone+::std::fmt::Debug+'static,
+	R: Semigroup,
+{
+	fn new() -> Self {
+		HashValBuilder {
+			builder: <HashedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>> as TupleBuilder>::new()
+		}
 	}
-	fn with_capacity(cap: usize) -> Self { 
-		HashValBuilder { 
-			builder: HashedBuilder::<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>>::with_capacity(cap) 
-		} 
+	fn with_capacity(cap: usize) -> Self {
+		HashValBuilder {
+			builder: <HashedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>>> as TupleBuilder>::with_capacity(cap)
+		}
 	}
 
 	#[inline]
@@ -135,11 +279,11 @@ where K: Clone+Default+HashOrdered+'static, V: Ord+Clone+'static, T: Lattice+Ord
 	}
 
 	#[inline(never)]
-	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> Rc<HashValBatch<K, V, T, R>> {
-		Rc::new(HashValBatch {
+	fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> HashValBatch<K, V, T, R> {
+		HashValBatch {
 			layer: self.builder.done(),
 			desc: Description::new(lower, upper, since)
-		})
+		}
 	}
 }
 
@@ -147,79 +291,187 @@ where K: Clone+Default+HashOrdered+'static, V: Ord+Clone+'static, T: Lattice+Ord
 
 
 /// An immutable collection of update tuples, from a contiguous interval of logical times.
-#[derive(Debug)]
-pub struct HashKeyBatch<K: HashOrdered, T: Lattice, R> {
+#[derive(Debug, Abomonation)]
+pub struct HashKeyBatch<K, T, R>
+where
+	T: Lattice,
+{
 	/// Where all the dataz is.
 	pub layer: HashedLayer<K, OrderedLeaf<T, R>>,
 	/// Description of the update times this layer represents.
 	pub desc: Description<T>,
 }
 
-impl<K, T, R> BatchReader<K, (), T, R> for Rc<HashKeyBatch<K, T, R>>
-where K: Clone+Default+HashOrdered+'static, T: Lattice+Ord+Clone+Default+'static, R: Diff {
+impl<K, T, R> BatchReader<K, (), T, R> for HashKeyBatch<K, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	T: Lattice+Ord+Clone+'static,
+	R: Semigroup,
+{
 	type Cursor = HashKeyCursor<T, R>;
-	// fn cursor(&self) -> (Self::Cursor, <Self::Cursor as Cursor<K, V, T, R>>::Storage) { 
-
-	fn cursor(&self) -> (Self::Cursor, <Self::Cursor as Cursor<K, (), T, R>>::Storage) { 
-		let cursor = HashKeyCursor {
+	fn cursor(&self) -> Self::Cursor {
+		HashKeyCursor {
 			empty: (),
 			valid: true,
 			cursor: self.layer.cursor(),
-		};
-		(cursor, self.clone())
-
-		// HashKeyCursor {
-		// 	empty: (),
-		// 	valid: true,
-		// 	cursor: self.layer.cursor(OwningRef::new(self.clone()).map(|x| &x.layer).erase_owner()),
-		// } 
+		}
 	}
 	fn len(&self) -> usize { <HashedLayer<K, OrderedLeaf<T, R>> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn statistics(&self) -> Statistics {
+		let keys = self.layer.offs.windows(2).filter(|range| range[0] < range[1]).count();
+		Statistics {
+			batches: if self.is_empty() { 0 } else { 1 },
+			batch_keys: keys,
+			batch_vals: keys,
+			updates: self.layer.vals.vals.len(),
+			heap_bytes:
+				self.layer.keys.capacity() * size_of::<K>() +
+				self.layer.offs.capacity() * size_of::<usize>() +
+				self.layer.vals.vals.capacity() * size_of::<(T, R)>(),
+		}
+	}
 }
 
-impl<K, T, R> Batch<K, (), T, R> for Rc<HashKeyBatch<K, T, R>>
-where K: Clone+Default+HashOrdered+'static, T: Lattice+Ord+Clone+Default+'static, R: Diff {
+impl<K, T, R> Batch<K, (), T, R> for HashKeyBatch<K, T, R>
+where
+	K: HashOrdered+RadixKey+Clone+'static,
+	T: Lattice+timely::progress::Timestamp+Ord+Clone+'static,
+	R: Semigroup,
+{
 	type Batcher = RadixBatcher<K, (), T, R, Self>;
 	type Builder = HashKeyBuilder<K, T, R>;
-	fn merge(&self, other: &Self) -> Self {
+	type Merger = HashKeyMerger<K, T, R>;
 
-		// Things are horribly wrong if this is not true.
-		assert!(self.desc.upper() == other.desc.lower());
+	fn begin_merge(&self, other: &Self, compaction_frontier: Option<AntichainRef<T>>) -> Self::Merger {
+		HashKeyMerger::new(self, other, compaction_frontier)
+	}
+}
 
-		// one of self.desc.since or other.desc.since needs to be not behind the other...
-		let since = if self.desc.since().iter().all(|t1| other.desc.since().iter().any(|t2| t2.less_equal(t1))) {
-			other.desc.since()
+/// State for an in-progress merge.
+pub struct HashKeyMerger<K, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	T: Lattice+Ord+Clone+'static,
+	R: Semigroup,
+{
+	// first batch, and position therein.
+	lower1: usize,
+	upper1: usize,
+	// second batch, and position therein.
+	lower2: usize,
+	upper2: usize,
+	// result that we are currently assembling.
+	result: <HashedLayer<K, OrderedLeaf<T, R>> as Trie>::MergeBuilder,
+	description: Description<T>,
+	should_compact: bool,
+}
+
+impl<K, T, R> Merger<K, (), T, R, HashKeyBatch<K, T, R>> for HashKeyMerger<K, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	T: Lattice+timely::progress::Timestamp+Ord+Clone+'static,
+	R: Semigroup,
+{
+	fn new(batch1: &HashKeyBatch<K, T, R>, batch2: &HashKeyBatch<K, T, R>, compaction_frontier: Option<AntichainRef<T>>) -> Self {
+
+		assert!(batch1.upper() == batch2.lower());
+
+		let mut since = batch1.description().since().join(batch2.description().since());
+		if let Some(compaction_frontier) = compaction_frontier {
+			since = since.join(&compaction_frontier.to_owned());
+		}
+
+		let description = Description::new(batch1.lower().clone(), batch2.upper().clone(), since);
+
+		HashKeyMerger {
+			lower1: 0,
+			upper1: batch1.layer.keys(),
+			lower2: 0,
+			upper2: batch2.layer.keys(),
+			result: <<HashedLayer<K, OrderedLeaf<T, R>> as Trie>::MergeBuilder as MergeBuilder>::with_capacity(&batch1.layer, &batch2.layer),
+			description: description,
+			should_compact: compaction_frontier.is_some(),
 		}
-		else {
-			self.desc.since()
-		};
-		
-		Rc::new(HashKeyBatch {
-			layer: <HashedLayer<K, OrderedLeaf<T, R>> as Trie>::merge(&self.layer, &other.layer),
-			desc: Description::new(self.desc.lower(), other.desc.upper(), since),
-		})
+	}
+	fn done(self) -> HashKeyBatch<K, T, R> {
+
+		assert!(self.lower1 == self.upper1);
+		assert!(self.lower2 == self.upper2);
+
+		HashKeyBatch {
+			layer: self.result.done(),
+			desc: self.description,
+		}
+	}
+	fn work(&mut self, source1: &HashKeyBatch<K,T,R>, source2: &HashKeyBatch<K,T,R>, fuel: &mut isize) {
+
+		let starting_updates = self.result.builder.vals.vals.len();
+		let mut effort = 0isize;
+
+		let initial_key_pos = self.result.builder.keys.len();
+
+		// while both mergees are still active
+		while self.lower1 < self.upper1 && self.lower2 < self.upper2 && effort < *fuel {
+			self.result.merge_step((&source1.layer, &mut self.lower1, self.upper1), (&source2.layer, &mut self.lower2, self.upper2));
+			effort = (self.result.builder.vals.vals.len() - starting_updates) as isize;
+		}
+
+		// Merging is complete; only copying remains.
+		if self.lower1 == self.upper1 || self.lower2 == self.upper2 {
+			// Limit merging by remaining fuel.
+			let remaining_fuel = *fuel - effort;
+			if remaining_fuel > 0 {
+				if self.lower1 < self.upper1 {
+					let mut to_copy = remaining_fuel as usize;
+					if to_copy < 1_000 { to_copy = 1_000; }
+					if to_copy > (self.upper1 - self.lower1) { to_copy = self.upper1 - self.lower1; }
+					self.result.copy_range(&source1.layer, self.lower1, self.lower1 + to_copy);
+					self.lower1 += to_copy;
+				}
+				if self.lower2 < self.upper2 {
+					let mut to_copy = remaining_fuel as usize;
+					if to_copy < 1_000 { to_copy = 1_000; }
+					if to_copy > (self.upper2 - self.lower2) { to_copy = self.upper2 - self.lower2; }
+					self.result.copy_range(&source2.layer, self.lower2, self.lower2 + to_copy);
+					self.lower2 += to_copy;
+				}
+			}
+		}
+
+		effort = (self.result.builder.vals.vals.len() - starting_updates) as isize;
+
+		// if we are supplied a frontier, we should compact. The builder is dense, as for ordered batches.
+		if self.should_compact {
+			OrdKeyBatch::<K, T, R>::advance_builder_from(&mut self.result.builder, self.description.since().borrow(), initial_key_pos);
+		}
+
+		*fuel -= effort;
 	}
 }
 
 /// A cursor for navigating a single layer.
 #[derive(Debug)]
-pub struct HashKeyCursor<T: Lattice+Ord+Clone, R: Diff> {
+pub struct HashKeyCursor<T: Lattice+Ord+Clone, R: Semigroup> {
 	valid: bool,
 	empty: (),
 	cursor: HashedCursor<OrderedLeaf<T, R>>,
 }
 
-impl<K: Clone+HashOrdered, T: Lattice+Ord+Clone, R: Diff> Cursor<K, (), T, R> for HashKeyCursor<T, R> {
-
-	type Storage = Rc<HashKeyBatch<K, T, R>>;
+impl<K, T, R> Cursor<K, (), T, R> for HashKeyCursor<T, R>
+where
+	K: HashOrdered+Clone,
+	T: Lattice+Ord+Clone,
+	R: Semigroup,
+{
+	type Storage = HashKeyBatch<K, T, R>;
 
 	fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { &self.cursor.key(&storage.layer) }
 	fn val<'a>(&self, _storage: &'a Self::Storage) -> &'a () { unsafe { ::std::mem::transmute(&self.empty) } }
-	fn map_times<L: FnMut(&T, R)>(&mut self, storage: &Self::Storage, mut logic: L) {
+	fn map_times<L: FnMut(&T, &R)>(&mut self, storage: &Self::Storage, mut logic: L) {
 		self.cursor.child.rewind(&storage.layer.vals);
 		while self.cursor.child.valid(&storage.layer.vals) {
-			logic(&self.cursor.child.key(&storage.layer.vals).0, self.cursor.child.key(&storage.layer.vals).1);
+			logic(&self.cursor.child.key(&storage.layer.vals).0, &self.cursor.child.key(&storage.layer.vals).1);
 			self.cursor.child.step(&storage.layer.vals);
 		}
 	}
@@ -227,6 +479,7 @@ impl<K: Clone+HashOrdered, T: Lattice+Ord+Clone, R: Diff> Cursor<K, (), T, R> fo
 	fn val_valid(&self, _storage: &Self::Storage) -> bool { self.valid }
 	fn step_key(&mut self, storage: &Self::Storage){ self.cursor.step(&storage.layer); self.valid = true; }
 	fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek(&storage.layer, key); self.valid = true; }
+	fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_where(&storage.layer, before); self.valid = true; }
 	fn step_val(&mut self, _storage: &Self::Storage) { self.valid = false; }
 	fn seek_val(&mut self, _storage: &Self::Storage, _val: &()) { }
 	fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind(&storage.layer); self.valid = true; }
@@ -234,22 +487,30 @@ impl<K: Clone+HashOrdered, T: Lattice+Ord+Clone, R: Diff> Cursor<K, (), T, R> fo
 }
 
 /// A builder for creating layers from unsorted update tuples.
-pub struct HashKeyBuilder<K: HashOrdered, T: Ord+Lattice, R: Diff> {
+pub struct HashKeyBuilder<K, T, R>
+where
+	K: Ord,
+	T: Ord+Lattice,
+	R: Semigroup,
+{
 	builder: HashedBuilder<K, OrderedLeafBuilder<T, R>>,
 }
 
-impl<K, T, R> Builder<K, (), T, R, Rc<HashKeyBatch<K, T, R>>> for HashKeyBuilder<K, T, R> 
-where K: Clone+Default+HashOrdered+'static, T: Lattice+Ord+Clone+Default+'static, R: Diff {
-
-	fn new() -> Self { 
-		HashKeyBuilder { 
-			builder: HashedBuilder::<K, OrderedLeafBuilder<T, R>>::new() 
-		} 
+impl<K, T, R> Builder<K, (), T, R, HashKeyBatch<K, T, R>> for HashKeyBuilder<K, T, R>
+where
+	K: HashOrdered+Clone+'static,
+	T: Lattice+timely::progress::Timestamp+Ord+Clone+'static,
+	R: Semigroup,
+{
+	fn new() -> Self {
+		HashKeyBuilder {
+			builder: <HashedBuilder<K, OrderedLeafBuilder<T, R>> as TupleBuilder>::new()
+		}
 	}
-	fn with_capacity(cap: usize) -> Self { 
-		HashKeyBuilder { 
-			builder: HashedBuilder::<K, OrderedLeafBuilder<T, R>>::with_capacity(cap) 
-		} 
+	fn with_capacity(cap: usize) -> Self {
+		HashKeyBuilder {
+			builder: <HashedBuilder<K, OrderedLeafBuilder<T, R>> as TupleBuilder>::with_capacity(cap)
+		}
 	}
 
 	#[inline]
@@ -258,11 +519,10 @@ where K: Clone+Default+HashOrdered+'static, T: Lattice+Ord+Clone+Default+'static
 	}
 
 	#[inline(never)]
-	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> Rc<HashKeyBatch<K, T, R>> {
-		Rc::new(HashKeyBatch {
+	fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> HashKeyBatch<K, T, R> {
+		HashKeyBatch {
 			layer: self.builder.done(),
 			desc: Description::new(lower, upper, since)
-		})
+		}
 	}
 }
-
@@ -0,0 +1,329 @@
+//! Batches whose keys and values are interned in a per-worker dictionary.
+//!
+//! An `InternedBatch<B>` presents updates with keys `K` and values `V`, but stores them in a batch `B`
+//! of `Interned<K>` keys and `Interned<V>` values. Each worker keeps one `Interner` for each interned type,
+//! and all of its batches share the single copy of each distinct key and value the interner holds. Merges
+//! copy handles rather than keys and values, and compare handles by address before comparing contents.
+//!
+//! Batches remain ordered by the contents of their keys and values, and cursors lend `&K` and `&V`, so
+//! interned traces are interchangeable with other traces. Interners release entries no batch references
+//! as they grow, and the bytes of interned keys and values are not reported in batch statistics.
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+use timely::progress::{Antichain, frontier::AntichainRef};
+
+use trace::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description, Statistics};
+
+use super::spine_fueled::Spine;
+use super::ord::OrdValBatch;
+
+/// A trace implementation using a spine of ordered lists, with interned keys and values.
+pub type InternedValSpine<K, V, T, R> = Spine<K, V, T, R, Rc<InternedBatch<OrdValBatch<Interned<K>, Interned<V>, T, R>>>>;
+
+/// A shared handle to an interned key or value.
+///
+/// Handles from the same interner are equal exactly when they share an address, and otherwise handles
+/// compare by their contents.
+pub struct Interned<T> {
+    value: Rc<T>,
+}
+
+impl<T> Interned<T> {
+    /// Wraps a value without interning it, for comparison with interned handles.
+    fn transient(value: T) -> Self { Interned { value: Rc::new(value) } }
+}
+
+impl<T> Clone for Interned<T> {
+    fn clone(&self) -> Self { Interned { value: self.value.clone() } }
+}
+
+impl<T> Deref for Interned<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &*self.value }
+}
+
+impl<T: PartialEq> PartialEq for Interned<T> {
+    fn eq(&self, other: &Self) -> bool { Rc::ptr_eq(&self.value, &other.value) || self.value == other.value }
+}
+
+impl<T: Eq> Eq for Interned<T> { }
+
+impl<T: PartialOrd> PartialOrd for Interned<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if Rc::ptr_eq(&self.value, &other.value) { Some(Ordering::Equal) }
+        else { self.value.partial_cmp(&other.value) }
+    }
+}
+
+impl<T: Ord> Ord for Interned<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if Rc::ptr_eq(&self.value, &other.value) { Ordering::Equal }
+        else { self.value.cmp(&other.value) }
+    }
+}
+
+impl<T: Hash> Hash for Interned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.value.hash(state) }
+}
+
+impl<T: Debug> Debug for Interned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { self.value.fmt(f) }
+}
+
+/// The least number of entries at which an interner releases unreferenced entries.
+const COLLECT_THRESHOLD: usize = 1 << 10;
+
+thread_local! {
+    /// The interners of the current worker, by interned type.
+    static INTERNERS: RefCell<HashMap<TypeId, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// A dictionary of distinct values, shared by the batches of a worker.
+pub struct Interner<T> {
+    values: HashSet<Rc<T>>,
+    // size at which to next release unreferenced entries.
+    threshold: usize,
+}
+
+impl<T: Eq+Hash+'static> Interner<T> {
+    /// Allocates an empty interner.
+    pub fn new() -> Self {
+        Interner {
+            values: HashSet::new(),
+            threshold: COLLECT_THRESHOLD,
+        }
+    }
+
+    /// The interner of the current worker.
+    pub fn local() -> Rc<RefCell<Self>> {
+        INTERNERS.with(|interners| {
+            interners
+                .borrow_mut()
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| Box::new(Rc::new(RefCell::new(Self::new()))))
+                .downcast_ref::<Rc<RefCell<Self>>>()
+                .expect("interner of unexpected type")
+                .clone()
+        })
+    }
+
+    /// The handle to `value`, interning it if it is not yet present.
+    pub fn intern(&mut self, value: T) -> Interned<T> {
+        if let Some(present) = self.values.get(&value) {
+            return Interned { value: present.clone() };
+        }
+        if self.values.len() >= self.threshold {
+            self.collect();
+        }
+        let value = Rc::new(value);
+        self.values.insert(value.clone());
+        Interned { value }
+    }
+
+    /// The handle to `value`, if it is present.
+    pub fn lookup(&self, value: &T) -> Option<Interned<T>> {
+        self.values.get(value).map(|present| Interned { value: present.clone() })
+    }
+
+    /// The number of distinct values present.
+    pub fn len(&self) -> usize { self.values.len() }
+
+    /// Releases values referenced only by the interner.
+    pub fn collect(&mut self) {
+        self.values.retain(|value| Rc::strong_count(value) > 1);
+        self.threshold = ::std::cmp::max(2 * self.values.len(), COLLECT_THRESHOLD);
+    }
+}
+
+/// A handle suitable for comparison with those of the current worker's interner.
+fn probe<T: Eq+Hash+Clone+'static>(value: &T) -> Interned<T> {
+    let interner = Interner::<T>::local();
+    let found = interner.borrow().lookup(value);
+    found.unwrap_or_else(|| Interned::transient(value.clone()))
+}
+
+/// A batch whose keys and values are interned.
+pub struct InternedBatch<B> {
+    batch: B,
+}
+
+impl<B> InternedBatch<B> {
+    /// The batch of interned keys and values.
+    pub fn inner(&self) -> &B { &self.batch }
+}
+
+impl<K, V, T, R, B: BatchReader<Interned<K>, Interned<V>, T, R>> BatchReader<K, V, T, R> for InternedBatch<B>
+where
+    K: Eq+Hash+Clone+'static,
+    V: Eq+Hash+Clone+'static,
+{
+    /// The type used to enumerate the batch's contents.
+    type Cursor = InternedCursor<K, V, T, R, B>;
+    /// Acquires a cursor to the batch's contents.
+    fn cursor(&self) -> Self::Cursor {
+        InternedCursor::new(self.batch.cursor())
+    }
+
+    /// The number of updates in the batch.
+    fn len(&self) -> usize { self.batch.len() }
+    /// Describes the times of the updates in the batch.
+    fn description(&self) -> &Description<T> { self.batch.description() }
+    /// Reports the size of the batch, excluding the interned keys and values.
+    fn statistics(&self) -> Statistics { self.batch.statistics() }
+}
+
+/// A cursor over a batch of interned keys and values.
+pub struct InternedCursor<K, V, T, R, B: BatchReader<Interned<K>, Interned<V>, T, R>> {
+    phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+    cursor: B::Cursor,
+}
+
+impl<K, V, T, R, B: BatchReader<Interned<K>, Interned<V>, T, R>> InternedCursor<K, V, T, R, B> {
+    fn new(cursor: B::Cursor) -> Self {
+        InternedCursor {
+            cursor,
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<K, V, T, R, B> Cursor<K, V, T, R> for InternedCursor<K, V, T, R, B>
+where
+    K: Eq+Hash+Clone+'static,
+    V: Eq+Hash+Clone+'static,
+    B: BatchReader<Interned<K>, Interned<V>, T, R>,
+{
+    type Storage = InternedBatch<B>;
+
+    #[inline] fn key_valid(&self, storage: &Self::Storage) -> bool { self.cursor.key_valid(&storage.batch) }
+    #[inline] fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.val_valid(&storage.batch) }
+
+    #[inline] fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { &**self.cursor.key(&storage.batch) }
+    #[inline] fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { &**self.cursor.val(&storage.batch) }
+
+    #[inline]
+    fn map_times<L: FnMut(&T, &R)>(&mut self, storage: &Self::Storage, logic: L) {
+        self.cursor.map_times(&storage.batch, logic)
+    }
+
+    #[inline] fn step_key(&mut self, storage: &Self::Storage) { self.cursor.step_key(&storage.batch) }
+    #[inline] fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek_key(&storage.batch, &probe(key)) }
+    #[inline] fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_key_with(&storage.batch, |key| before(&**key)) }
+
+    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(&storage.batch) }
+    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(&storage.batch, &probe(val)) }
+
+    #[inline] fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind_keys(&storage.batch) }
+    #[inline] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(&storage.batch) }
+}
+
+/// An immutable collection of updates.
+impl<K, V, T, R, B: Batch<Interned<K>, Interned<V>, T, R>> Batch<K, V, T, R> for InternedBatch<B>
+where
+    K: Eq+Hash+Clone+'static,
+    V: Eq+Hash+Clone+'static,
+{
+    type Batcher = InternedBatcher<K, V, T, R, B>;
+    type Builder = InternedBuilder<K, V, T, R, B>;
+    type Merger = InternedMerger<K, V, T, R, B>;
+}
+
+/// Wrapper type for batching updates into batches of interned keys and values.
+pub struct InternedBatcher<K, V, T, R, B: Batch<Interned<K>, Interned<V>, T, R>> {
+    batcher: B::Batcher,
+    keys: Rc<RefCell<Interner<K>>>,
+    vals: Rc<RefCell<Interner<V>>>,
+    buffer: Vec<((Interned<K>, Interned<V>), T, R)>,
+}
+
+/// Functionality for collecting and batching updates.
+impl<K, V, T, R, B> Batcher<K, V, T, R, InternedBatch<B>> for InternedBatcher<K, V, T, R, B>
+where
+    K: Eq+Hash+Clone+'static,
+    V: Eq+Hash+Clone+'static,
+    B: Batch<Interned<K>, Interned<V>, T, R>,
+{
+    fn new() -> Self {
+        InternedBatcher {
+            batcher: <B::Batcher as Batcher<Interned<K>, Interned<V>, T, R, B>>::new(),
+            keys: Interner::local(),
+            vals: Interner::local(),
+            buffer: Vec::new(),
+        }
+    }
+    fn push_batch(&mut self, batch: &mut Vec<((K, V), T, R)>) {
+        let mut keys = self.keys.borrow_mut();
+        let mut vals = self.vals.borrow_mut();
+        self.buffer.extend(batch.drain(..).map(|((key, val), time, diff)| ((keys.intern(key), vals.intern(val)), time, diff)));
+        self.batcher.push_batch(&mut self.buffer);
+        self.buffer.clear();
+    }
+    fn seal(&mut self, upper: Antichain<T>) -> InternedBatch<B> { InternedBatch { batch: self.batcher.seal(upper) } }
+    fn frontier(&mut self) -> AntichainRef<T> { self.batcher.frontier() }
+}
+
+/// Wrapper type for building batches of interned keys and values.
+pub struct InternedBuilder<K, V, T, R, B: Batch<Interned<K>, Interned<V>, T, R>> {
+    builder: B::Builder,
+    keys: Rc<RefCell<Interner<K>>>,
+    vals: Rc<RefCell<Interner<V>>>,
+}
+
+/// Functionality for building batches from ordered update sequences.
+impl<K, V, T, R, B> Builder<K, V, T, R, InternedBatch<B>> for InternedBuilder<K, V, T, R, B>
+where
+    K: Eq+Hash+Clone+'static,
+    V: Eq+Hash+Clone+'static,
+    B: Batch<Interned<K>, Interned<V>, T, R>,
+{
+    fn new() -> Self { Self::with_capacity(0) }
+    fn with_capacity(cap: usize) -> Self {
+        InternedBuilder {
+            builder: <B::Builder as Builder<Interned<K>, Interned<V>, T, R, B>>::with_capacity(cap),
+            keys: Interner::local(),
+            vals: Interner::local(),
+        }
+    }
+    fn push(&mut self, (key, val, time, diff): (K, V, T, R)) {
+        let key = self.keys.borrow_mut().intern(key);
+        let val = self.vals.borrow_mut().intern(val);
+        self.builder.push((key, val, time, diff))
+    }
+    fn push_ordered(&mut self, (key, val, time, diff): (K, V, T, R)) {
+        let key = self.keys.borrow_mut().intern(key);
+        let val = self.vals.borrow_mut().intern(val);
+        self.builder.push_ordered((key, val, time, diff))
+    }
+    fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> InternedBatch<B> {
+        InternedBatch { batch: self.builder.done(lower, upper, since) }
+    }
+}
+
+/// Wrapper type for merging batches of interned keys and values.
+pub struct InternedMerger<K, V, T, R, B: Batch<Interned<K>, Interned<V>, T, R>> { merger: B::Merger }
+
+/// Represents a merge in progress.
+impl<K, V, T, R, B> Merger<K, V, T, R, InternedBatch<B>> for InternedMerger<K, V, T, R, B>
+where
+    K: Eq+Hash+Clone+'static,
+    V: Eq+Hash+Clone+'static,
+    B: Batch<Interned<K>, Interned<V>, T, R>,
+{
+    fn new(source1: &InternedBatch<B>, source2: &InternedBatch<B>, compaction_frontier: Option<AntichainRef<T>>) -> Self {
+        InternedMerger { merger: source1.batch.begin_merge(&source2.batch, compaction_frontier) }
+    }
+    fn work(&mut self, source1: &InternedBatch<B>, source2: &InternedBatch<B>, fuel: &mut isize) {
+        self.merger.work(&source1.batch, &source2.batch, fuel)
+    }
+    fn done(self) -> InternedBatch<B> {
+        InternedBatch { batch: self.merger.done() }
+    }
+}
@@ -45,6 +45,12 @@ pub use self::spine_fueled_neu as spine_fueled;
 mod merge_batcher;
 
 pub use self::merge_batcher::MergeBatcher as Batcher;
+pub mod radix_batcher;
 
 pub mod ord;
-// pub mod hash;
+pub mod hash;
+pub mod interned;
+
+pub mod checkpoint;
+pub mod spill;
+pub mod background;
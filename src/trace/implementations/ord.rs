@@ -12,6 +12,7 @@ use std::rc::Rc;
 use std::convert::{TryFrom, TryInto};
 use std::marker::PhantomData;
 use std::fmt::Debug;
+use std::mem::size_of;
 
 use timely::progress::{Antichain, frontier::AntichainRef};
 
@@ -23,7 +24,7 @@ use trace::layers::Builder as TrieBuilder;
 use trace::layers::Cursor as TrieCursor;
 use trace::layers::ordered::{OrdOffset, OrderedLayer, OrderedBuilder, OrderedCursor};
 use trace::layers::ordered_leaf::{OrderedLeaf, OrderedLeafBuilder};
-use trace::{Batch, BatchReader, Builder, Merger, Cursor};
+use trace::{Batch, BatchReader, Builder, Merger, Cursor, Statistics};
 use trace::description::Description;
 
 use trace::layers::MergeBuilder;
@@ -31,6 +32,9 @@ use trace::layers::MergeBuilder;
 // use super::spine::Spine;
 use super::spine_fueled::Spine;
 use super::merge_batcher::MergeBatcher;
+use super::spill::SpillBatch;
+use super::background::BackgroundBatch;
+use super::radix_batcher::RadixBatch;
 
 use abomonation::abomonated::Abomonated;
 
@@ -40,12 +44,34 @@ pub type OrdValSpine<K, V, T, R, O=usize> = Spine<K, V, T, R, Rc<OrdValBatch<K,
 /// A trace implementation using a spine of abomonated ordered lists.
 pub type OrdValSpineAbom<K, V, T, R, O=usize> = Spine<K, V, T, R, Rc<Abomonated<OrdValBatch<K, V, T, R, O>, Vec<u8>>>>;
 
+/// A trace implementation using a spine of ordered lists, spilling large merged batches to disk.
+pub type OrdValSpineSpill<K, V, T, R, O=usize> = Spine<K, V, T, R, SpillBatch<OrdValBatch<K, V, T, R, O>>>;
+
+/// A trace implementation using a spine of ordered lists, merging large batches on helper threads.
+pub type OrdValSpineBackground<K, V, T, R, O=usize> = Spine<K, V, T, R, BackgroundBatch<OrdValBatch<K, V, T, R, O>>>;
+
+/// A trace implementation using a spine of ordered lists, whose updates are batched by radix sorting.
+///
+/// Keys must implement `RadixKey`, as unsigned integers and `UnsignedWrapper` and `OrdWrapper` keys do.
+pub type OrdValSpineRadix<K, V, T, R, O=usize> = Spine<K, V, T, R, Rc<RadixBatch<OrdValBatch<K, V, T, R, O>>>>;
+
 /// A trace implementation for empty values using a spine of ordered lists.
 pub type OrdKeySpine<K, T, R, O=usize> = Spine<K, (), T, R, Rc<OrdKeyBatch<K, T, R, O>>>;
 
 /// A trace implementation for empty values using a spine of abomonated ordered lists.
 pub type OrdKeySpineAbom<K, T, R, O=usize> = Spine<K, (), T, R, Rc<Abomonated<OrdKeyBatch<K, T, R, O>, Vec<u8>>>>;
 
+/// A trace implementation for empty values using a spine of ordered lists, spilling large merged batches to disk.
+pub type OrdKeySpineSpill<K, T, R, O=usize> = Spine<K, (), T, R, SpillBatch<OrdKeyBatch<K, T, R, O>>>;
+
+/// A trace implementation for empty values using a spine of ordered lists, merging large batches on helper threads.
+pub type OrdKeySpineBackground<K, T, R, O=usize> = Spine<K, (), T, R, BackgroundBatch<OrdKeyBatch<K, T, R, O>>>;
+
+/// A trace implementation for empty values using a spine of ordered lists, whose updates are batched by radix sorting.
+///
+/// Keys must implement `RadixKey`, as unsigned integers and `UnsignedWrapper` and `OrdWrapper` keys do.
+pub type OrdKeySpineRadix<K, T, R, O=usize> = Spine<K, (), T, R, Rc<RadixBatch<OrdKeyBatch<K, T, R, O>>>>;
+
 
 /// An immutable collection of update tuples, from a contiguous interval of logical times.
 #[derive(Debug, Abomonation)]
@@ -74,6 +100,21 @@ where
 	fn cursor(&self) -> Self::Cursor { OrdValCursor { cursor: self.layer.cursor() } }
 	fn len(&self) -> usize { <OrderedLayer<K, OrderedLayer<V, OrderedLeaf<T, R>, O>, O> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn statistics(&self) -> Statistics {
+		let vals = &self.layer.vals;
+		Statistics {
+			batches: if self.is_empty() { 0 } else { 1 },
+			batch_keys: self.layer.keys.len(),
+			batch_vals: vals.keys.len(),
+			updates: vals.vals.vals.len(),
+			heap_bytes:
+				self.layer.keys.capacity() * size_of::<K>() +
+				self.layer.offs.capacity() * size_of::<O>() +
+				vals.keys.capacity() * size_of::<V>() +
+				vals.offs.capacity() * size_of::<O>() +
+				vals.vals.vals.capacity() * size_of::<(T, R)>(),
+		}
+	}
 }
 
 impl<K, V, T, R, O> Batch<K, V, T, R> for OrdValBatch<K, V, T, R, O>
@@ -101,7 +142,7 @@ where
     R: Semigroup,
     O: OrdOffset, <O as TryFrom<usize>>::Error: Debug, <O as TryInto<usize>>::Error: Debug
 {
-	fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>, O>, O>, frontier: AntichainRef<T>, key_pos: usize) {
+	pub(crate) fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedBuilder<V, OrderedLeafBuilder<T, R>, O>, O>, frontier: AntichainRef<T>, key_pos: usize) {
 
 		let key_start = key_pos;
 		let val_start: usize = layer.offs[key_pos].try_into().unwrap();
@@ -339,6 +380,7 @@ where
 	fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.child.valid(&storage.layer.vals) }
 	fn step_key(&mut self, storage: &Self::Storage){ self.cursor.step(&storage.layer); }
 	fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek(&storage.layer, key); }
+	fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_where(&storage.layer, before); }
 	fn step_val(&mut self, storage: &Self::Storage) { self.cursor.child.step(&storage.layer.vals); }
 	fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.child.seek(&storage.layer.vals, val); }
 	fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind(&storage.layer); }
@@ -383,6 +425,19 @@ where
 		self.builder.push_tuple((key, (val, (time, diff))));
 	}
 
+	#[inline]
+	fn push_ordered(&mut self, (key, val, time, diff): (K, V, T, R)) {
+		debug_assert!(!diff.is_zero(), "push_ordered: zero difference");
+		debug_assert!({
+			let vals = &self.builder.vals;
+			match (self.builder.keys.last(), vals.keys.last(), vals.vals.vals.last()) {
+				(Some(k), Some(v), Some((t, _))) => (k, v, t) < (&key, &val, &time),
+				_ => true,
+			}
+		}, "push_ordered: update out of order");
+		self.builder.push_tuple((key, (val, (time, diff))));
+	}
+
 	#[inline(never)]
 	fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> OrdValBatch<K, V, T, R, O> {
 		OrdValBatch {
@@ -390,6 +445,58 @@ where
 			desc: Description::new(lower, upper, since)
 		}
 	}
+
+	#[inline(never)]
+	fn from_ordered<I: IntoIterator<Item=(K,V,T,R)>>(updates: I, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> OrdValBatch<K, V, T, R, O> {
+
+		let updates = updates.into_iter();
+
+		let mut keys = Vec::new();
+		let mut key_offs = vec![O::try_from(0).unwrap()];
+		let mut vals = Vec::new();
+		let mut val_offs = vec![O::try_from(0).unwrap()];
+		let mut times = Vec::with_capacity(updates.size_hint().0);
+
+		// Offsets are written as each key and value is closed, rather than fixed up afterwards.
+		for (key, val, time, diff) in updates {
+			debug_assert!(!diff.is_zero(), "from_ordered: zero difference");
+			if keys.last() != Some(&key) {
+				debug_assert!(keys.last().map(|k| k < &key).unwrap_or(true), "from_ordered: keys out of order");
+				if !keys.is_empty() {
+					val_offs.push(O::try_from(times.len()).unwrap());
+					key_offs.push(O::try_from(vals.len()).unwrap());
+				}
+				keys.push(key);
+				vals.push(val);
+			}
+			else if vals.last() != Some(&val) {
+				debug_assert!(vals.last().map(|v| v < &val).unwrap_or(true), "from_ordered: vals out of order");
+				val_offs.push(O::try_from(times.len()).unwrap());
+				vals.push(val);
+			}
+			else {
+				debug_assert!(times.last().map(|(t, _): &(T, R)| t < &time).unwrap_or(true), "from_ordered: times out of order");
+			}
+			times.push((time, diff));
+		}
+		if !keys.is_empty() {
+			val_offs.push(O::try_from(times.len()).unwrap());
+			key_offs.push(O::try_from(vals.len()).unwrap());
+		}
+
+		OrdValBatch {
+			layer: OrderedLayer {
+				keys,
+				offs: key_offs,
+				vals: OrderedLayer {
+					keys: vals,
+					offs: val_offs,
+					vals: OrderedLeaf { vals: times },
+				},
+			},
+			desc: Description::new(lower, upper, since)
+		}
+	}
 }
 
 
@@ -427,6 +534,18 @@ where
 	}
 	fn len(&self) -> usize { <OrderedLayer<K, OrderedLeaf<T, R>, O> as Trie>::tuples(&self.layer) }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn statistics(&self) -> Statistics {
+		Statistics {
+			batches: if self.is_empty() { 0 } else { 1 },
+			batch_keys: self.layer.keys.len(),
+			batch_vals: self.layer.keys.len(),
+			updates: self.layer.vals.vals.len(),
+			heap_bytes:
+				self.layer.keys.capacity() * size_of::<K>() +
+				self.layer.offs.capacity() * size_of::<O>() +
+				self.layer.vals.vals.capacity() * size_of::<(T, R)>(),
+		}
+	}
 }
 
 impl<K, T, R, O> Batch<K, (), T, R> for OrdKeyBatch<K, T, R, O>
@@ -452,7 +571,7 @@ where
     R: Semigroup,
     O: OrdOffset, <O as TryFrom<usize>>::Error: Debug, <O as TryInto<usize>>::Error: Debug
 {
-	fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedLeafBuilder<T, R>, O>, frontier: AntichainRef<T>, key_pos: usize) {
+	pub(crate) fn advance_builder_from(layer: &mut OrderedBuilder<K, OrderedLeafBuilder<T, R>, O>, frontier: AntichainRef<T>, key_pos: usize) {
 
 		let key_start = key_pos;
 		let time_start: usize = layer.offs[key_pos].try_into().unwrap();
@@ -662,6 +781,7 @@ where
 	fn val_valid(&self, _storage: &Self::Storage) -> bool { self.valid }
 	fn step_key(&mut self, storage: &Self::Storage){ self.cursor.step(&storage.layer); self.valid = true; }
 	fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek(&storage.layer, key); self.valid = true; }
+	fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_where(&storage.layer, before); self.valid = true; }
 	fn step_val(&mut self, _storage: &Self::Storage) { self.valid = false; }
 	fn seek_val(&mut self, _storage: &Self::Storage, _val: &()) { }
 	fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind(&storage.layer); self.valid = true; }
@@ -705,6 +825,18 @@ where
 		self.builder.push_tuple((key, (time, diff)));
 	}
 
+	#[inline]
+	fn push_ordered(&mut self, (key, _, time, diff): (K, (), T, R)) {
+		debug_assert!(!diff.is_zero(), "push_ordered: zero difference");
+		debug_assert!({
+			match (self.builder.keys.last(), self.builder.vals.vals.last()) {
+				(Some(k), Some((t, _))) => (k, t) < (&key, &time),
+				_ => true,
+			}
+		}, "push_ordered: update out of order");
+		self.builder.push_tuple((key, (time, diff)));
+	}
+
 	#[inline(never)]
 	fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> OrdKeyBatch<K, T, R, O> {
 		OrdKeyBatch {
@@ -712,4 +844,41 @@ where
 			desc: Description::new(lower, upper, since)
 		}
 	}
+
+	#[inline(never)]
+	fn from_ordered<I: IntoIterator<Item=(K,(),T,R)>>(updates: I, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> OrdKeyBatch<K, T, R, O> {
+
+		let updates = updates.into_iter();
+
+		let mut keys = Vec::new();
+		let mut key_offs = vec![O::try_from(0).unwrap()];
+		let mut times = Vec::with_capacity(updates.size_hint().0);
+
+		for (key, _, time, diff) in updates {
+			debug_assert!(!diff.is_zero(), "from_ordered: zero difference");
+			if keys.last() != Some(&key) {
+				debug_assert!(keys.last().map(|k| k < &key).unwrap_or(true), "from_ordered: keys out of order");
+				if !keys.is_empty() {
+					key_offs.push(O::try_from(times.len()).unwrap());
+				}
+				keys.push(key);
+			}
+			else {
+				debug_assert!(times.last().map(|(t, _): &(T, R)| t < &time).unwrap_or(true), "from_ordered: times out of order");
+			}
+			times.push((time, diff));
+		}
+		if !keys.is_empty() {
+			key_offs.push(O::try_from(times.len()).unwrap());
+		}
+
+		OrdKeyBatch {
+			layer: OrderedLayer {
+				keys,
+				offs: key_offs,
+				vals: OrderedLeaf { vals: times },
+			},
+			desc: Description::new(lower, upper, since)
+		}
+	}
 }
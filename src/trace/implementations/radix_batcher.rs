@@ -0,0 +1,319 @@
+//! A `Batcher` implementation based on radix sorting by key.
+//!
+//! The `RadixBatcher` sorts updates by an unsigned integer derived from their keys, using as many
+//! byte-wide passes as the integer type has bytes, and only then compares updates with equal integers.
+//! It requires keys that implement `RadixKey`, whose `Ord` implementation orders first by this integer,
+//! so that the sorted output is in the order batch builders expect. Unsigned integers are their own
+//! radix, and all `HashOrdered` keys, such as those wrapped in `OrdWrapper`, use their hash; keys wrapped
+//! in `UnsignedWrapper` hash to themselves, and so are radix sorted by their own value in as few passes
+//! as their width requires.
+//!
+//! Batch types select their batcher, and `RadixBatch` wraps a batch type to use a `RadixBatcher`
+//! while leaving its representation, building, and merging unchanged.
+
+use std::mem::size_of;
+
+use timely::progress::{Antichain, frontier::AntichainRef};
+use timely_sort::Unsigned;
+
+use ::difference::Semigroup;
+use lattice::Lattice;
+use hashable::{Hashable, HashOrdered};
+use consolidation::consolidate_updates_slice;
+use trace::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description, Statistics};
+
+/// Keys ordered first by an unsigned integer derived from them, by which they may be radix sorted.
+///
+/// Types implementing this trait *must* satisfy the property that two keys with different radixes
+/// have the same order as their radixes.
+pub trait RadixKey : Ord {
+    /// The type of the radix.
+    type Radix: Unsigned+Copy;
+    /// The unsigned integer by which the key is first ordered.
+    fn radix(&self) -> Self::Radix;
+}
+
+macro_rules! implement_radix_key {
+    ($($index_type:ty,)*) => (
+        $(
+            impl RadixKey for $index_type {
+                type Radix = $index_type;
+                #[inline] fn radix(&self) -> Self::Radix { *self }
+            }
+        )*
+    )
+}
+
+implement_radix_key!(u8, u16, u32, u64, usize,);
+
+// Hash-ordered keys are ordered first by their hash, and so every key that could form a batch by radix
+// sorting before `RadixKey` was introduced still can.
+impl<K: HashOrdered> RadixKey for K {
+    type Radix = <K as Hashable>::Output;
+    #[inline] fn radix(&self) -> Self::Radix { self.hashed() }
+}
+
+/// The number of pending updates above which `push_batch` consolidates them.
+const CONSOLIDATE_THRESHOLD: usize = 1 << 16;
+
+/// Creates batches from unordered tuples, by radix sorting on keys.
+pub struct RadixBatcher<K, V, T, R, B> {
+    pending: Vec<((K, V), T, R)>,
+    radixed: Vec<(u64, ((K, V), T, R))>,
+    buckets: Vec<Vec<(u64, ((K, V), T, R))>>,
+    consolidated: usize,
+    lower: Antichain<T>,
+    frontier: Antichain<T>,
+    phantom: ::std::marker::PhantomData<B>,
+}
+
+impl<K, V, T, R, B> RadixBatcher<K, V, T, R, B>
+where
+    K: RadixKey,
+    V: Ord,
+    T: Ord,
+    R: Semigroup,
+{
+    /// Sorts `updates` by key radix, then consolidates runs of equal radix.
+    fn sort_and_consolidate(&mut self, updates: &mut Vec<((K, V), T, R)>) {
+
+        if self.buckets.is_empty() {
+            self.buckets = (0 .. 256).map(|_| Vec::new()).collect();
+        }
+
+        // The radix of each key is determined once, and travels with its update through the passes.
+        let radixed = &mut self.radixed;
+        radixed.extend(updates.drain(..).map(|update| ((update.0).0.radix().as_u64(), update)));
+
+        // Least significant byte first; passes in which all updates share a byte are skipped.
+        for pass in 0 .. size_of::<<K as RadixKey>::Radix>() {
+            let shift = 8 * pass;
+            let mut counts = [0usize; 256];
+            for &(radix, _) in radixed.iter() {
+                counts[((radix >> shift) & 0xFF) as usize] += 1;
+            }
+            if counts.iter().any(|&count| count == radixed.len()) {
+                continue;
+            }
+            for (bucket, &count) in self.buckets.iter_mut().zip(counts.iter()) {
+                bucket.reserve(count);
+            }
+            for (radix, update) in radixed.drain(..) {
+                self.buckets[((radix >> shift) & 0xFF) as usize].push((radix, update));
+            }
+            for bucket in self.buckets.iter_mut() {
+                radixed.extend(bucket.drain(..));
+            }
+        }
+
+        // Updates with equal radixes are sorted and consolidated as they are moved back.
+        let mut start = 0;
+        let mut current = None;
+        for (radix, update) in radixed.drain(..) {
+            if current != Some(radix) {
+                let length = consolidate_updates_slice(&mut updates[start ..]);
+                updates.truncate(start + length);
+                start = updates.len();
+                current = Some(radix);
+            }
+            updates.push(update);
+        }
+        let length = consolidate_updates_slice(&mut updates[start ..]);
+        updates.truncate(start + length);
+    }
+}
+
+impl<K, V, T, R, B> Batcher<K, V, T, R, B> for RadixBatcher<K, V, T, R, B>
+where
+    K: RadixKey+Clone,
+    V: Ord+Clone,
+    T: Lattice+timely::progress::Timestamp+Ord+Clone,
+    R: Semigroup,
+    B: Batch<K, V, T, R>,
+{
+    fn new() -> Self {
+        RadixBatcher {
+            pending: Vec::new(),
+            radixed: Vec::new(),
+            buckets: Vec::new(),
+            consolidated: 0,
+            lower: Antichain::from_elem(T::minimum()),
+            frontier: Antichain::new(),
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+
+    #[inline(never)]
+    fn push_batch(&mut self, batch: &mut Vec<((K,V),T,R)>) {
+        self.pending.extend(batch.drain(..));
+        // Bound the footprint of pending updates by consolidating when they have doubled.
+        if self.pending.len() > CONSOLIDATE_THRESHOLD && self.pending.len() > 2 * self.consolidated {
+            let mut pending = ::std::mem::replace(&mut self.pending, Vec::new());
+            self.sort_and_consolidate(&mut pending);
+            self.consolidated = pending.len();
+            self.pending = pending;
+        }
+    }
+
+    // Sealing a batch means finding those updates with times not greater or equal to any time
+    // in `upper`. All updates must have time greater or equal to the previously used `upper`,
+    // which we call `lower`, by assumption that after sealing a batcher we receive no more
+    // updates with times not greater or equal to `upper`.
+    #[inline(never)]
+    fn seal(&mut self, upper: Antichain<T>) -> B {
+
+        self.frontier.clear();
+
+        let mut ready = Vec::new();
+        let mut keep = Vec::new();
+        for update in self.pending.drain(..) {
+            if upper.less_equal(&update.1) {
+                self.frontier.insert(update.1.clone());
+                keep.push(update);
+            }
+            else {
+                ready.push(update);
+            }
+        }
+        self.pending = keep;
+        self.consolidated = self.pending.len();
+
+        self.sort_and_consolidate(&mut ready);
+
+        let updates = ready.drain(..).map(|((key, val), time, diff)| (key, val, time, diff));
+        let seal = B::Builder::from_ordered(updates, self.lower.clone(), upper.clone(), Antichain::from_elem(T::minimum()));
+        self.lower = upper;
+        seal
+    }
+
+    // the frontier of elements remaining after the most recent call to `self.seal`.
+    fn frontier(&mut self) -> AntichainRef<T> {
+        self.frontier.borrow()
+    }
+}
+
+/// A batch wrapper whose updates are batched by a `RadixBatcher`.
+#[derive(Debug, Abomonation)]
+pub struct RadixBatch<B> {
+    /// The wrapped batch.
+    pub batch: B,
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> BatchReader<K, V, T, R> for RadixBatch<B> {
+
+    /// The type used to enumerate the batch's contents.
+    type Cursor = RadixCursor<K, V, T, R, B>;
+    /// Acquires a cursor to the batch's contents.
+    fn cursor(&self) -> Self::Cursor {
+        RadixCursor::new(self.batch.cursor())
+    }
+
+    /// The number of updates in the batch.
+    fn len(&self) -> usize { self.batch.len() }
+    /// Describes the times of the updates in the batch.
+    fn description(&self) -> &Description<T> { self.batch.description() }
+    /// Reports the size of the batch.
+    fn statistics(&self) -> Statistics { self.batch.statistics() }
+}
+
+/// A cursor over a `RadixBatch`.
+pub struct RadixCursor<K, V, T, R, B: BatchReader<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+    cursor: B::Cursor,
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> RadixCursor<K, V, T, R, B> {
+    fn new(cursor: B::Cursor) -> Self {
+        RadixCursor {
+            cursor,
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> Cursor<K, V, T, R> for RadixCursor<K, V, T, R, B> {
+
+    type Storage = RadixBatch<B>;
+
+    #[inline] fn key_valid(&self, storage: &Self::Storage) -> bool { self.cursor.key_valid(&storage.batch) }
+    #[inline] fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.val_valid(&storage.batch) }
+
+    #[inline] fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.cursor.key(&storage.batch) }
+    #[inline] fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.cursor.val(&storage.batch) }
+
+    #[inline]
+    fn map_times<L: FnMut(&T, &R)>(&mut self, storage: &Self::Storage, logic: L) {
+        self.cursor.map_times(&storage.batch, logic)
+    }
+
+    #[inline] fn step_key(&mut self, storage: &Self::Storage) { self.cursor.step_key(&storage.batch) }
+    #[inline] fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek_key(&storage.batch, key) }
+    #[inline] fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_key_with(&storage.batch, before) }
+
+    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(&storage.batch) }
+    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(&storage.batch, val) }
+
+    #[inline] fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind_keys(&storage.batch) }
+    #[inline] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(&storage.batch) }
+}
+
+/// An immutable collection of updates.
+impl<K, V, T, R, B> Batch<K, V, T, R> for RadixBatch<B>
+where
+    K: RadixKey+Clone,
+    V: Ord+Clone,
+    T: Lattice+timely::progress::Timestamp+Ord+Clone,
+    R: Semigroup,
+    B: Batch<K, V, T, R>,
+{
+    type Batcher = RadixBatcher<K, V, T, R, Self>;
+    type Builder = RadixBuilder<K, V, T, R, B>;
+    type Merger = RadixMerger<K, V, T, R, B>;
+}
+
+/// Wrapper type for building `RadixBatch` batches.
+pub struct RadixBuilder<K, V, T, R, B: Batch<K, V, T, R>> { builder: B::Builder }
+
+/// Functionality for building batches from ordered update sequences.
+impl<K, V, T, R, B> Builder<K, V, T, R, RadixBatch<B>> for RadixBuilder<K, V, T, R, B>
+where
+    K: RadixKey+Clone,
+    V: Ord+Clone,
+    T: Lattice+timely::progress::Timestamp+Ord+Clone,
+    R: Semigroup,
+    B: Batch<K, V, T, R>,
+{
+    fn new() -> Self { RadixBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::new() } }
+    fn with_capacity(cap: usize) -> Self { RadixBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::with_capacity(cap) } }
+    fn push(&mut self, element: (K, V, T, R)) { self.builder.push(element) }
+    fn push_ordered(&mut self, element: (K, V, T, R)) { self.builder.push_ordered(element) }
+    fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> RadixBatch<B> {
+        RadixBatch { batch: self.builder.done(lower, upper, since) }
+    }
+    fn from_ordered<I: IntoIterator<Item=(K, V, T, R)>>(updates: I, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> RadixBatch<B> {
+        RadixBatch { batch: <B::Builder as Builder<K, V, T, R, B>>::from_ordered(updates, lower, upper, since) }
+    }
+}
+
+/// Wrapper type for merging `RadixBatch` batches.
+pub struct RadixMerger<K, V, T, R, B: Batch<K, V, T, R>> { merger: B::Merger }
+
+/// Represents a merge in progress.
+impl<K, V, T, R, B> Merger<K, V, T, R, RadixBatch<B>> for RadixMerger<K, V, T, R, B>
+where
+    K: RadixKey+Clone,
+    V: Ord+Clone,
+    T: Lattice+timely::progress::Timestamp+Ord+Clone,
+    R: Semigroup,
+    B: Batch<K, V, T, R>,
+{
+    fn new(source1: &RadixBatch<B>, source2: &RadixBatch<B>, compaction_frontier: Option<AntichainRef<T>>) -> Self {
+        RadixMerger { merger: <B::Merger as Merger<K, V, T, R, B>>::new(&source1.batch, &source2.batch, compaction_frontier) }
+    }
+    fn work(&mut self, source1: &RadixBatch<B>, source2: &RadixBatch<B>, fuel: &mut isize) {
+        self.merger.work(&source1.batch, &source2.batch, fuel)
+    }
+    fn done(self) -> RadixBatch<B> {
+        RadixBatch { batch: self.merger.done() }
+    }
+}
@@ -0,0 +1,390 @@
+//! Batches that spill large merged results to memory-mapped files.
+//!
+//! A `SpillBatch<B>` wraps a batch type `B` and behaves exactly as `B` does, except that when a merge
+//! produces a batch with many updates the result is written to a file and memory-mapped, rather than
+//! held in memory. Because the spine only produces large batches by merging into its deeper levels,
+//! it is these large and comparatively cold batches that leave memory, while recent small batches
+//! stay resident.
+//!
+//! Spilling is enabled for the merges of a worker thread by installing a `SpillConfig` with `configure`,
+//! which names a directory in which to create the files and the number of updates at which a merged
+//! batch is spilled. A worker that installs no configuration reads it once from the environment, where
+//! `DIFFERENTIAL_SPILL_DIR` names the directory and `DIFFERENTIAL_SPILL_THRESHOLD` sets the number of
+//! updates, which defaults to 1,000,000. Spilled files are unlinked as soon as they are mapped, so they
+//! are reclaimed by the operating system when the batch is dropped.
+//!
+//! Each installed configuration creates its files in a subdirectory of its own, named for the process,
+//! which is removed along with any files left in it once the configuration is replaced or its worker
+//! exits, and the merges started under it have finished. Files that cannot be removed are reported on
+//! standard error.
+//!
+//! Merges whose results may be large enough to spill are performed, written, and mapped by the helper
+//! threads of the `background` module, so that the worker does not wait on the file system. As for
+//! background batches, such a merge remains in progress until the helper thread delivers its result, and
+//! blocks the worker only if the spine must complete it at once.
+//!
+//! The mapping is private and copy-on-write, which abomonation requires as it corrects pointers in place.
+//! Spilling is restricted to updates of `Copy` types, which hold no pointers, so that decoding corrects
+//! only the pointers of the few vectors that hold a batch's updates, and copies only the pages they are on.
+//! A batch of updates with pointers, such as `String` keys, would have each of its pages copied into memory
+//! as it is decoded, and be no less resident than if it had not been spilled.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use abomonation::{Abomonation, encode};
+use abomonation::abomonated::Abomonated;
+use memmap2::{MmapMut, MmapOptions};
+
+use timely::progress::{Antichain, frontier::AntichainRef};
+
+use trace::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description, Statistics};
+use super::background::HelperMerge;
+
+/// The default number of updates at which merged batches are spilled.
+const DEFAULT_THRESHOLD: usize = 1_000_000;
+
+/// Distinguishes spill files and directories created by this process.
+static SPILL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The configuration of spilling for a worker thread.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpillConfig {
+    /// The directory in which spill files are created.
+    pub directory: PathBuf,
+    /// The number of updates at which merged batches are spilled.
+    pub threshold: usize,
+}
+
+impl SpillConfig {
+    /// Spills merged batches with the default number of updates to files in `directory`.
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        SpillConfig {
+            directory: directory.into(),
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Reads the configuration from the environment, if a spill directory is set.
+    pub fn from_env() -> Option<Self> {
+        let directory = ::std::env::var_os("DIFFERENTIAL_SPILL_DIR")?;
+        let threshold = ::std::env::var("DIFFERENTIAL_SPILL_THRESHOLD")
+            .ok()
+            .and_then(|x| x.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_THRESHOLD);
+        Some(SpillConfig { directory: PathBuf::from(directory), threshold })
+    }
+}
+
+/// A directory of spill files, removed along with any files left in it when dropped.
+struct SpillDirectory {
+    path: PathBuf,
+}
+
+impl SpillDirectory {
+    /// Creates a new directory for the files of this process within `parent`.
+    fn create(parent: &Path) -> io::Result<Self> {
+        let name = format!("differential-spill-{}-{}", ::std::process::id(), SPILL_COUNTER.fetch_add(1, Ordering::SeqCst));
+        let path = parent.join(name);
+        fs::create_dir_all(&path)?;
+        Ok(SpillDirectory { path })
+    }
+}
+
+impl Drop for SpillDirectory {
+    fn drop(&mut self) {
+        if let Err(error) = fs::remove_dir_all(&self.path) {
+            eprintln!("spill: failed to remove {}: {}", self.path.display(), error);
+        }
+    }
+}
+
+/// An installed configuration, and the directory its merges spill to.
+#[derive(Clone)]
+struct Spilling {
+    config: SpillConfig,
+    directory: Arc<SpillDirectory>,
+}
+
+impl Spilling {
+    /// Creates the directory of `config`, disabling spilling if it cannot be created.
+    fn new(config: Option<SpillConfig>) -> Option<Self> {
+        let config = config?;
+        match SpillDirectory::create(&config.directory) {
+            Ok(directory) => Some(Spilling { config, directory: Arc::new(directory) }),
+            Err(error) => {
+                eprintln!("spill: failed to create a directory in {}: {}; spilling is disabled", config.directory.display(), error);
+                None
+            },
+        }
+    }
+}
+
+thread_local! {
+    /// The configuration of the current worker, read from the environment unless installed.
+    static CONFIG: RefCell<Option<Option<Spilling>>> = RefCell::new(None);
+}
+
+/// Installs the configuration of spilling for merges started by the current worker thread.
+///
+/// The argument `None` disables spilling. Merges already started are unaffected. If the configuration's
+/// directory cannot be created, the error is reported on standard error and spilling is disabled.
+pub fn configure(config: Option<SpillConfig>) {
+    CONFIG.with(|current| *current.borrow_mut() = Some(Spilling::new(config)));
+}
+
+/// The configuration of spilling for merges started by the current worker thread, if spilling is enabled.
+pub fn configuration() -> Option<SpillConfig> {
+    spilling().map(|spilling| spilling.config)
+}
+
+/// The installed configuration of the current worker thread, if spilling is enabled.
+fn spilling() -> Option<Spilling> {
+    CONFIG.with(|current| current.borrow_mut().get_or_insert_with(|| Spilling::new(SpillConfig::from_env())).clone())
+}
+
+/// A batch either resident in memory or backed by a memory-mapped file.
+pub enum SpillBatch<B> {
+    /// A batch held in memory.
+    Memory(Arc<B>),
+    /// A batch decoded in place from a memory-mapped file.
+    Mapped(Arc<Abomonated<B, MmapMut>>),
+}
+
+impl<B> Clone for SpillBatch<B> {
+    fn clone(&self) -> Self {
+        match self {
+            SpillBatch::Memory(batch) => SpillBatch::Memory(batch.clone()),
+            SpillBatch::Mapped(batch) => SpillBatch::Mapped(batch.clone()),
+        }
+    }
+}
+
+impl<B: Abomonation> SpillBatch<B> {
+    /// The wrapped batch, wherever it lives.
+    #[inline]
+    pub fn inner(&self) -> &B {
+        match self {
+            SpillBatch::Memory(batch) => &**batch,
+            SpillBatch::Mapped(batch) => &***batch,
+        }
+    }
+
+    /// Indicates whether the batch is backed by a file.
+    pub fn is_spilled(&self) -> bool {
+        match self {
+            SpillBatch::Memory(_) => false,
+            SpillBatch::Mapped(_) => true,
+        }
+    }
+
+    /// Wraps a batch produced by a merge, spilling it if it has at least `spilling.config.threshold` updates.
+    ///
+    /// If the batch cannot be written or mapped, it is retained in memory.
+    fn from_merge<K, V, T, R>(batch: B, spilling: &Spilling) -> Self where B: BatchReader<K, V, T, R> {
+        if batch.len() >= spilling.config.threshold {
+            if let Ok(mapped) = spill(&batch, &spilling.directory.path) {
+                return SpillBatch::Mapped(Arc::new(mapped));
+            }
+        }
+        SpillBatch::Memory(Arc::new(batch))
+    }
+}
+
+/// Writes `batch` to a new file in `directory` and maps the file into memory.
+fn spill<B: Abomonation>(batch: &B, directory: &Path) -> io::Result<Abomonated<B, MmapMut>> {
+
+    let name = format!("spill-{}", SPILL_COUNTER.fetch_add(1, Ordering::SeqCst));
+    let path = directory.join(name);
+
+    let mapped = File::create(&path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        unsafe { encode(batch, &mut writer)?; }
+        writer.flush()?;
+        let file = writer.into_inner().map_err(|error| io::Error::new(error.error().kind(), "spill: failed to flush"))?;
+        // A private mapping allows abomonation to correct pointers without writing to the file.
+        unsafe { MmapOptions::new().map_copy(&file) }
+    });
+
+    // The mapping keeps the contents alive; the name is no longer needed, whether or not the batch spilled.
+    if let Err(error) = fs::remove_file(&path) {
+        if error.kind() != io::ErrorKind::NotFound {
+            eprintln!("spill: failed to remove {}: {}", path.display(), error);
+        }
+    }
+
+    unsafe { Abomonated::<B, _>::new(mapped?) }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "spill: failed to decode batch"))
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>+Abomonation> BatchReader<K, V, T, R> for SpillBatch<B> {
+
+    /// The type used to enumerate the batch's contents.
+    type Cursor = SpillCursor<K, V, T, R, B>;
+    /// Acquires a cursor to the batch's contents.
+    fn cursor(&self) -> Self::Cursor {
+        SpillCursor::new(self.inner().cursor())
+    }
+
+    /// The number of updates in the batch.
+    fn len(&self) -> usize { self.inner().len() }
+    /// Describes the times of the updates in the batch.
+    fn description(&self) -> &Description<T> { self.inner().description() }
+    /// Reports the size of the batch, whose bytes are not on the heap once spilled.
+    fn statistics(&self) -> Statistics {
+        let mut statistics = self.inner().statistics();
+        if self.is_spilled() { statistics.heap_bytes = 0; }
+        statistics
+    }
+}
+
+/// A cursor over a possibly spilled batch.
+pub struct SpillCursor<K, V, T, R, B: BatchReader<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+    cursor: B::Cursor,
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> SpillCursor<K, V, T, R, B> {
+    fn new(cursor: B::Cursor) -> Self {
+        SpillCursor {
+            cursor,
+            phantom: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>+Abomonation> Cursor<K, V, T, R> for SpillCursor<K, V, T, R, B> {
+
+    type Storage = SpillBatch<B>;
+
+    #[inline] fn key_valid(&self, storage: &Self::Storage) -> bool { self.cursor.key_valid(storage.inner()) }
+    #[inline] fn val_valid(&self, storage: &Self::Storage) -> bool { self.cursor.val_valid(storage.inner()) }
+
+    #[inline] fn key<'a>(&self, storage: &'a Self::Storage) -> &'a K { self.cursor.key(storage.inner()) }
+    #[inline] fn val<'a>(&self, storage: &'a Self::Storage) -> &'a V { self.cursor.val(storage.inner()) }
+
+    #[inline]
+    fn map_times<L: FnMut(&T, &R)>(&mut self, storage: &Self::Storage, logic: L) {
+        self.cursor.map_times(storage.inner(), logic)
+    }
+
+    #[inline] fn step_key(&mut self, storage: &Self::Storage) { self.cursor.step_key(storage.inner()) }
+    #[inline] fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek_key(storage.inner(), key) }
+    #[inline] fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_key_with(storage.inner(), before) }
+
+    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(storage.inner()) }
+    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(storage.inner(), val) }
+
+    #[inline] fn rewind_keys(&mut self, storage: &Self::Storage) { self.cursor.rewind_keys(storage.inner()) }
+    #[inline] fn rewind_vals(&mut self, storage: &Self::Storage) { self.cursor.rewind_vals(storage.inner()) }
+}
+
+/// An immutable collection of updates.
+impl<K, V, T, R, B> Batch<K, V, T, R> for SpillBatch<B>
+where
+    K: Copy+'static,
+    V: Copy+'static,
+    T: Copy+Send+'static,
+    R: Copy+'static,
+    B: Batch<K, V, T, R>+Abomonation+Send+Sync+'static,
+{
+    type Batcher = SpillBatcher<K, V, T, R, B>;
+    type Builder = SpillBuilder<K, V, T, R, B>;
+    type Merger = SpillMerger<K, V, T, R, B>;
+}
+
+/// Wrapper type for batching possibly spilled batches.
+pub struct SpillBatcher<K, V, T, R, B: Batch<K, V, T, R>> { batcher: B::Batcher }
+
+/// Functionality for collecting and batching updates.
+impl<K, V, T, R, B> Batcher<K, V, T, R, SpillBatch<B>> for SpillBatcher<K, V, T, R, B>
+where
+    K: Copy+'static,
+    V: Copy+'static,
+    T: Copy+Send+'static,
+    R: Copy+'static,
+    B: Batch<K, V, T, R>+Abomonation+Send+Sync+'static,
+{
+    fn new() -> Self { SpillBatcher { batcher: <B::Batcher as Batcher<K, V, T, R, B>>::new() } }
+    fn push_batch(&mut self, batch: &mut Vec<((K, V), T, R)>) { self.batcher.push_batch(batch) }
+    fn seal(&mut self, upper: Antichain<T>) -> SpillBatch<B> { SpillBatch::Memory(Arc::new(self.batcher.seal(upper))) }
+    fn frontier(&mut self) -> AntichainRef<T> { self.batcher.frontier() }
+}
+
+/// Wrapper type for building possibly spilled batches.
+pub struct SpillBuilder<K, V, T, R, B: Batch<K, V, T, R>> { builder: B::Builder }
+
+/// Functionality for building batches from ordered update sequences.
+impl<K, V, T, R, B> Builder<K, V, T, R, SpillBatch<B>> for SpillBuilder<K, V, T, R, B>
+where
+    K: Copy+'static,
+    V: Copy+'static,
+    T: Copy+Send+'static,
+    R: Copy+'static,
+    B: Batch<K, V, T, R>+Abomonation+Send+Sync+'static,
+{
+    fn new() -> Self { SpillBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::new() } }
+    fn with_capacity(cap: usize) -> Self { SpillBuilder { builder: <B::Builder as Builder<K, V, T, R, B>>::with_capacity(cap) } }
+    fn push(&mut self, element: (K, V, T, R)) { self.builder.push(element) }
+    fn push_ordered(&mut self, element: (K, V, T, R)) { self.builder.push_ordered(element) }
+    fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> SpillBatch<B> {
+        SpillBatch::Memory(Arc::new(self.builder.done(lower, upper, since)))
+    }
+    fn from_ordered<I: IntoIterator<Item=(K, V, T, R)>>(updates: I, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> SpillBatch<B> {
+        SpillBatch::Memory(Arc::new(<B::Builder as Builder<K, V, T, R, B>>::from_ordered(updates, lower, upper, since)))
+    }
+}
+
+/// Wrapper type for merging possibly spilled batches.
+pub enum SpillMerger<K, V, T, R, B: Batch<K, V, T, R>> {
+    /// A merge performed by the worker, whose result is too small to spill.
+    Memory(B::Merger),
+    /// A merge performed, and spilled if large enough, by a helper thread.
+    Helper(HelperMerge<SpillBatch<B>>),
+}
+
+/// Represents a merge in progress.
+impl<K, V, T, R, B> Merger<K, V, T, R, SpillBatch<B>> for SpillMerger<K, V, T, R, B>
+where
+    K: Copy+'static,
+    V: Copy+'static,
+    T: Copy+Send+'static,
+    R: Copy+'static,
+    B: Batch<K, V, T, R>+Abomonation+Send+Sync+'static,
+{
+    fn new(source1: &SpillBatch<B>, source2: &SpillBatch<B>, compaction_frontier: Option<AntichainRef<T>>) -> Self {
+        // The merged batch has at most as many updates as its sources.
+        let updates = source1.len() + source2.len();
+        match spilling() {
+            Some(spilling) if updates >= spilling.config.threshold => {
+                let source1 = source1.clone();
+                let source2 = source2.clone();
+                let frontier = compaction_frontier.map(|frontier| frontier.to_owned());
+                SpillMerger::Helper(HelperMerge::start(updates, move || {
+                    let frontier = frontier.as_ref().map(|frontier| frontier.borrow());
+                    let mut merger = <B::Merger as Merger<K, V, T, R, B>>::new(source1.inner(), source2.inner(), frontier);
+                    let mut fuel = isize::max_value();
+                    merger.work(source1.inner(), source2.inner(), &mut fuel);
+                    SpillBatch::<B>::from_merge::<K, V, T, R>(merger.done(), &spilling)
+                }))
+            },
+            _ => SpillMerger::Memory(<B::Merger as Merger<K, V, T, R, B>>::new(source1.inner(), source2.inner(), compaction_frontier)),
+        }
+    }
+    fn work(&mut self, source1: &SpillBatch<B>, source2: &SpillBatch<B>, fuel: &mut isize) {
+        match self {
+            SpillMerger::Memory(merger) => merger.work(source1.inner(), source2.inner(), fuel),
+            SpillMerger::Helper(merge) => merge.work(fuel),
+        }
+    }
+    fn done(self) -> SpillBatch<B> {
+        match self {
+            SpillMerger::Memory(merger) => SpillBatch::Memory(Arc::new(merger.done())),
+            SpillMerger::Helper(merge) => merge.done(),
+        }
+    }
+}
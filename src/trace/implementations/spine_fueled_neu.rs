@@ -74,9 +74,10 @@ use std::fmt::Debug;
 use ::logging::Logger;
 use ::difference::Semigroup;
 use lattice::Lattice;
-use trace::{Batch, BatchReader, Trace, TraceReader};
+use trace::{Batch, BatchReader, Trace, TraceReader, Statistics, MemoryPressure};
 use trace::cursor::{Cursor, CursorList};
 use trace::Merger;
+use trace::merge_policy::{MergePolicy, DefaultPolicy, natural_level};
 
 use ::timely::dataflow::operators::generic::OperatorInfo;
 use ::timely::progress::{Antichain, frontier::AntichainRef};
@@ -98,6 +99,11 @@ pub struct Spine<K, V, T: Lattice+Ord, R: Semigroup, B: Batch<K, V, T, R>> {
     upper: Antichain<T>,
     effort: usize,
     activator: Option<timely::scheduling::activate::Activator>,
+    pressure: Option<MemoryPressure>,           // Heap bytes relative to a budget, if any.
+    policy: Box<dyn MergePolicy>,               // Chooses levels, fuel, and idle effort.
+    statistics: Vec<Statistics>,                // The sizes of the batches at each level, as of its last change.
+    pending_statistics: Vec<Statistics>,        // The sizes of the batches in `pending`.
+    merged: bool,                               // A merge completed since statistics were last logged.
 }
 
 impl<K, V, T, R, B> TraceReader for Spine<K, V, T, R, B>
@@ -213,6 +219,11 @@ where
     }
     fn distinguish_frontier(&mut self) -> AntichainRef<T> { self.through_frontier.borrow() }
 
+    /// Reports one entry for each level of the spine, followed by an entry for batches not yet
+    /// admitted to a level, because they are beyond the `distinguish_since` frontier.
+    fn statistics(&mut self) -> Vec<Statistics> { self.level_statistics() }
+    fn memory_pressure(&mut self) -> Option<MemoryPressure> { self.pressure.clone() }
+
     fn map_batches<F: FnMut(&Self::Batch)>(&mut self, mut f: F) {
         for batch in self.merging.iter().rev() {
             match batch {
@@ -251,25 +262,52 @@ where
     /// The units of effort are updates, and the method should be
     /// thought of as analogous to inserting as many empty updates,
     /// where the trace is permitted to perform proportionate work.
+    /// The effort spent is subtracted from `effort`.
     fn exert(&mut self, effort: &mut isize) {
         // If there is work to be done, ...
         self.tidy_layers();
         if !self.reduced() {
 
-            // If any merges exist, we can directly call `apply_fuel`.
-            if self.merging.iter().any(|b| b.is_double()) {
-                self.apply_fuel(effort);
+            let merges = self.merging.iter().any(|b| b.is_double());
+
+            // Over budget, merges in progress are completed rather than fueled. New merges are not
+            // started for this reason, as a trace that remains over budget would otherwise be merged
+            // down to a single batch at each call.
+            let spent = if merges && self.over_budget() {
+                let mut fuel = isize::max_value();
+                self.apply_fuel(&mut fuel);
+                isize::max_value().saturating_sub(fuel)
             }
-            // Otherwise, we'll need to introduce fake updates to move merges along.
             else {
-                // Introduce an empty batch with roughly *effort number of virtual updates.
-                let level = (*effort as usize).next_power_of_two().trailing_zeros() as usize;
-                self.introduce_batch(None, level);
-            }
+                // The policy may suspend maintenance while idle, in which case merges await new batches.
+                let mut idle = match self.policy.idle_effort(*effort) {
+                    Some(idle) => idle,
+                    None => return,
+                };
+                let supplied = idle;
+
+                // If any merges exist, we can directly call `apply_fuel`.
+                if merges {
+                    self.apply_fuel(&mut idle);
+                    supplied.saturating_sub(idle)
+                }
+                // Otherwise, we'll need to introduce fake updates to move merges along.
+                else {
+                    // Introduce an empty batch with roughly `idle` number of virtual updates,
+                    // at a level no greater than necessary to merge all existing batches.
+                    let level = natural_level(idle as usize).min(self.merging.len());
+                    self.introduce_batch(None, level);
+                    supplied
+                }
+            };
+            // The effort spent is drawn from the caller's effort, and may overdraw it.
+            *effort = effort.saturating_sub(spent);
+
             // We were not in reduced form, so let's check again in the future.
             if let Some(activator) = &self.activator {
                 activator.activate();
             }
+            self.observe_statistics();
         }
     }
 
@@ -290,8 +328,10 @@ where
         self.upper.clone_from(batch.upper());
 
         // TODO: Consolidate or discard empty batches.
+        self.pending_statistics.push(batch.statistics());
         self.pending.push(batch);
         self.consider_merges();
+        self.observe_statistics();
     }
 
     /// Completes the trace with a final empty batch.
@@ -303,6 +343,24 @@ where
             self.insert(batch);
         }
     }
+
+    fn set_memory_budget(&mut self, budget: Option<usize>) {
+        match (budget, &self.pressure) {
+            (Some(budget), Some(pressure)) => pressure.set_budget(budget),
+            (Some(budget), None) => self.pressure = Some(MemoryPressure::new(budget)),
+            (None, Some(pressure)) => {
+                // Release anyone still holding the handle.
+                pressure.set_budget(usize::max_value());
+                self.pressure = None;
+            },
+            (None, None) => { },
+        }
+        self.observe_statistics();
+    }
+
+    fn set_merge_policy(&mut self, policy: Box<dyn MergePolicy>) {
+        self.policy = policy;
+    }
 }
 
 // Drop implementation allows us to log batch drops, to zero out maintained totals.
@@ -387,6 +445,56 @@ where
         true
     }
 
+    /// The sizes of the batches at each level, followed by the sizes of pending batches.
+    ///
+    /// The sizes of batches are determined once, as they arrive at a level, rather than on each call.
+    fn level_statistics(&self) -> Vec<Statistics> {
+        let mut result =
+        (0 .. self.merging.len())
+            .map(|index| self.statistics.get(index).cloned().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let mut pending = Statistics::default();
+        for statistics in self.pending_statistics.iter() {
+            pending += *statistics;
+        }
+        result.push(pending);
+        result
+    }
+
+    /// Records the sizes of the batches at level `index`, which has just changed.
+    fn refresh_statistics(&mut self, index: usize) {
+        while self.statistics.len() <= index {
+            self.statistics.push(Statistics::default());
+        }
+        self.statistics[index] = self.merging[index].statistics();
+    }
+
+    /// Reports heap bytes to the memory pressure handle, if there is a budget, and logs the statistics of
+    /// each level of the trace if logging is enabled and a merge has completed since they were last logged.
+    fn observe_statistics(&mut self) {
+        if let Some(pressure) = &self.pressure {
+            let levels = self.statistics.iter().map(|level| level.heap_bytes).sum::<usize>();
+            let pending = self.pending_statistics.iter().map(|batch| batch.heap_bytes).sum::<usize>();
+            pressure.update(levels + pending);
+        }
+        if self.merged {
+            self.merged = false;
+            if let Some(logger) = &self.logger {
+                for (level, level_statistics) in self.level_statistics().into_iter().enumerate() {
+                    logger.log(::logging::TraceStatistics {
+                        operator: self.operator.global_id,
+                        level,
+                        batches: level_statistics.batches,
+                        batch_keys: level_statistics.batch_keys,
+                        batch_vals: level_statistics.batch_vals,
+                        updates: level_statistics.updates,
+                        heap_bytes: level_statistics.heap_bytes,
+                    });
+                }
+            }
+        }
+    }
+
     /// Describes the merge progress of layers in the trace.
     ///
     /// Intended for diagnostics rather than public consumption.
@@ -428,9 +536,51 @@ where
             upper: Antichain::from_elem(<T as timely::progress::Timestamp>::minimum()),
             effort,
             activator,
+            pressure: None,
+            policy: Box::new(DefaultPolicy),
+            statistics: Vec::new(),
+            pending_statistics: Vec::new(),
+            merged: false,
         }
     }
 
+    /// Allocates a fueled `Spine` whose merges are scheduled by `policy`.
+    ///
+    /// The `effort` multiplier is supplied to the policy, which may use or ignore it.
+    pub fn with_policy<P: MergePolicy+'static>(
+        effort: usize,
+        policy: P,
+        operator: OperatorInfo,
+        logger: Option<::logging::Logger>,
+        activator: Option<timely::scheduling::activate::Activator>,
+    ) -> Self {
+        let mut spine = Self::with_effort(effort, operator, logger, activator);
+        spine.policy = Box::new(policy);
+        spine
+    }
+
+    /// Allocates a fueled `Spine` with a specified effort multiplier and a budget of heap bytes.
+    ///
+    /// While the batches of the spine hold more than `budget` heap bytes, the spine completes merges
+    /// eagerly rather than in proportion to arriving updates, and its `memory_pressure` handle reports
+    /// that it is over budget so that input sources may slow the admission of further updates.
+    pub fn with_budget(
+        effort: usize,
+        budget: usize,
+        operator: OperatorInfo,
+        logger: Option<::logging::Logger>,
+        activator: Option<timely::scheduling::activate::Activator>,
+    ) -> Self {
+        let mut spine = Self::with_effort(effort, operator, logger, activator);
+        spine.pressure = Some(MemoryPressure::new(budget));
+        spine
+    }
+
+    /// Indicates whether the spine last observed more heap bytes than its budget.
+    fn over_budget(&self) -> bool {
+        self.pressure.as_ref().map(|p| p.over_budget()).unwrap_or(false)
+    }
+
     /// Migrate data from `self.pending` into `self.merging`.
     ///
     /// This method reflects on the bookmarks held by others that may prevent merging, and in the
@@ -446,6 +596,7 @@ where
             // Batch can be taken in optimized insertion.
             // Otherwise it is inserted normally at the end of the method.
             let mut batch = Some(self.pending.remove(0));
+            self.pending_statistics.remove(0);
 
             // If `batch` and the most recently inserted batch are both empty, we can just fuse them.
             // We can also replace a structurally empty batch with this empty batch, preserving the
@@ -456,14 +607,16 @@ where
                         self.insert_at(batch.take(), position);
                         let merged = self.complete_at(position);
                         self.merging[position] = MergeState::Single(merged);
+                        self.refresh_statistics(position);
                     }
                 }
             }
 
             // Normal insertion for the batch.
             if let Some(batch) = batch {
-                let index = batch.len().next_power_of_two();
-                self.introduce_batch(Some(batch), index.trailing_zeros() as usize);
+                let length = batch.len();
+                let index = self.policy.level(length);
+                self.introduce_batch(Some(batch), index);
             }
         }
 
@@ -509,13 +662,14 @@ where
         // for each virtual record associated with promoting existing smaller
         // batches. We could try and make this be less, or be scaled to merges
         // based on their deficit at time of instantiation. For now, we remain
-        // conservative.
-        let mut fuel = 8 << batch_index;
-        // Scale up by the effort parameter, which is calibrated to one as the
-        // minimum amount of effort.
-        fuel *= self.effort;
-        // Convert to an `isize` so we can observe any fuel shortfall.
-        let mut fuel = fuel as isize;
+        // conservative by default, and a policy may ask for more or less.
+        // The policy scales up by the effort parameter, or not, as it sees fit.
+        // Fuel is an `isize` so we can observe any fuel shortfall.
+        let mut fuel = self.policy.fuel(batch_index, self.effort);
+        // Over budget, complete merges rather than spread their work across future batches.
+        if self.over_budget() {
+            fuel = isize::max_value();
+        }
 
         // Step 1.  Apply fuel to each in-progress merge.
         //
@@ -599,7 +753,10 @@ where
     /// the fuel non-uniformly (e.g. prioritizing merges at low layers) we could do
     /// so in order to maintain fewer batches on average (at the risk of completing
     /// merges of large batches later, but tbh probably not much later).
+    ///
+    /// On return, `fuel` is reduced by the most fuel that any one merge consumed.
     pub fn apply_fuel(&mut self, fuel: &mut isize) {
+        let mut spent = 0;
         // For the moment our strategy is to apply fuel independently to each merge
         // in progress, rather than prioritizing small merges. This sounds like a
         // great idea, but we need better accounting in place to ensure that merges
@@ -607,9 +764,10 @@ where
         // to pay back their debts.
         for index in 0 .. self.merging.len() {
             // Give each level independent fuel, for now.
-            let mut fuel = *fuel;
+            let mut level_fuel = *fuel;
             // Pass along various logging stuffs, in case we need to report success.
-            self.merging[index].work(&mut fuel);
+            self.merging[index].work(&mut level_fuel);
+            spent = ::std::cmp::max(spent, fuel.saturating_sub(level_fuel));
             // `fuel` could have a deficit at this point, meaning we over-spent when
             // we took a merge step. We could ignore this, or maintain the deficit
             // and account future fuel against it before spending again. It isn't
@@ -625,6 +783,7 @@ where
                 self.insert_at(complete, index+1);
             }
         }
+        *fuel = fuel.saturating_sub(spent);
     }
 
     /// Inserts a batch at a specific location.
@@ -656,16 +815,26 @@ where
                 let compaction_frontier = Some(self.advance_frontier.borrow());
                 self.merging[index] = MergeState::begin_merge(old, batch, compaction_frontier);
             }
-            MergeState::Double(_) => {
-                panic!("Attempted to insert batch into incomplete merge!")
+            MergeState::Double(state) => {
+                // Our fueling discipline should prevent this, but a policy may choose
+                // levels or fuel that do not. We complete the merge and promote its
+                // result, which leaves the layer ready to absorb the batch.
+                self.merging[index] = MergeState::Double(state);
+                let merged = self.complete_at(index);
+                self.insert_at(merged, index + 1);
+                self.merging[index] = MergeState::Single(batch);
             }
         };
+        self.refresh_statistics(index);
     }
 
     /// Completes and extracts what ever is at layer `index`.
     fn complete_at(&mut self, index: usize) -> Option<B> {
-        if let Some((merged, inputs)) = self.merging[index].complete() {
+        let complete = self.merging[index].complete();
+        self.refresh_statistics(index);
+        if let Some((merged, inputs)) = complete {
             if let Some((input1, input2)) = inputs {
+                self.merged = true;
                 // Log the completion of a merge from existing parts.
                 self.logger.as_ref().map(|l| l.log(
                     ::logging::MergeEvent {
@@ -709,6 +878,7 @@ where
                         // Vacant or structurally empty batches can be absorbed.
                         MergeState::Vacant | MergeState::Single(None) => {
                             self.merging.remove(length-2);
+                            if self.statistics.len() > length-2 { self.statistics.remove(length-2); }
                             length = self.merging.len();
                         }
                         // Single batches may initiate a merge, if sizes are
@@ -730,6 +900,7 @@ where
 
                             if smaller <= (1 << length) / 8 {
                                 self.merging.remove(length-2);
+                                if self.statistics.len() > length-2 { self.statistics.remove(length-2); }
                                 self.insert_at(Some(batch), length-2);
                             }
                             else {
@@ -778,6 +949,16 @@ impl<K, V, T: Eq, R, B: Batch<K, V, T, R>> MergeState<K, V, T, R, B> {
         }
     }
 
+    /// The sizes of the batches in the level.
+    fn statistics(&self) -> Statistics {
+        match self {
+            MergeState::Single(Some(b)) => b.statistics(),
+            MergeState::Double(MergeVariant::InProgress(b1,b2,_)) => b1.statistics() + b2.statistics(),
+            MergeState::Double(MergeVariant::Complete(Some((b, _)))) => b.statistics(),
+            _ => Statistics::default(),
+        }
+    }
+
     /// True only for the MergeState::Vacant variant.
     fn is_vacant(&self) -> bool {
         if let MergeState::Vacant = self { true } else { false }
@@ -880,8 +1061,11 @@ impl<K, V, T, R, B: Batch<K, V, T, R>> MergeVariant<K, V, T, R, B> {
     fn complete(mut self) -> Option<(B, Option<(B, B)>)> {
         let mut fuel = isize::max_value();
         self.work(&mut fuel);
-        if let MergeVariant::Complete(batch) = self { batch }
-        else { panic!("Failed to complete a merge!"); }
+        match self {
+            MergeVariant::Complete(batch) => batch,
+            // The merger may await work performed elsewhere, and completes the merge as it is extracted.
+            MergeVariant::InProgress(b1, b2, merge) => Some((merge.done(), Some((b1, b2)))),
+        }
     }
 
     /// Applies some amount of work, potentially completing the merge.
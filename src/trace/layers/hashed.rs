@@ -0,0 +1,319 @@
+//! Implementation using hash-ordered keys placed by Robin Hood hashing.
+//!
+//! Keys are stored in hash order, as for `OrderedLayer`, but spread through a table with some
+//! empty slots so that each key sits at or shortly after the position its hash would suggest. This
+//! is Robin Hood placement for keys inserted in hash order: no key is ever displaced past a key with
+//! a larger hash, which means that a seek can jump directly to the ideal position of the sought key
+//! and find it after a short scan, rather than galloping from its current position.
+//!
+//! Empty slots have empty ranges in the layer below, and repeat the key of the slot before them
+//! (or after them, for slots before the first key). This keeps the key sequence sorted, so that
+//! exponential search remains correct, and avoids the need for a default key value.
+
+use std::mem::size_of;
+
+use timely_sort::Unsigned;
+
+use hashable::{Hashable, HashOrdered};
+
+use super::{Trie, Cursor, Builder, MergeBuilder, TupleBuilder, advance};
+use super::ordered::{OrderedLayer, OrderedBuilder};
+
+/// The number of slots allocated per 256 keys.
+const SLOTS_PER_256_KEYS: usize = 320;
+
+/// A level of the trie, with hash-placed keys and offsets into a lower layer.
+///
+/// In this representation, the values for `keys[i]` are found at `vals[offs[i] .. offs[i+1]]`,
+/// and slot `i` is empty exactly when this range is empty.
+#[derive(Debug, Eq, PartialEq, Clone, Abomonation)]
+pub struct HashedLayer<K, L> {
+    /// The keys of the layer, including repeated keys in empty slots.
+    pub keys: Vec<K>,
+    /// The offsets associated with each slot.
+    ///
+    /// The bounds for `keys[i]` are `(offs[i], offs[i+1])`. The offset array is guaranteed to be one
+    /// element longer than the keys array, ensuring that these accesses do not panic.
+    pub offs: Vec<usize>,
+    /// The number of slots used to determine ideal positions.
+    pub table: usize,
+    /// The ranges of values associated with the keys.
+    pub vals: L,
+}
+
+impl<K: HashOrdered, L> HashedLayer<K, L> {
+    /// The ideal position of `key`, at or before which no key greater or equal to `key` resides.
+    #[inline]
+    pub fn ideal(&self, key: &K) -> usize {
+        ideal_position(key, self.table)
+    }
+    /// Indicates whether slot `index` holds a key.
+    #[inline]
+    pub fn occupied(&self, index: usize) -> bool {
+        self.offs[index] < self.offs[index + 1]
+    }
+    /// The number of non-empty slots in the range `lower .. upper`.
+    pub fn occupied_count(&self, lower: usize, upper: usize) -> usize {
+        (lower .. upper).filter(|&index| self.occupied(index)).count()
+    }
+}
+
+/// Scales the hash of `key` to a position in a table of `table` slots.
+#[inline]
+fn ideal_position<K: Hashable>(key: &K, table: usize) -> usize {
+    let bits = 8 * size_of::<<K as Hashable>::Output>();
+    ((key.hashed().as_u64() as u128 * table as u128) >> bits) as usize
+}
+
+impl<K, L> Trie for HashedLayer<K, L>
+where
+    K: HashOrdered+Clone,
+    L: Trie,
+{
+    type Item = (K, L::Item);
+    type Cursor = HashedCursor<L>;
+    type MergeBuilder = HashedBuilder<K, L::MergeBuilder>;
+    type TupleBuilder = HashedBuilder<K, L::TupleBuilder>;
+
+    /// The number of slots, some of which may be empty.
+    fn keys(&self) -> usize { self.keys.len() }
+    fn tuples(&self) -> usize { self.vals.tuples() }
+    fn cursor_from(&self, lower: usize, upper: usize) -> Self::Cursor {
+        let mut cursor = HashedCursor {
+            bounds: (0, 0),
+            child: self.vals.cursor_from(0, 0),
+            pos: 0,
+        };
+        cursor.reposition(self, lower, upper);
+        cursor
+    }
+}
+
+/// Assembles a hashed layer.
+///
+/// Keys are assembled densely, as in an `OrderedLayer`, and are only spread out to their hashed
+/// positions by `done`. This allows the contents to be manipulated in the dense representation,
+/// for example to compact updates during a merge.
+pub struct HashedBuilder<K: Ord, L> {
+    /// The dense layer under construction.
+    pub builder: OrderedBuilder<K, L>,
+}
+
+impl<K, L> Builder for HashedBuilder<K, L>
+where
+    K: HashOrdered+Clone,
+    L: Builder,
+{
+    type Trie = HashedLayer<K, L::Trie>;
+    fn boundary(&mut self) -> usize { self.builder.boundary() }
+    fn done(self) -> Self::Trie {
+        let OrderedLayer { keys, offs, vals } = self.builder.done();
+        spread(keys, offs, vals)
+    }
+}
+
+/// Places densely packed keys at their Robin Hood positions, filling the gaps with empty slots.
+fn spread<K: HashOrdered+Clone, L>(keys: Vec<K>, offs: Vec<usize>, vals: L) -> HashedLayer<K, L> {
+
+    let table = (keys.len() * SLOTS_PER_256_KEYS) / 256;
+
+    let mut slot_keys = Vec::with_capacity(table + 1);
+    let mut slot_offs = Vec::with_capacity(table + 2);
+    slot_offs.push(0);
+
+    for (index, key) in keys.into_iter().enumerate() {
+        // Each key sits at its ideal position, or immediately after the previous key.
+        let position = ::std::cmp::max(ideal_position(&key, table), slot_keys.len());
+        while slot_keys.len() < position {
+            // Empty slots repeat the previous key, or the next key if there is no previous key.
+            let filler = slot_keys.last().cloned().unwrap_or_else(|| key.clone());
+            slot_keys.push(filler);
+            slot_offs.push(offs[index]);
+        }
+        slot_keys.push(key);
+        slot_offs.push(offs[index + 1]);
+    }
+
+    HashedLayer {
+        keys: slot_keys,
+        offs: slot_offs,
+        table,
+        vals,
+    }
+}
+
+impl<K, L> MergeBuilder for HashedBuilder<K, L>
+where
+    K: HashOrdered+Clone,
+    L: MergeBuilder,
+{
+    fn with_capacity(other1: &Self::Trie, other2: &Self::Trie) -> Self {
+        let mut offs = Vec::with_capacity(other1.keys() + other2.keys() + 1);
+        offs.push(0);
+        HashedBuilder {
+            builder: OrderedBuilder {
+                keys: Vec::with_capacity(other1.keys() + other2.keys()),
+                offs: offs,
+                vals: L::with_capacity(&other1.vals, &other2.vals),
+            }
+        }
+    }
+    #[inline]
+    fn copy_range(&mut self, other: &Self::Trie, lower: usize, upper: usize) {
+        debug_assert!(lower < upper);
+        let other_basis = other.offs[lower];
+        let self_basis = self.builder.offs.last().map(|&x| x).unwrap_or(0);
+
+        for index in lower .. upper {
+            if other.occupied(index) {
+                self.builder.keys.push(other.keys[index].clone());
+                self.builder.offs.push((other.offs[index + 1] + self_basis) - other_basis);
+            }
+        }
+        // Empty slots have empty ranges, so the values of the range are contiguous.
+        if other_basis < other.offs[upper] {
+            self.builder.vals.copy_range(&other.vals, other_basis, other.offs[upper]);
+        }
+    }
+
+    fn push_merge(&mut self, other1: (&Self::Trie, usize, usize), other2: (&Self::Trie, usize, usize)) -> usize {
+        let (trie1, mut lower1, upper1) = other1;
+        let (trie2, mut lower2, upper2) = other2;
+
+        self.builder.keys.reserve((upper1 - lower1) + (upper2 - lower2));
+
+        // while both mergees are still active
+        while lower1 < upper1 && lower2 < upper2 {
+            self.merge_step((trie1, &mut lower1, upper1), (trie2, &mut lower2, upper2));
+        }
+
+        if lower1 < upper1 { self.copy_range(trie1, lower1, upper1); }
+        if lower2 < upper2 { self.copy_range(trie2, lower2, upper2); }
+
+        self.builder.keys.len()
+    }
+}
+
+impl<K, L> HashedBuilder<K, L>
+where
+    K: HashOrdered+Clone,
+    L: MergeBuilder,
+{
+    /// Performs one step of merging.
+    #[inline]
+    pub fn merge_step(&mut self, other1: (&<Self as Builder>::Trie, &mut usize, usize), other2: (&<Self as Builder>::Trie, &mut usize, usize)) {
+
+        let (trie1, lower1, upper1) = other1;
+        let (trie2, lower2, upper2) = other2;
+
+        // Empty slots contribute nothing, and are skipped (or copied along with other slots).
+        if !trie1.occupied(*lower1) { *lower1 += 1; return; }
+        if !trie2.occupied(*lower2) { *lower2 += 1; return; }
+
+        match trie1.keys[*lower1].cmp(&trie2.keys[*lower2]) {
+            ::std::cmp::Ordering::Less => {
+                // determine how far we can advance lower1 until we reach/pass lower2
+                let step = 1 + advance(&trie1.keys[(1 + *lower1)..upper1], |x| x < &trie2.keys[*lower2]);
+                let step = std::cmp::min(step, 1_000);
+                self.copy_range(trie1, *lower1, *lower1 + step);
+                *lower1 += step;
+            },
+            ::std::cmp::Ordering::Equal => {
+                let lower = self.builder.vals.boundary();
+                // record vals_length so we can tell if anything was pushed.
+                let upper = self.builder.vals.push_merge(
+                    (&trie1.vals, trie1.offs[*lower1], trie1.offs[*lower1 + 1]),
+                    (&trie2.vals, trie2.offs[*lower2], trie2.offs[*lower2 + 1])
+                );
+                if upper > lower {
+                    self.builder.keys.push(trie1.keys[*lower1].clone());
+                    self.builder.offs.push(upper);
+                }
+
+                *lower1 += 1;
+                *lower2 += 1;
+            },
+            ::std::cmp::Ordering::Greater => {
+                // determine how far we can advance lower2 until we reach/pass lower1
+                let step = 1 + advance(&trie2.keys[(1 + *lower2)..upper2], |x| x < &trie1.keys[*lower1]);
+                let step = std::cmp::min(step, 1_000);
+                self.copy_range(trie2, *lower2, *lower2 + step);
+                *lower2 += step;
+            },
+        }
+    }
+}
+
+impl<K, L> TupleBuilder for HashedBuilder<K, L>
+where
+    K: HashOrdered+Clone,
+    L: TupleBuilder,
+{
+    type Item = (K, L::Item);
+    fn new() -> Self { HashedBuilder { builder: <OrderedBuilder<K, L> as TupleBuilder>::new() } }
+    fn with_capacity(cap: usize) -> Self { HashedBuilder { builder: <OrderedBuilder<K, L> as TupleBuilder>::with_capacity(cap) } }
+    #[inline]
+    fn push_tuple(&mut self, tuple: (K, L::Item)) { self.builder.push_tuple(tuple) }
+}
+
+/// A cursor with a child cursor that is updated as we move.
+#[derive(Debug)]
+pub struct HashedCursor<L: Trie> {
+    pos: usize,
+    bounds: (usize, usize),
+    /// The cursor for the trie layer below this one.
+    pub child: L::Cursor,
+}
+
+impl<L: Trie> HashedCursor<L> {
+    /// Advances the cursor to the first key for which `before` returns false.
+    ///
+    /// Keys, including the repeated keys of empty slots, are in order, and so the slots are galloped
+    /// through as for `OrderedCursor::seek_where`. There is no ideal position to jump to, as there is no key.
+    pub fn seek_where<K: HashOrdered, F: Fn(&K)->bool>(&mut self, storage: &HashedLayer<K, L>, before: F) {
+        self.pos += advance(&storage.keys[self.pos .. self.bounds.1], before);
+        self.settle(storage);
+    }
+    /// Moves past empty slots, and positions the child cursor if valid.
+    #[inline]
+    fn settle<K: HashOrdered>(&mut self, storage: &HashedLayer<K, L>) {
+        while self.pos < self.bounds.1 && !storage.occupied(self.pos) {
+            self.pos += 1;
+        }
+        if self.pos < self.bounds.1 {
+            self.child.reposition(&storage.vals, storage.offs[self.pos], storage.offs[self.pos + 1]);
+        }
+    }
+}
+
+impl<K, L> Cursor<HashedLayer<K, L>> for HashedCursor<L>
+where
+    K: HashOrdered,
+    L: Trie,
+{
+    type Key = K;
+    fn key<'a>(&self, storage: &'a HashedLayer<K, L>) -> &'a Self::Key { &storage.keys[self.pos] }
+    fn step(&mut self, storage: &HashedLayer<K, L>) {
+        self.pos += 1;
+        self.settle(storage);
+    }
+    fn seek(&mut self, storage: &HashedLayer<K, L>, key: &Self::Key) {
+        // No key greater or equal to `key` precedes its ideal position.
+        let ideal = storage.ideal(key);
+        if self.pos < ideal {
+            self.pos = ::std::cmp::min(ideal, self.bounds.1);
+        }
+        self.pos += advance(&storage.keys[self.pos .. self.bounds.1], |k| k.lt(key));
+        self.settle(storage);
+    }
+    fn valid(&self, _storage: &HashedLayer<K, L>) -> bool { self.pos < self.bounds.1 }
+    fn rewind(&mut self, storage: &HashedLayer<K, L>) {
+        self.pos = self.bounds.0;
+        self.settle(storage);
+    }
+    fn reposition(&mut self, storage: &HashedLayer<K, L>, lower: usize, upper: usize) {
+        self.pos = lower;
+        self.bounds = (lower, upper);
+        self.settle(storage);
+    }
+}
@@ -6,7 +6,7 @@
 
 pub mod ordered;
 pub mod ordered_leaf;
-// pub mod hashed;
+pub mod hashed;
 // pub mod weighted;
 // pub mod unordered;
 
@@ -244,6 +244,23 @@ pub struct OrderedCursor<L: Trie> {
 	pub child: L::Cursor,
 }
 
+impl<L: Trie> OrderedCursor<L> {
+	/// Advances the cursor to the first key for which `before` returns false.
+	///
+	/// As with `seek`, `before` must hold for a prefix of the remaining keys and fail for the rest.
+	pub fn seek_where<K, O, F>(&mut self, storage: &OrderedLayer<K, L, O>, before: F)
+	where
+		K: Ord,
+		O: OrdOffset, <O as TryFrom<usize>>::Error: Debug, <O as TryInto<usize>>::Error: Debug,
+		F: Fn(&K)->bool,
+	{
+		self.pos += advance(&storage.keys[self.pos .. self.bounds.1], before);
+		if self.pos < self.bounds.1 {
+			self.child.reposition(&storage.vals, storage.offs[self.pos].try_into().unwrap(), storage.offs[self.pos + 1].try_into().unwrap());
+		}
+	}
+}
+
 impl<K, L, O> Cursor<OrderedLayer<K, L, O>> for OrderedCursor<L>
 where
     K: Ord,
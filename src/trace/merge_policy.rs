@@ -0,0 +1,72 @@
+//! Policies for scheduling the merges of a `Spine`.
+//!
+//! A spine organizes its batches into levels, where the batches at level `i` are treated as if they
+//! hold `2^i` updates, and it advances the merges in progress with fuel drawn from arriving batches.
+//! A `MergePolicy` chooses the level at which new batches are introduced, how much fuel each introduced
+//! batch contributes, and how much maintenance the spine performs when it is idle.
+//!
+//! The default policy introduces each batch at the least level that can hold it, and supplies `8 << level`
+//! fuel for it, which ensures that merges complete before the levels they occupy are needed again. Policies
+//! may choose other levels and other amounts of fuel. A merge that is not complete when its level is needed
+//! again is completed at that moment, which costs the time of the remaining merge work but not correctness.
+
+/// The least level whose batches are treated as holding at least `len` updates.
+pub fn natural_level(len: usize) -> usize {
+    len.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Chooses how a `Spine` schedules its merges.
+pub trait MergePolicy {
+    /// The level at which to introduce a batch of `len` updates.
+    fn level(&self, len: usize) -> usize { natural_level(len) }
+    /// The fuel each merge in progress receives when a batch is introduced at `level`.
+    ///
+    /// The `effort` argument is the multiplier the spine was constructed with.
+    fn fuel(&self, level: usize, effort: usize) -> isize { ((8 << level) * effort) as isize }
+    /// The effort to apply when the spine is exerted with `effort` and has maintenance to perform.
+    ///
+    /// Returning `None` suspends maintenance while the spine is idle, so that merges advance only as
+    /// batches arrive.
+    fn idle_effort(&self, effort: isize) -> Option<isize> { Some(effort) }
+}
+
+/// Fuels merges in proportion to arriving updates, scaled by the spine's effort multiplier.
+///
+/// This is the policy spines use unless instructed otherwise.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultPolicy;
+
+impl MergePolicy for DefaultPolicy { }
+
+/// Performs the least merge work each step that the spine requires.
+///
+/// Batches contribute only the fuel the spine requires, regardless of the effort multiplier, and idle
+/// maintenance proceeds in slices of at most `idle_limit` effort, which bounds the time any one step
+/// spends merging. In exchange, merges complete later and the spine holds more batches on average.
+#[derive(Clone, Copy, Debug)]
+pub struct LowLatency {
+    /// The greatest effort applied by one idle exertion.
+    pub idle_limit: isize,
+}
+
+impl Default for LowLatency {
+    fn default() -> Self { LowLatency { idle_limit: 1 << 10 } }
+}
+
+impl MergePolicy for LowLatency {
+    fn fuel(&self, level: usize, _effort: usize) -> isize { (8 << level) as isize }
+    fn idle_effort(&self, effort: isize) -> Option<isize> { Some(effort.min(self.idle_limit)) }
+}
+
+/// Completes merges as soon as they begin, and merges all batches when idle.
+///
+/// Each merge holds both of its inputs and its partial output until it completes, and compaction only
+/// takes effect in completed merges. This policy minimizes the time that memory is held this way, at
+/// the cost of occasional steps that perform large merges.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LowMemory;
+
+impl MergePolicy for LowMemory {
+    fn fuel(&self, _level: usize, _effort: usize) -> isize { isize::max_value() }
+    fn idle_effort(&self, _effort: isize) -> Option<isize> { Some(isize::max_value()) }
+}
@@ -11,6 +11,8 @@ pub mod cursor;
 pub mod description;
 pub mod implementations;
 pub mod layers;
+pub mod merge_policy;
+pub mod statistics;
 pub mod wrappers;
 
 use timely::progress::{Antichain, frontier::AntichainRef};
@@ -19,6 +21,8 @@ use timely::progress::Timestamp;
 // use ::difference::Semigroup;
 pub use self::cursor::Cursor;
 pub use self::description::Description;
+pub use self::statistics::{Statistics, MemoryPressure};
+pub use self::merge_policy::MergePolicy;
 
 // 	The traces and batch and cursors want the flexibility to appear as if they manage certain types of keys and
 // 	values and such, while perhaps using other representations, I'm thinking mostly of wrappers around the keys
@@ -116,6 +120,19 @@ pub trait TraceReader {
 	/// cursor methods, as they (by default) just move through batches accumulating cursors into a cursor list.
 	fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F);
 
+	/// Reports the sizes of the batches in the trace, grouped by level.
+	///
+	/// Traces that organize their batches into levels report one entry for each level, and the default
+	/// implementation reports all batches as a single level.
+	fn statistics(&mut self) -> Vec<Statistics> {
+		let mut total = Statistics::default();
+		self.map_batches(|batch| total += batch.statistics());
+		vec![total]
+	}
+
+	/// A handle reporting whether the trace exceeds its memory budget, if it has a budget.
+	fn memory_pressure(&mut self) -> Option<MemoryPressure> { None }
+
 	/// Reads the upper frontier of committed times.
 	///
 	///
@@ -184,6 +201,17 @@ where <Self as TraceReader>::Batch: Batch<Self::Key, Self::Val, Self::Time, Self
 	/// This method should be logically equivalent to introducing an empty batch whose lower frontier equals
 	/// the upper frontier of the most recently introduced batch, and whose upper frontier is empty.
 	fn close(&mut self);
+
+	/// Sets a budget of heap bytes for the trace, or removes the budget if `None`.
+	///
+	/// Traces that support budgets merge more eagerly while they exceed the budget, and report it through
+	/// `memory_pressure`. The default implementation ignores the budget.
+	fn set_memory_budget(&mut self, _budget: Option<usize>) { }
+
+	/// Replaces the policy that schedules the merging of the trace's batches.
+	///
+	/// Traces that do not merge batches progressively may ignore the policy, as the default implementation does.
+	fn set_merge_policy(&mut self, _policy: Box<dyn MergePolicy>) { }
 }
 
 /// A batch of updates whose contents may be read.
@@ -211,6 +239,12 @@ where
 	fn lower(&self) -> &Antichain<T> { self.description().lower() }
 	/// All times in the batch are not greater or equal to any element of `upper`.
 	fn upper(&self) -> &Antichain<T> { self.description().upper() }
+
+	/// Reports the size of the batch.
+	///
+	/// The default implementation walks the batch with a cursor, and implementations that know their
+	/// sizes without doing so should report them directly.
+	fn statistics(&self) -> Statistics { Statistics::from_cursor(self.cursor(), self) }
 }
 
 /// An immutable collection of updates.
@@ -260,8 +294,28 @@ pub trait Builder<K, V, T, R, Output: Batch<K, V, T, R>> {
 	fn extend<I: Iterator<Item=(K,V,T,R)>>(&mut self, iter: I) {
 		for item in iter { self.push(item); }
 	}
+	/// Adds an element strictly greater in `(key, val, time)` order than all elements added before it.
+	///
+	/// The element should also have a non-zero difference. Implementations may check these properties
+	/// with debug assertions, and may rely on them to avoid work.
+	fn push_ordered(&mut self, element: (K, V, T, R)) {
+		self.push(element);
+	}
 	/// Completes building and returns the batch.
 	fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> Output;
+	/// Builds a batch from a sequence of updates sorted and consolidated by `(key, val, time)`.
+	///
+	/// The sequence must satisfy the requirements of `push_ordered`, and implementations may assemble
+	/// the batch directly rather than through an instance of the builder.
+	fn from_ordered<I: IntoIterator<Item=(K,V,T,R)>>(updates: I, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> Output
+	where
+		Self: Sized,
+	{
+		let updates = updates.into_iter();
+		let mut builder = Self::with_capacity(updates.size_hint().0);
+		for update in updates { builder.push_ordered(update); }
+		builder.done(lower, upper, since)
+	}
 }
 
 /// Represents a merge in progress.
@@ -278,7 +332,9 @@ pub trait Merger<K, V, T, R, Output: Batch<K, V, T, R>> {
 	///
 	/// This method should only be called after `work` has been called and
 	/// has not brought `fuel` to zero. Otherwise, the merge is still in
-	/// progress.
+	/// progress. The exception is a merger whose work is performed elsewhere,
+	/// which may leave no fuel even when offered `isize::max_value()`, and
+	/// must then complete the merge in this method, blocking if needed.
 	fn done(self) -> Output;
 }
 
@@ -289,7 +345,7 @@ pub mod rc_blanket_impls {
 	use std::rc::Rc;
 
 	use timely::progress::{Antichain, frontier::AntichainRef};
-	use super::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description};
+	use super::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description, Statistics};
 
 	impl<K, V, T, R, B: BatchReader<K,V,T,R>> BatchReader<K,V,T,R> for Rc<B> {
 
@@ -304,6 +360,8 @@ pub mod rc_blanket_impls {
 		fn len(&self) -> usize { (&**self).len() }
 		/// Describes the times of the updates in the batch.
 		fn description(&self) -> &Description<T> { (&**self).description() }
+		/// Reports the size of the batch.
+		fn statistics(&self) -> Statistics { (&**self).statistics() }
 	}
 
 	/// Wrapper to provide cursor to nested scope.
@@ -338,6 +396,7 @@ pub mod rc_blanket_impls {
 
 	    #[inline] fn step_key(&mut self, storage: &Self::Storage) { self.cursor.step_key(storage) }
 	    #[inline] fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek_key(storage, key) }
+	    #[inline] fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_key_with(storage, before) }
 
 	    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(storage) }
 	    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(storage, val) }
@@ -372,7 +431,11 @@ pub mod rc_blanket_impls {
 		fn new() -> Self { RcBuilder { builder: <B::Builder as Builder<K,V,T,R,B>>::new() } }
 		fn with_capacity(cap: usize) -> Self { RcBuilder { builder: <B::Builder as Builder<K,V,T,R,B>>::with_capacity(cap) } }
 		fn push(&mut self, element: (K, V, T, R)) { self.builder.push(element) }
+		fn push_ordered(&mut self, element: (K, V, T, R)) { self.builder.push_ordered(element) }
 		fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> Rc<B> { Rc::new(self.builder.done(lower, upper, since)) }
+		fn from_ordered<I: IntoIterator<Item=(K,V,T,R)>>(updates: I, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> Rc<B> {
+			Rc::new(<B::Builder as Builder<K,V,T,R,B>>::from_ordered(updates, lower, upper, since))
+		}
 	}
 
 	/// Wrapper type for merging reference counted batches.
@@ -396,7 +459,7 @@ pub mod abomonated_blanket_impls {
 	use abomonation::abomonated::Abomonated;
 	use timely::progress::{Antichain, frontier::AntichainRef};
 
-	use super::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description};
+	use super::{Batch, BatchReader, Batcher, Builder, Merger, Cursor, Description, Statistics};
 
 	impl<K, V, T, R, B: BatchReader<K,V,T,R>+Abomonation> BatchReader<K,V,T,R> for Abomonated<B, Vec<u8>> {
 
@@ -411,6 +474,8 @@ pub mod abomonated_blanket_impls {
 		fn len(&self) -> usize { (&**self).len() }
 		/// Describes the times of the updates in the batch.
 		fn description(&self) -> &Description<T> { (&**self).description() }
+		/// Reports the size of the batch.
+		fn statistics(&self) -> Statistics { (&**self).statistics() }
 	}
 
 	/// Wrapper to provide cursor to nested scope.
@@ -445,6 +510,7 @@ pub mod abomonated_blanket_impls {
 
 	    #[inline] fn step_key(&mut self, storage: &Self::Storage) { self.cursor.step_key(storage) }
 	    #[inline] fn seek_key(&mut self, storage: &Self::Storage, key: &K) { self.cursor.seek_key(storage, key) }
+	    #[inline] fn seek_key_with<F: Fn(&K)->bool>(&mut self, storage: &Self::Storage, before: F) { self.cursor.seek_key_with(storage, before) }
 
 	    #[inline] fn step_val(&mut self, storage: &Self::Storage) { self.cursor.step_val(storage) }
 	    #[inline] fn seek_val(&mut self, storage: &Self::Storage, val: &V) { self.cursor.seek_val(storage, val) }
@@ -484,6 +550,7 @@ pub mod abomonated_blanket_impls {
 		fn new() -> Self { AbomonatedBuilder { builder: <B::Builder as Builder<K,V,T,R,B>>::new() } }
 		fn with_capacity(cap: usize) -> Self { AbomonatedBuilder { builder: <B::Builder as Builder<K,V,T,R,B>>::with_capacity(cap) } }
 		fn push(&mut self, element: (K, V, T, R)) { self.builder.push(element) }
+		fn push_ordered(&mut self, element: (K, V, T, R)) { self.builder.push_ordered(element) }
 		fn done(self, lower: Antichain<T>, upper: Antichain<T>, since: Antichain<T>) -> Abomonated<B, Vec<u8>> {
 			let batch = self.builder.done(lower, upper, since);
 			let mut bytes = Vec::with_capacity(measure(&batch));
@@ -0,0 +1,101 @@
+//! Summaries of the sizes of batches and traces, and signals of memory pressure.
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::ops::{Add, AddAssign};
+use std::mem::size_of;
+
+use super::cursor::Cursor;
+
+/// Counts describing the size of one or more batches.
+///
+/// Keys and `(key, val)` pairs are counted once for each batch that contains them. For one batch these
+/// are the distinct counts, and for several batches the sums are upper bounds on the distinct counts.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Abomonation)]
+pub struct Statistics {
+    /// The number of non-empty batches.
+    pub batches: usize,
+    /// The number of keys, counted once for each batch that contains them.
+    pub batch_keys: usize,
+    /// The number of `(key, val)` pairs, counted once for each batch that contains them.
+    pub batch_vals: usize,
+    /// The number of `(key, val, time, diff)` updates.
+    pub updates: usize,
+    /// The approximate number of heap bytes allocated to hold the updates.
+    ///
+    /// This does not include heap allocations owned by keys, values, times, or differences.
+    pub heap_bytes: usize,
+}
+
+impl Statistics {
+    /// Counts the contents of a batch by walking it with a cursor.
+    ///
+    /// Heap bytes are estimated as the space for as many update tuples as are found.
+    pub fn from_cursor<K, V, T, R, C: Cursor<K, V, T, R>>(mut cursor: C, storage: &C::Storage) -> Self {
+        let mut result = Statistics::default();
+        cursor.rewind_keys(storage);
+        while cursor.key_valid(storage) {
+            result.batch_keys += 1;
+            while cursor.val_valid(storage) {
+                result.batch_vals += 1;
+                let mut updates = 0;
+                cursor.map_times(storage, |_, _| updates += 1);
+                result.updates += updates;
+                cursor.step_val(storage);
+            }
+            cursor.step_key(storage);
+        }
+        result.heap_bytes = result.updates * size_of::<(K, V, T, R)>();
+        if result.updates > 0 { result.batches = 1; }
+        result
+    }
+}
+
+impl AddAssign for Statistics {
+    fn add_assign(&mut self, other: Self) {
+        self.batches += other.batches;
+        self.batch_keys += other.batch_keys;
+        self.batch_vals += other.batch_vals;
+        self.updates += other.updates;
+        self.heap_bytes += other.heap_bytes;
+    }
+}
+
+impl Add for Statistics {
+    type Output = Self;
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+/// A shared indication of whether a trace holds more heap bytes than its budget.
+///
+/// A trace with a budget updates its handle as batches are inserted and merged, and clones of the handle
+/// observe the same trace. Input sources can consult `over_budget` to slow their admission of updates.
+#[derive(Clone, Debug)]
+pub struct MemoryPressure {
+    /// The heap bytes last observed, and the budget.
+    state: Rc<Cell<(usize, usize)>>,
+}
+
+impl MemoryPressure {
+    /// Creates a handle for a budget of `budget` heap bytes.
+    pub fn new(budget: usize) -> Self {
+        MemoryPressure { state: Rc::new(Cell::new((0, budget))) }
+    }
+    /// The heap bytes last reported by the trace.
+    pub fn heap_bytes(&self) -> usize { self.state.get().0 }
+    /// The budget of heap bytes.
+    pub fn budget(&self) -> usize { self.state.get().1 }
+    /// Indicates whether the heap bytes last reported exceed the budget.
+    pub fn over_budget(&self) -> bool { self.heap_bytes() > self.budget() }
+    /// Records the heap bytes currently held by the trace.
+    pub fn update(&self, heap_bytes: usize) {
+        self.state.set((heap_bytes, self.budget()));
+    }
+    /// Changes the budget of heap bytes.
+    pub fn set_budget(&self, budget: usize) {
+        self.state.set((self.heap_bytes(), budget));
+    }
+}
@@ -0,0 +1,164 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::*;
+use timely::progress::frontier::AntichainRef;
+
+use differential_dataflow::input::InputSession;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::trace::TraceReader;
+
+#[test]
+fn test_peek() {
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let mut input = InputSession::<usize, (u64, u64), isize>::new();
+        let (mut trace, probe) = worker.dataflow(|scope| {
+            let arranged = input.to_collection(scope).arrange_by_key();
+            (arranged.trace.clone(), arranged.stream.probe())
+        });
+
+        input.insert((1, 10));
+        input.insert((1, 11));
+        input.insert((2, 20));
+        input.insert((3, 30));
+        input.advance_to(1);
+        input.remove((1, 10));
+        input.insert((3, 30));
+        input.advance_to(2);
+        input.flush();
+        while probe.less_than(input.time()) { worker.step(); }
+
+        assert_eq!(trace.peek(&1, &0), Some(vec![(10, 1), (11, 1)]));
+        assert_eq!(trace.peek(&1, &1), Some(vec![(11, 1)]));
+        assert_eq!(trace.peek(&4, &1), Some(vec![]));
+        assert_eq!(trace.peek(&1, &2), None);
+        assert_eq!(trace.peek_range(&2, &4, &1), Some(vec![(2, 20, 1), (3, 30, 2)]));
+        assert_eq!(trace.peek_range(&0, &2, &0), Some(vec![(1, 10, 1), (1, 11, 1)]));
+
+        trace.advance_by(AntichainRef::new(&[1]));
+        assert_eq!(trace.peek(&1, &0), None);
+        assert_eq!(trace.peek(&1, &1), Some(vec![(11, 1)]));
+
+    }).unwrap();
+}
+
+#[test]
+fn test_lookup_range() {
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use differential_dataflow::trace::cursor::range::Between;
+
+    let guards = timely::execute(timely::Configuration::Process(2), move |worker| {
+
+        let index = worker.index();
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let results2 = results.clone();
+
+        let mut input = InputSession::<usize, (u64, u64), isize>::new();
+        let (mut queries, probe) = worker.dataflow(|scope| {
+            let (handle, queries) = scope.new_input::<(Between<u64>, usize)>();
+            let probe =
+            input.to_collection(scope)
+                 .arrange_by_key()
+                 .lookup_range(&queries)
+                 .inspect(move |x| results2.borrow_mut().push(x.clone()))
+                 .probe();
+            (handle, probe)
+        });
+
+        // Each time's updates form their own batch, and later batches retract earlier updates.
+        if index == 0 {
+            input.insert((1, 10));
+            input.insert((2, 20));
+            input.insert((3, 30));
+            input.insert((5, 50));
+            queries.send((Between { lower: 1, upper: 4 }, 0));
+        }
+        input.advance_to(1);
+        queries.advance_to(1);
+        if index == 0 {
+            input.remove((2, 20));
+            input.insert((3, 31));
+            input.insert((4, 40));
+            queries.send((Between { lower: 1, upper: 4 }, 1));
+            queries.send((Between { lower: 4, upper: 10 }, 1));
+        }
+        input.advance_to(2);
+        queries.advance_to(2);
+        if index == 0 {
+            input.remove((1, 10));
+            queries.send((Between { lower: 0, upper: 2 }, 2));
+            queries.send((Between { lower: 6, upper: 10 }, 2));
+        }
+        input.advance_to(3);
+        queries.advance_to(3);
+        input.flush();
+        while probe.less_than(&3) { worker.step(); }
+
+        let results = results.borrow().clone();
+        results
+    }).unwrap();
+
+    let mut results = guards.join().into_iter().flat_map(|result| result.unwrap()).collect::<Vec<_>>();
+    results.sort();
+
+    let range1 = Between { lower: 1, upper: 4 };
+    let range2 = Between { lower: 4, upper: 10 };
+    let mut expected = vec![
+        (range1, 1, 10, 0, 1),
+        (range1, 2, 20, 0, 1),
+        (range1, 3, 30, 0, 1),
+        (range1, 1, 10, 1, 1),
+        (range1, 3, 30, 1, 1),
+        (range1, 3, 31, 1, 1),
+        (range2, 4, 40, 1, 1),
+        (range2, 5, 50, 1, 1),
+    ];
+    expected.sort();
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn test_arrange_with_budget() {
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        use differential_dataflow::operators::arrange::Arrange;
+        use differential_dataflow::trace::implementations::ord::OrdValSpine;
+
+        let mut input = InputSession::<usize, (u64, u64), isize>::new();
+        let (mut trace, probe) = worker.dataflow(|scope| {
+            let arranged = input.to_collection(scope).arrange_with_budget::<OrdValSpine<u64,u64,usize,isize>>("Budgeted", 1 << 30);
+            (arranged.trace.clone(), arranged.stream.probe())
+        });
+
+        let pressure = trace.memory_pressure().unwrap();
+        for round in 0 .. 10 {
+            for key in 0 .. 100 {
+                input.insert((key, round));
+            }
+            input.advance_to(round as usize + 1);
+            input.flush();
+            while probe.less_than(input.time()) { worker.step(); }
+        }
+        assert!(pressure.heap_bytes() > 0);
+        assert!(!pressure.over_budget());
+
+        // An arrangement that remains over budget continues to maintain its trace correctly.
+        trace.set_memory_budget(Some(0));
+        assert!(pressure.over_budget());
+        for round in 10 .. 20 {
+            for key in 0 .. 100 {
+                input.insert((key, round));
+                input.remove((key, round - 10));
+            }
+            input.advance_to(round as usize + 1);
+            input.flush();
+            while probe.less_than(input.time()) { worker.step(); }
+        }
+        assert!(pressure.over_budget());
+        assert_eq!(trace.peek(&7, &19), Some((10 .. 20).map(|val| (val, 1)).collect()));
+
+    }).unwrap();
+}
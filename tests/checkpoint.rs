@@ -0,0 +1,79 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+mod common;
+
+use timely::dataflow::operators::generic::OperatorInfo;
+use timely::progress::{Antichain, frontier::AntichainRef};
+
+use differential_dataflow::trace::{Trace, TraceReader, Batch, Batcher};
+use differential_dataflow::trace::cursor::CursorDebug;
+use differential_dataflow::trace::implementations::checkpoint;
+use differential_dataflow::trace::implementations::ord::OrdValSpine;
+
+use common::TempDir;
+
+type IntegerTrace = OrdValSpine<u64, u64, usize, i64>;
+
+fn get_trace() -> IntegerTrace {
+    let op_info = OperatorInfo::new(0, 0, &[]);
+    let mut trace = IntegerTrace::new(op_info, None, None);
+    {
+        let mut batcher = <<IntegerTrace as TraceReader>::Batch as Batch<u64, u64, usize, i64>>::Batcher::new();
+
+        batcher.push_batch(&mut vec![
+            ((1, 2), 0, 1),
+            ((2, 3), 1, 1),
+            ((2, 3), 2, -1),
+        ]);
+
+        for time in 1 .. 4 {
+            trace.insert(batcher.seal(Antichain::from_elem(time)));
+        }
+    }
+    trace
+}
+
+#[test]
+fn test_checkpoint_restore() {
+
+    let directory = TempDir::new("differential-checkpoint");
+    let path = directory.path();
+
+    let mut trace = get_trace();
+    trace.advance_by(AntichainRef::new(&[1]));
+    trace.distinguish_since(AntichainRef::new(&[1]));
+    checkpoint::write(&mut trace, path).unwrap();
+    // A second checkpoint replaces the first.
+    checkpoint::write(&mut trace, path).unwrap();
+
+    let mut restored: IntegerTrace = checkpoint::restore(path, OperatorInfo::new(0, 0, &[]), None, None).unwrap();
+
+    let mut upper1 = Antichain::new();
+    let mut upper2 = Antichain::new();
+    trace.read_upper(&mut upper1);
+    restored.read_upper(&mut upper2);
+    assert_eq!(upper1, upper2);
+    assert_eq!(upper2, Antichain::from_elem(3));
+
+    // The compaction frontiers are restored along with the batches.
+    assert_eq!(restored.advance_frontier().to_vec(), vec![1]);
+    assert_eq!(restored.distinguish_frontier().to_vec(), vec![1]);
+
+    for time in 1 .. 4 {
+        let (mut cursor1, storage1) = trace.cursor_through(AntichainRef::new(&[time])).unwrap();
+        let (mut cursor2, storage2) = restored.cursor_through(AntichainRef::new(&[time])).unwrap();
+        assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+    }
+}
+
+#[test]
+fn test_checkpoint_malformed_manifest() {
+
+    let directory = TempDir::new("differential-checkpoint");
+    ::std::fs::write(directory.path().join("MANIFEST"), "not a generation\n").unwrap();
+
+    // A checkpoint over an unreadable manifest fails rather than starting over.
+    let mut trace = get_trace();
+    assert!(checkpoint::write(&mut trace, directory.path()).is_err());
+}
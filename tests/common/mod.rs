@@ -0,0 +1,69 @@
+//! Helpers shared by the integration tests.
+
+// Each test file uses only some of the helpers.
+#![allow(dead_code)]
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use timely::dataflow::{Scope, ProbeHandle};
+use timely::dataflow::operators::Probe;
+
+use differential_dataflow::{Collection, ExchangeData, Hashable};
+use differential_dataflow::difference::Abelian;
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::Consolidate;
+
+/// Asserts that `actual` and `expected` have equal contents at each time, and attaches the check to `probe`.
+///
+/// Once `probe` has passed a time, the collections have been compared at all earlier times, and so a worker
+/// stepping while the probe is behind the input observes any failed assertion for the input it introduced.
+pub fn assert_eq_probed<G, D, R>(actual: &Collection<G, D, R>, expected: &Collection<G, D, R>, probe: &mut ProbeHandle<G::Timestamp>)
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    D: ExchangeData+Hashable,
+    R: ExchangeData+Hashable+Abelian,
+{
+    actual
+        .negate()
+        .concat(expected)
+        .consolidate()
+        .inspect(|x| panic!("Assertion failed: collections differ: {:?}", x))
+        .inner
+        .probe_with(probe);
+}
+
+/// A directory for the files of one test, removed along with its contents when dropped.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a new, empty directory whose name starts with `prefix`.
+    pub fn new(prefix: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        loop {
+            let name = format!("{}-{}-{}", prefix, ::std::process::id(), COUNTER.fetch_add(1, Ordering::SeqCst));
+            let path = ::std::env::temp_dir().join(name);
+            match fs::create_dir(&path) {
+                Ok(()) => return TempDir { path },
+                // Left behind by an earlier process with the same identifier.
+                Err(ref error) if error.kind() == io::ErrorKind::AlreadyExists => { },
+                Err(error) => panic!("failed to create {}: {}", path.display(), error),
+            }
+        }
+    }
+    /// The path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,157 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use timely::progress::Antichain;
+
+use differential_dataflow::durable::{self, LogWriter, DurableInput, SinkLog};
+
+use common::TempDir;
+
+/// Accumulates the diffs of each record, across all times.
+fn accumulate(updates: Vec<(u64, u64, isize)>) -> Vec<(u64, isize)> {
+    let mut totals = BTreeMap::new();
+    for (data, _time, diff) in updates {
+        *totals.entry(data).or_insert(0) += diff;
+    }
+    totals.into_iter().filter(|&(_, diff)| diff != 0).collect()
+}
+
+#[test]
+fn test_log_compaction() {
+
+    let directory = TempDir::new("differential-durable-compaction");
+    let path = directory.path();
+    {
+        let mut log = LogWriter::<u64, u64, isize>::open(path).unwrap();
+        log.set_segment_limit(1);
+        for time in 0 .. 10 {
+            let mut updates = vec![(time, time, 1)];
+            if time > 0 { updates.push((time - 1, time, -1)); }
+            log.append(&mut updates).unwrap();
+            log.advance_to(&[time + 1]).unwrap();
+        }
+    }
+
+    // Compaction keeps the log to a base and a few segments.
+    assert!(::std::fs::read_dir(path).unwrap().count() <= 3);
+
+    let (updates, frontier) = durable::replay::<u64, u64, isize, _>(path).unwrap();
+    assert_eq!(frontier, Antichain::from_elem(10));
+    assert_eq!(accumulate(updates), vec![(9, 1)]);
+}
+
+#[test]
+fn test_log_unsealed_updates() {
+
+    let directory = TempDir::new("differential-durable-unsealed");
+    let path = directory.path();
+    {
+        let mut log = LogWriter::<u64, u64, isize>::open(path).unwrap();
+        log.append(&mut vec![(0, 0, 1)]).unwrap();
+        log.advance_to(&[1]).unwrap();
+        // Not followed by a frontier, and so not durable.
+        log.append(&mut vec![(1, 1, 1)]).unwrap();
+        assert!(log.compact().is_err());
+    }
+
+    let (updates, frontier) = durable::replay::<u64, u64, isize, _>(path).unwrap();
+    assert_eq!(frontier, Antichain::from_elem(1));
+    assert_eq!(accumulate(updates), vec![(0, 1)]);
+}
+
+#[test]
+fn test_durable_input() {
+
+    let input_directory = TempDir::new("differential-durable-input");
+    let sink_directory = TempDir::new("differential-durable-sink");
+    let input_path = input_directory.path().to_path_buf();
+    let sink_path = sink_directory.path().to_path_buf();
+
+    {
+        let input_path = input_path.clone();
+        let sink_path = sink_path.clone();
+        timely::execute(timely::Configuration::Thread, move |worker| {
+
+            let mut input = DurableInput::<u64, u64, isize>::open(&input_path).unwrap();
+            worker.dataflow(|scope| {
+                input.to_collection(scope).sink_log(&sink_path).unwrap();
+            });
+
+            for data in 0 .. 5 { input.insert(data); }
+            input.advance_to(1).unwrap();
+            input.remove(0);
+            input.advance_to(2).unwrap();
+
+            // Not logged, as the session is not advanced past it, but seen by the sink.
+            input.insert(5);
+
+            drop(input);
+            while worker.step() { }
+        }).unwrap();
+    }
+
+    // Reopening replays the logged updates, and resumes at the logged time.
+    let input = DurableInput::<u64, u64, isize>::open(&input_path).unwrap();
+    assert_eq!(input.time(), &2);
+    drop(input);
+
+    let (updates, frontier) = durable::replay::<u64, u64, isize, _>(&input_path).unwrap();
+    assert_eq!(frontier, Antichain::from_elem(2));
+    assert_eq!(accumulate(updates), vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+
+    // The sink records each completed time, and the completion of its input.
+    let (updates, frontier) = durable::replay::<u64, u64, isize, _>(&sink_path).unwrap();
+    assert_eq!(frontier, Antichain::new());
+    assert_eq!(accumulate(updates), vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1)]);
+}
+
+#[test]
+fn test_durable_replay() {
+
+    let directory = TempDir::new("differential-durable-replay");
+    let path = directory.path().to_path_buf();
+
+    // Log more updates than the session buffers before sending.
+    {
+        let mut input = DurableInput::<u64, u64, isize>::open(&path).unwrap();
+        for data in 0 .. 5000 { input.insert(data); }
+        input.advance_to(1).unwrap();
+        for data in 0 .. 1000 { input.remove(data); }
+        input.advance_to(2).unwrap();
+    }
+
+    let path2 = path.clone();
+    let guards = timely::execute(timely::Configuration::Thread, move |worker| {
+
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use timely::dataflow::operators::{Inspect, Probe};
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let results2 = results.clone();
+
+        let mut input = DurableInput::<u64, u64, isize>::open(&path2).unwrap();
+        assert_eq!(input.time(), &2);
+        let probe = worker.dataflow(|scope| {
+            input.to_collection(scope)
+                 .inner
+                 .inspect(move |x| results2.borrow_mut().push(x.clone()))
+                 .probe()
+        });
+
+        input.insert(5000);
+        input.advance_to(3).unwrap();
+        input.flush();
+        while probe.less_than(input.time()) { worker.step(); }
+
+        let results = results.borrow().clone();
+        results
+    }).unwrap();
+
+    let results = guards.join().into_iter().flat_map(|result| result.unwrap()).collect::<Vec<_>>();
+    assert_eq!(accumulate(results), (1000 .. 5001).map(|data| (data, 1)).collect::<Vec<_>>());
+}
@@ -1,11 +1,15 @@
 extern crate timely;
 extern crate differential_dataflow;
 
+mod common;
+
 use timely::dataflow::operators::{ToStream, Capture, Map};
 use timely::dataflow::operators::capture::Extract;
 use differential_dataflow::AsCollection;
 use differential_dataflow::operators::{Consolidate, Join, Count};
 
+use common::assert_eq_probed;
+
 #[test]
 fn join() {
 
@@ -101,4 +105,292 @@ fn join_scaling(scale: u64) {
 
     let extracted = data.extract();
     assert_eq!(extracted.len(), 0);
-}
\ No newline at end of file
+}
+#[test]
+fn join_replicated() {
+
+    use differential_dataflow::input::Input;
+    use differential_dataflow::operators::arrange::ArrangeReplicated;
+    use differential_dataflow::operators::join::JoinCore;
+    use differential_dataflow::trace::implementations::ord::OrdValSpine;
+
+    timely::execute(timely::Configuration::Process(3), |worker| {
+        let index = worker.index() as u32;
+        worker.dataflow::<(),_,_>(|scope| {
+
+            // Each worker introduces different records, which are neither partitioned nor exchanged.
+            let facts = scope.new_collection_from((0 .. 100u32).map(move |x| (x % 10, x + index))).1;
+            let dimension = scope.new_collection_from((0 .. 5u32).map(move |x| (x + index, index))).1;
+
+            let replicated = dimension.arrange_replicated::<OrdValSpine<_,_,_,_>>();
+
+            facts.join_replicated(&replicated, |k, &f, &d| Some((*k, f, d)))
+                 .assert_eq(&facts.join(&dimension).map(|(k, (f, d))| (k, f, d)));
+        });
+    }).unwrap();
+}
+
+#[test]
+fn outer_joins() {
+
+    use differential_dataflow::input::InputSession;
+    use differential_dataflow::operators::Threshold;
+    use timely::dataflow::ProbeHandle;
+
+    timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut input1 = InputSession::new();
+        let mut input2 = InputSession::new();
+        let mut probe = ProbeHandle::new();
+
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let x = input1.to_collection(scope);
+            let y = input2.to_collection(scope);
+
+            let matched = x.join(&y).map(|(k, (a, b))| (k, Some(a), Some(b)));
+            let left = x.antijoin(&y.map(|(k, _)| k).distinct()).map(|(k, a)| (k, Some(a), None));
+            let right = y.antijoin(&x.map(|(k, _)| k).distinct()).map(|(k, b)| (k, None, Some(b)));
+
+            assert_eq_probed(&x.left_join(&y), &matched.concat(&left), &mut probe);
+            assert_eq_probed(&x.right_join(&y), &matched.concat(&right), &mut probe);
+            assert_eq_probed(&x.full_join(&y), &matched.concat(&left).concat(&right), &mut probe);
+        });
+
+        if worker.index() == 0 {
+            for key in 0 .. 10u32 {
+                input1.insert((key, key));
+                input2.insert((key + 5, (key as u64) << 32));
+            }
+            // Matches appear and disappear, with multiplicities.
+            for round in 1 .. 10u32 {
+                input1.advance_to(round);
+                input2.advance_to(round);
+                input2.insert((round, 0));
+                input2.insert((round, 0));
+                input2.remove((round + 4, ((round - 1) as u64) << 32));
+                input1.remove((round - 1, round - 1));
+                input1.flush();
+                input2.flush();
+                worker.step_while(|| probe.less_than(input1.time()));
+            }
+        }
+    }).unwrap();
+}
+
+#[test]
+fn join_as_of() {
+
+    use differential_dataflow::input::InputSession;
+    use differential_dataflow::operators::Reduce;
+    use differential_dataflow::operators::arrange::ArrangeByKey;
+    use differential_dataflow::operators::asof::AsOfJoin;
+    use timely::dataflow::ProbeHandle;
+
+    timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut events = InputSession::new();
+        let mut versions = InputSession::new();
+        let mut probe = ProbeHandle::new();
+
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let events = events.to_collection(scope);
+            let versions = versions.to_collection(scope);
+
+            let expected =
+            events
+                .join(&versions)
+                .filter(|&(_, ((time, _), (from, _)))| from <= time)
+                .map(|(key, ((time, val), version))| ((key, time, val), version))
+                .reduce(|_, input, output| output.push(((input[input.len()-1].0).1, 1)))
+                .map(|((key, time, val), dim)| (key, time, val, dim));
+
+            let joined = events.join_as_of(&versions.arrange_by_key(), |&key, &time, &val, &dim| (key, time, val, dim));
+            assert_eq_probed(&joined, &expected, &mut probe);
+        });
+
+        if worker.index() == 0 {
+            for key in 0 .. 5u32 {
+                for time in 0 .. 20u32 {
+                    events.insert((key, (time, key * time)));
+                }
+                versions.insert((key, (5u32, 'a')));
+                versions.insert((key, (10u32, 'b')));
+            }
+            // Corrections to the history: versions are inserted before, between, and after others, and removed.
+            for round in 1 .. 10u32 {
+                events.advance_to(round);
+                versions.advance_to(round);
+                let key = round % 5;
+                match round % 3 {
+                    0 => versions.insert((key, (round, 'c'))),
+                    1 => versions.remove((key, (10, 'b'))),
+                    _ => versions.insert((key, (10, 'd'))),
+                }
+                events.insert((key, (100 + round, round)));
+                events.flush();
+                versions.flush();
+                worker.step_while(|| probe.less_than(events.time()));
+            }
+        }
+    }).unwrap();
+}
+
+#[test]
+fn band_join() {
+
+    use differential_dataflow::input::InputSession;
+    use differential_dataflow::operators::arrange::ArrangeByKey;
+    use differential_dataflow::operators::band::BandJoin;
+    use differential_dataflow::operators::join::{JoinConfig, WorkOrder};
+    use differential_dataflow::trace::cursor::range::Between;
+    use timely::dataflow::ProbeHandle;
+
+    timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut left = InputSession::new();
+        let mut right = InputSession::new();
+        let mut probe = ProbeHandle::new();
+
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let left = left.to_collection(scope);
+            let right = right.to_collection(scope);
+
+            // All pairs of records, where the key of the second is from three before to four after the first.
+            let expected =
+            left.map(|record| ((), record))
+                .join(&right.map(|record| ((), record)))
+                .map(|((), pair)| pair)
+                .filter(|&((key1, _), (key2, _)): &((u32, u32), (u32, char))| key1 <= key2 + 3 && key2 <= key1 + 4);
+
+            let joined = left.band_join(&right, |&key| Between { lower: key.saturating_sub(3), upper: key + 5 });
+            assert_eq_probed(&joined, &expected, &mut probe);
+
+            // Little fuel, so that work is deferred across many activations.
+            let config = JoinConfig::new().fuel(10).order(WorkOrder::SmallestFirst);
+            let configured = left.band_join_core_with(&right.arrange_by_key(), |&key| Between { lower: key.saturating_sub(3), upper: key + 5 }, config, |&key1, &val1, &key2, &val2| Some(((key1, val1), (key2, val2))));
+            assert_eq_probed(&configured, &expected, &mut probe);
+        });
+
+        if worker.index() == 0 {
+            for key in 0 .. 50u32 {
+                left.insert((key * 7, key));
+                right.insert((key * 3, 'a'));
+            }
+            // Changes to both inputs, some at the same time.
+            for round in 1 .. 10u32 {
+                left.advance_to(round);
+                right.advance_to(round);
+                left.insert((round * 11, round));
+                if round % 2 == 0 { left.remove((round * 7, round)); }
+                right.insert((round * 13, 'b'));
+                if round % 3 == 0 { right.remove((round * 3, 'a')); }
+                left.flush();
+                right.flush();
+                worker.step_while(|| probe.less_than(left.time()));
+            }
+        }
+    }).unwrap();
+}
+
+#[test]
+fn join_skewed() {
+
+    use differential_dataflow::input::InputSession;
+    use differential_dataflow::operators::skew::JoinSkewed;
+    use timely::dataflow::ProbeHandle;
+
+    timely::execute(timely::Configuration::Process(4), |worker| {
+
+        let mut edges = InputSession::new();
+        let mut names = InputSession::new();
+        let mut probe = ProbeHandle::new();
+
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let edges = edges.to_collection(scope);
+            let names = names.to_collection(scope);
+
+            assert_eq_probed(&edges.join_skewed(&names, vec![0, 1]), &edges.join(&names), &mut probe);
+        });
+
+        if worker.index() == 0 {
+            // Nodes zero and one have most of the edges.
+            for node in 0 .. 1000u32 {
+                edges.insert((node % 2, node));
+                edges.insert((node, node + 1));
+                names.insert((node, node % 7));
+            }
+            for round in 1 .. 10u32 {
+                edges.advance_to(round);
+                names.advance_to(round);
+                edges.remove((round % 2, round));
+                edges.insert((round % 2, 1000 + round));
+                // Renames nodes zero and one, and gives another node a second name.
+                names.remove((round % 2, if round > 2 { round - 2 } else { round % 2 }));
+                names.insert((round % 2, round));
+                names.insert((10 + round, round));
+                edges.flush();
+                names.flush();
+                worker.step_while(|| probe.less_than(edges.time()));
+            }
+        }
+    }).unwrap();
+}
+
+#[test]
+fn join_core_with() {
+
+    use differential_dataflow::input::InputSession;
+    use differential_dataflow::operators::arrange::ArrangeByKey;
+    use differential_dataflow::operators::join::{JoinCore, JoinConfig, WorkOrder, YieldPolicy};
+    use timely::dataflow::ProbeHandle;
+
+    timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut input1 = InputSession::new();
+        let mut input2 = InputSession::new();
+        let mut probe = ProbeHandle::new();
+
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let input1 = input1.to_collection(scope);
+            let input2 = input2.to_collection(scope);
+
+            let expected = input1.join(&input2);
+
+            let arranged1 = input1.arrange_by_key();
+            let arranged2 = input2.arrange_by_key();
+
+            // Little fuel, so that work is deferred across many activations.
+            let configs = vec![
+                JoinConfig::new().fuel(10),
+                JoinConfig::new().fuel(10).order(WorkOrder::SmallestFirst),
+                JoinConfig::new().fuel(100).yield_policy(YieldPolicy::Batch).order(WorkOrder::SmallestFirst),
+                JoinConfig::new().yield_policy(YieldPolicy::Elapsed(::std::time::Duration::from_millis(0))),
+                JoinConfig::new().fuel(10).yield_policy(YieldPolicy::Elapsed(::std::time::Duration::from_micros(10))).order(WorkOrder::SmallestFirst),
+            ];
+            for config in configs {
+                let joined = arranged1.join_core_with(&arranged2, config, |&key, &val1, &val2| Some((key, (val1, val2))));
+                assert_eq_probed(&joined, &expected, &mut probe);
+            }
+        });
+
+        if worker.index() == 0 {
+            for round in 0 .. 10u32 {
+                // Batches of varying sizes.
+                for val in 0 .. 100 * (round % 3) {
+                    input1.insert((val % 10, round * 1000 + val));
+                }
+                input2.insert((round % 10, round));
+                input1.advance_to(round + 1);
+                input2.advance_to(round + 1);
+                input1.flush();
+                input2.flush();
+                worker.step_while(|| probe.less_than(input1.time()));
+            }
+        }
+    }).unwrap();
+}
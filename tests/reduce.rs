@@ -42,4 +42,88 @@ fn reduce_scaling() {
 
     let extracted = data.extract();
     assert_eq!(extracted.len(), 1);
-}
\ No newline at end of file
+}
+#[test]
+fn reduce_partitioned() {
+
+    use differential_dataflow::input::Input;
+    use differential_dataflow::operators::Consolidate;
+    use differential_dataflow::operators::partitioned::{Partitioned, PartitionByKey};
+
+    timely::execute(timely::Configuration::Process(3), |worker| {
+        let index = worker.index() as u32;
+        worker.dataflow::<(),_,_>(|scope| {
+
+            let data = scope.new_collection_from((0 .. 100u32).map(move |x| (x % 7, x * index))).1;
+
+            // A second reduce of the first's output needs no exchange, and must match one that exchanges.
+            let partitioned = data.partition_by_key();
+            let maxima = partitioned.reduce(|_k, s, t| t.push((*s[s.len()-1].0, 1isize)));
+            let totals = Partitioned::new_unchecked(maxima.clone())
+                .concat(&partitioned.consolidate())
+                .reduce(|_k, s, t| t.push((s.len(), 1isize)));
+
+            let expected = data.reduce(|_k, s, t| t.push((*s[s.len()-1].0, 1isize)))
+                .concat(&data)
+                .reduce(|_k, s, t| t.push((s.len(), 1isize)));
+
+            totals.assert_eq(&expected);
+            maxima.assert_eq(&data.reduce(|_k, s, t| t.push((*s[s.len()-1].0, 1isize))));
+        });
+    }).unwrap();
+}
+
+#[test]
+fn aggregate() {
+
+    use differential_dataflow::input::InputSession;
+    use differential_dataflow::Collection;
+    use differential_dataflow::lattice::Lattice;
+    use differential_dataflow::operators::{Aggregate, Iterate, Threshold};
+    use timely::dataflow::Scope;
+    use timely::dataflow::operators::Probe;
+
+    // The sums of the values of each key, which are not reported when zero.
+    fn sums<G: Scope>(collection: &Collection<G, (u32, u32)>) -> Collection<G, (u32, isize)>
+    where G::Timestamp: Lattice+Ord {
+        collection.reduce(|_key, input, output| {
+            let sum = input.iter().map(|&(val, count)| *val as isize * count).sum::<isize>();
+            if sum != 0 { output.push((sum, 1)); }
+        })
+    }
+
+    timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut input = InputSession::new();
+
+        let probe = worker.dataflow::<u32,_,_>(|scope| {
+
+            let data = input.to_collection(scope);
+
+            data.aggregate(|&val, &diff| val as isize * diff)
+                .assert_eq(&sums(&data));
+
+            // Partially ordered times, as the inputs change across rounds and the iterations within each.
+            data.iterate(|inner| {
+                    let data = data.enter(&inner.scope());
+                    let next = inner.map(|(key, val)| (key, val / 2)).concat(&data).distinct();
+                    next.aggregate(|&val, &diff| val as isize * diff)
+                        .assert_eq(&sums(&next));
+                    next
+                })
+                .probe()
+        });
+
+        if worker.index() == 0 {
+            for round in 0 .. 10u32 {
+                for key in 0 .. 10u32 {
+                    input.insert((key, 100 * round + key));
+                    if round > 0 && key % 3 == round % 3 { input.remove((key, 100 * (round - 1) + key)); }
+                }
+                input.advance_to(round + 1);
+                input.flush();
+                worker.step_while(|| probe.less_than(input.time()));
+            }
+        }
+    }).unwrap();
+}
@@ -0,0 +1,109 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::*;
+
+use differential_dataflow::input::InputSession;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+
+#[test]
+fn test_remote() {
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        use std::net::TcpListener;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use differential_dataflow::operators::arrange::remote;
+        use differential_dataflow::trace::implementations::ord::OrdValSpine;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let mut input = InputSession::<usize, (u64, u64), isize>::new();
+        let mut trace = worker.dataflow(|scope| {
+            input.to_collection(scope).arrange_by_key().trace
+        });
+
+        input.insert((1, 10));
+        input.insert((2, 20));
+        input.advance_to(1);
+        input.flush();
+        worker.step();
+
+        worker.dataflow(|scope| trace.publish(scope, "Publish", listener));
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let results2 = results.clone();
+        let (probe, subscription) = worker.dataflow(|scope| {
+            // The publisher is built from this code, and trusted.
+            let (arranged, subscription) = unsafe { remote::subscribe::<_, OrdValSpine<u64, u64, usize, isize>, _>(scope, "Subscribe", address) }.unwrap();
+            let probe =
+            arranged
+                .as_collection(|k, v| (*k, *v))
+                .inner
+                .inspect(move |x| results2.borrow_mut().push(x.clone()))
+                .probe();
+            (probe, subscription)
+        });
+
+        // Updates after the subscriber connects arrive as subsequent batches.
+        input.remove((1, 10));
+        input.insert((3, 30));
+        input.close();
+
+        while !probe.done() {
+            worker.step();
+            ::std::thread::yield_now();
+        }
+
+        let mut results = results.borrow().clone();
+        results.sort();
+        assert_eq!(results, vec![((1, 10), 0, 1), ((1, 10), 1, -1), ((2, 20), 0, 1), ((3, 30), 1, 1)]);
+        assert!(subscription.take_error().is_none());
+
+    }).unwrap();
+}
+
+#[test]
+fn test_remote_oversized_frame() {
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        use std::io::Write;
+        use std::net::TcpListener;
+        use differential_dataflow::operators::arrange::remote;
+        use differential_dataflow::trace::implementations::ord::OrdValSpine;
+
+        // A publisher that announces a frame longer than any subscriber accepts.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let publisher = ::std::thread::spawn(move || {
+            let (mut connection, _) = listener.accept().unwrap();
+            let mut header = vec![0u8];
+            header.extend_from_slice(&u64::max_value().to_le_bytes());
+            connection.write_all(&header).unwrap();
+        });
+
+        let (probe, mut subscription) = worker.dataflow(|scope| {
+            // The publisher fails before sending anything to decode.
+            let (arranged, subscription) = unsafe { remote::subscribe::<_, OrdValSpine<u64, u64, usize, isize>, _>(scope, "Subscribe", address) }.unwrap();
+            (arranged.stream.probe(), subscription)
+        });
+
+        let error = loop {
+            if let Some(error) = subscription.take_error() { break error; }
+            worker.step();
+            ::std::thread::yield_now();
+        };
+        publisher.join().unwrap();
+        assert_eq!(error.kind(), ::std::io::ErrorKind::InvalidData);
+
+        // The failed subscription holds its frontier until abandoned.
+        worker.step();
+        assert!(!probe.done());
+        subscription.abandon();
+        while !probe.done() {
+            worker.step();
+        }
+
+    }).unwrap();
+}
@@ -0,0 +1,100 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+mod common;
+
+use timely::dataflow::ProbeHandle;
+
+use differential_dataflow::input::InputSession;
+use differential_dataflow::operators::{Reduce, TopK};
+
+use common::assert_eq_probed;
+
+#[test]
+fn test_top_k() {
+
+    timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut input = InputSession::new();
+        let mut probe = ProbeHandle::new();
+
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let data = input.to_collection(scope);
+
+            // The third through fifth greatest values of each key.
+            let top = data.top_k_by(2, 3, |x: &u32, y: &u32| y.cmp(x));
+
+            let expected = data.reduce(|_key, input, output| {
+                let mut values = Vec::new();
+                for &(val, count) in input.iter().rev() {
+                    for _ in 0 .. count { values.push(*val); }
+                }
+                for val in values.into_iter().skip(2).take(3) {
+                    output.push((val, 1));
+                }
+            });
+
+            assert_eq_probed(&top, &expected, &mut probe);
+        });
+
+        if worker.index() == 0 {
+            for round in 0 .. 10u32 {
+                for key in 0 .. 5u32 {
+                    for val in 0 .. 100u32 {
+                        if round > 0 && val % 10 == round { input.remove((key, val + 100 * round)); }
+                        input.insert((key, val + 100 * (round + 1)));
+                    }
+                }
+                // Duplicates occupy several places.
+                input.insert((round % 5, 10_000));
+                input.insert((round % 5, 10_000));
+                input.advance_to(round + 1);
+                input.flush();
+                worker.step_while(|| probe.less_than(input.time()));
+            }
+        }
+    }).unwrap();
+}
+
+#[test]
+fn test_min_max() {
+
+    use differential_dataflow::operators::MinMax;
+
+    timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let mut input = InputSession::new();
+        let mut probe = ProbeHandle::new();
+
+        worker.dataflow::<u32,_,_>(|scope| {
+
+            let data = input.to_collection(scope);
+
+            assert_eq_probed(&data.min(), &data.reduce(|_key, input, output| output.push((*input[0].0, 1))), &mut probe);
+            assert_eq_probed(&data.max(), &data.reduce(|_key, input, output| output.push((*input[input.len()-1].0, 1))), &mut probe);
+            let argmin = data.reduce(|_key, input, output| {
+                let least = input.iter().map(|&(val, _)| *val).min_by_key(|val| val % 7).unwrap();
+                output.push((least, 1))
+            });
+            assert_eq_probed(&data.argmin(|val| val % 7), &argmin, &mut probe);
+            let argmax = data.reduce(|_key, input, output| {
+                let greatest = input.iter().rev().map(|&(val, _)| *val).max_by_key(|val| val % 7).unwrap();
+                output.push((greatest, 1))
+            });
+            assert_eq_probed(&data.argmax(|val| val % 7), &argmax, &mut probe);
+        });
+
+        if worker.index() == 0 {
+            // Retract the extreme values of each key, one round at a time.
+            for val in 0 .. 1000u32 { input.insert((val % 3, val)); }
+            for round in 0 .. 10u32 {
+                input.remove((round % 3, round));
+                input.remove(((999 - round) % 3, 999 - round));
+                input.advance_to(round + 1);
+                input.flush();
+                worker.step_while(|| probe.less_than(input.time()));
+            }
+        }
+    }).unwrap();
+}
@@ -1,17 +1,28 @@
 extern crate timely;
 extern crate differential_dataflow;
 
+mod common;
+
 use std::rc::Rc;
 
 use timely::dataflow::operators::generic::OperatorInfo;
 use timely::progress::{Antichain, frontier::AntichainRef};
 
-use differential_dataflow::hashable::UnsignedWrapper;
+use differential_dataflow::hashable::{UnsignedWrapper, OrdWrapper};
 
 use differential_dataflow::trace::implementations::ord::OrdValBatch;
-use differential_dataflow::trace::{Trace, TraceReader, Batch, Batcher};
-use differential_dataflow::trace::cursor::CursorDebug;
+use differential_dataflow::trace::{Trace, TraceReader, Batch, BatchReader, Batcher, Builder, Cursor, Statistics};
+use differential_dataflow::trace::cursor::{CursorDebug, Prefix};
 use differential_dataflow::trace::implementations::spine_fueled::Spine;
+use differential_dataflow::trace::merge_policy::{MergePolicy, LowLatency, LowMemory};
+use differential_dataflow::trace::implementations::ord::{OrdValSpineRadix, OrdValSpineBackground, OrdValSpineSpill};
+use differential_dataflow::trace::implementations::hash::HashValSpine;
+use differential_dataflow::trace::implementations::radix_batcher::RadixKey;
+use differential_dataflow::trace::implementations::background::{self, BackgroundConfig};
+use differential_dataflow::trace::implementations::spill::{self, SpillConfig};
+use differential_dataflow::trace::implementations::interned::{InternedValSpine, Interner};
+
+use common::TempDir;
 
 pub type OrdValSpine<K, V, T, R> = Spine<K, V, T, R, Rc<OrdValBatch<K, V, T, R>>>;
 
@@ -66,6 +77,435 @@ fn test_trace() {
     assert_eq!(vec_4, vec_3);
 }
 
+/// Inserts `updates` into a trace with the default batcher and one with the radix batcher, and compares them.
+fn compare_radix<K>(updates: Vec<((K, u64), usize, i64)>)
+where
+    K: RadixKey+Clone+::std::fmt::Debug+'static,
+{
+    let mut reference = OrdValSpine::<K, u64, usize, i64>::new(OperatorInfo::new(0, 0, &[]), None, None);
+    let mut trace = OrdValSpineRadix::<K, u64, usize, i64>::new(OperatorInfo::new(0, 0, &[]), None, None);
+    {
+        let mut batcher1 = <<OrdValSpine<K, u64, usize, i64> as TraceReader>::Batch as Batch<K, u64, usize, i64>>::Batcher::new();
+        let mut batcher2 = <<OrdValSpineRadix<K, u64, usize, i64> as TraceReader>::Batch as Batch<K, u64, usize, i64>>::Batcher::new();
+        batcher1.push_batch(&mut updates.clone());
+        batcher2.push_batch(&mut updates.clone());
+        for time in 1 .. 4 {
+            reference.insert(batcher1.seal(Antichain::from_elem(time)));
+            trace.insert(batcher2.seal(Antichain::from_elem(time)));
+        }
+    }
+
+    let (mut cursor1, storage1) = reference.cursor();
+    let (mut cursor2, storage2) = trace.cursor();
+    assert_eq!(cursor2.to_vec(&storage2), cursor1.to_vec(&storage1));
+}
+
+#[test]
+fn test_radix_batcher() {
+
+    // Keys ordered by their hashes, with repeated updates and a key whose updates cancel.
+    compare_radix::<UnsignedWrapper<u64>>(vec![
+        ((2.into(), 3), 2, -1),
+        (((1u64 << 40).into(), 1), 1, 1),
+        ((2.into(), 3), 1, 1),
+        ((1.into(), 2), 0, 1),
+        ((1.into(), 2), 0, 1),
+        (((1u64 << 40).into(), 1), 1, -1),
+    ]);
+
+    // Plain unsigned keys, which are their own radix.
+    compare_radix::<u32>((0 .. 1000u32).map(|x| ((x.wrapping_mul(2654435761) % 300, (x % 7) as u64), (x % 3) as usize, if x % 5 == 0 { -1 } else { 1 })).collect());
+}
+
+#[test]
+fn test_hash_trace() {
+
+    type HashTrace = HashValSpine<OrdWrapper<u64>, u64, usize, i64>;
+
+    let op_info = OperatorInfo::new(0, 0, &[]);
+    let mut trace = HashTrace::new(op_info, None, None);
+    {
+        let mut batcher = <<HashTrace as TraceReader>::Batch as Batch<OrdWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        for time in 0 .. 10 {
+            let mut updates = (0 .. 100u64).map(|key| ((OrdWrapper { item: key }, key * time as u64), time, 1)).collect();
+            batcher.push_batch(&mut updates);
+            trace.insert(batcher.seal(Antichain::from_elem(time + 1)));
+        }
+    }
+
+    let (mut cursor, storage) = trace.cursor();
+    for key in (0 .. 100u64).rev() {
+        cursor.rewind_keys(&storage);
+        cursor.seek_key(&storage, &OrdWrapper { item: key });
+        assert!(cursor.key_valid(&storage));
+        assert_eq!(cursor.key(&storage).item, key);
+        let mut count = 0;
+        while cursor.val_valid(&storage) {
+            assert_eq!(cursor.val(&storage) % key.max(1), 0);
+            count += 1;
+            cursor.step_val(&storage);
+        }
+        assert_eq!(count, if key == 0 { 1 } else { 10 });
+    }
+    cursor.rewind_keys(&storage);
+    cursor.seek_key(&storage, &OrdWrapper { item: 1000 });
+    assert!(!cursor.key_valid(&storage) || cursor.key(&storage).item != 1000);
+
+    // Seeking by a predicate visits the same keys, in the same order, as seeking each key.
+    cursor.rewind_keys(&storage);
+    let mut keys = Vec::new();
+    while cursor.key_valid(&storage) {
+        keys.push(cursor.key(&storage).clone());
+        cursor.step_key(&storage);
+    }
+    assert_eq!(keys.len(), 100);
+    cursor.rewind_keys(&storage);
+    for key in keys.iter() {
+        cursor.seek_key_with(&storage, |other| other < key);
+        assert_eq!(cursor.key(&storage), key);
+    }
+}
+
+#[test]
+fn test_from_ordered() {
+
+    type IntegerBatch = OrdValBatch<u64, u64, usize, i64>;
+    type IntegerBuilder = <IntegerBatch as Batch<u64, u64, usize, i64>>::Builder;
+
+    let updates: Vec<(u64, u64, usize, i64)> = vec![
+        (0, 0, 0, 1),
+        (0, 0, 2, -1),
+        (0, 3, 1, 2),
+        (4, 1, 0, 1),
+        (5, 0, 1, 1),
+        (5, 2, 1, -3),
+    ];
+
+    let lower = Antichain::from_elem(0);
+    let upper = Antichain::from_elem(3);
+    let since = Antichain::from_elem(0);
+
+    let ordered = IntegerBuilder::from_ordered(updates.clone(), lower.clone(), upper.clone(), since.clone());
+    let mut builder = IntegerBuilder::new();
+    for update in updates.iter().cloned() {
+        builder.push_ordered(update);
+    }
+    let pushed = builder.done(lower, upper, since);
+
+    assert_eq!(ordered.len(), updates.len());
+    assert_eq!(ordered.cursor().to_vec(&ordered), pushed.cursor().to_vec(&pushed));
+
+    let empty = IntegerBuilder::from_ordered(Vec::new(), Antichain::from_elem(0), Antichain::from_elem(1), Antichain::from_elem(0));
+    assert_eq!(empty.len(), 0);
+    assert!(!empty.cursor().key_valid(&empty));
+}
+
+#[test]
+fn test_range_scan() {
+
+    type PairBatch = OrdValBatch<(u64, u64), u64, usize, i64>;
+    type PairBuilder = <PairBatch as Batch<(u64, u64), u64, usize, i64>>::Builder;
+
+    let updates = (0 .. 10u64).flat_map(|a| (0 .. 10u64).map(move |b| ((a, b), a + b, 0, 1))).collect::<Vec<_>>();
+    let batch = PairBuilder::from_ordered(updates, Antichain::from_elem(0), Antichain::from_elem(1), Antichain::from_elem(0));
+
+    let mut cursor = batch.cursor();
+    let range = (3, 5) .. (4, 2);
+    let mut keys = Vec::new();
+    cursor.seek_range(&batch, &range);
+    while cursor.key_in_range(&batch, &range) {
+        keys.push(*cursor.key(&batch));
+        cursor.step_key(&batch);
+    }
+    assert_eq!(keys, vec![(3, 5), (3, 6), (3, 7), (3, 8), (3, 9), (4, 0), (4, 1)]);
+
+    cursor.rewind_keys(&batch);
+    let mut count = 0;
+    assert!(cursor.seek_range(&batch, &Prefix(7)));
+    while cursor.key_in_range(&batch, &Prefix(7)) {
+        assert_eq!(cursor.key(&batch).0, 7);
+        count += 1;
+        cursor.step_key(&batch);
+    }
+    assert_eq!(count, 10);
+
+    cursor.rewind_keys(&batch);
+    assert!(!cursor.seek_range(&batch, &Prefix(10)));
+}
+
+#[test]
+fn test_statistics() {
+
+    let mut trace = get_trace();
+    let levels = trace.statistics();
+
+    let mut total = Statistics::default();
+    for level in levels.iter() {
+        total += *level;
+    }
+    assert_eq!(total.updates, 3);
+    assert_eq!(total.updates, {
+        let mut updates = 0;
+        trace.map_batches(|batch| updates += batch.len());
+        updates
+    });
+    assert!(total.batch_keys >= 2);
+    assert!(total.batch_vals >= 2);
+    assert!(total.heap_bytes > 0);
+
+    let (cursor, storage) = trace.cursor();
+    let walked = Statistics::from_cursor(cursor, &storage);
+    assert_eq!((walked.batch_keys, walked.batch_vals, walked.updates), (2, 2, 3));
+}
+
+#[test]
+fn test_memory_budget() {
+
+    let mut trace = IntegerTrace::with_budget(1, 1 << 30, OperatorInfo::new(0, 0, &[]), None, None);
+    let pressure = trace.memory_pressure().unwrap();
+    {
+        let mut batcher = <<IntegerTrace as TraceReader>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        for time in 0 .. 10 {
+            let mut updates = (0 .. 100u64).map(|key| ((key.into(), time as u64), time, 1)).collect();
+            batcher.push_batch(&mut updates);
+            trace.insert(batcher.seal(Antichain::from_elem(time + 1)));
+        }
+    }
+    assert!(pressure.heap_bytes() > 0);
+    assert!(!pressure.over_budget());
+
+    trace.set_memory_budget(Some(0));
+    assert!(pressure.over_budget());
+
+    trace.set_memory_budget(None);
+    assert!(trace.memory_pressure().is_none());
+    assert!(!pressure.over_budget());
+}
+
+#[test]
+fn test_merge_policy() {
+
+    let mut latency = IntegerTrace::with_policy(1, LowLatency::default(), OperatorInfo::new(0, 0, &[]), None, None);
+    let mut memory = IntegerTrace::with_policy(1, LowMemory, OperatorInfo::new(0, 0, &[]), None, None);
+    latency.distinguish_since(AntichainRef::new(&[]));
+    memory.distinguish_since(AntichainRef::new(&[]));
+    {
+        let mut batcher = <<IntegerTrace as TraceReader>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        for time in 0 .. 10 {
+            let mut updates = (0 .. 100u64).map(|key| ((key.into(), time as u64), time, 1)).collect();
+            batcher.push_batch(&mut updates);
+            let batch = batcher.seal(Antichain::from_elem(time + 1));
+            latency.insert(batch.clone());
+            memory.insert(batch);
+        }
+    }
+
+    let (mut cursor1, storage1) = latency.cursor();
+    let (mut cursor2, storage2) = memory.cursor();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+
+    // Idle effort merges all batches under the low memory policy.
+    for _ in 0 .. 100 {
+        memory.exert(&mut 1);
+    }
+    let mut batches = 0;
+    memory.map_batches(|batch| if batch.len() > 0 { batches += 1; });
+    assert_eq!(batches, 1);
+}
+
+#[test]
+fn test_merge_geometry() {
+
+    // Introduces every batch at the lowest level, and supplies no fuel for it.
+    struct Flat;
+    impl MergePolicy for Flat {
+        fn level(&self, _len: usize) -> usize { 0 }
+        fn fuel(&self, _level: usize, _effort: usize) -> isize { 0 }
+    }
+
+    let mut natural = IntegerTrace::new(OperatorInfo::new(0, 0, &[]), None, None);
+    let mut flat = IntegerTrace::with_policy(1, Flat, OperatorInfo::new(0, 0, &[]), None, None);
+    natural.distinguish_since(AntichainRef::new(&[]));
+    flat.distinguish_since(AntichainRef::new(&[]));
+    {
+        // Each batch holds one distinct record, so that merges neither cancel nor consolidate records.
+        let mut batcher = <<IntegerTrace as TraceReader>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        for time in 0 .. 1024 {
+            batcher.push_batch(&mut vec![(((time as u64).into(), 0), time, 1)]);
+            let batch = batcher.seal(Antichain::from_elem(time + 1));
+            natural.insert(batch.clone());
+            flat.insert(batch);
+
+            // The default policy keeps the number of batches logarithmic in the number of records.
+            let records = time + 1;
+            let mut batches = 0;
+            natural.map_batches(|batch| if batch.len() > 0 { batches += 1; });
+            let log2 = (records as f64).log2() as usize;
+            assert!(batches <= log2 + 2, "{} batches for {} records", batches, records);
+        }
+    }
+
+    let (mut cursor1, storage1) = natural.cursor();
+    let (mut cursor2, storage2) = flat.cursor();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+
+    // Without fuel, merges remain in progress, and exerting the trace draws the effort it spends
+    // from the caller's effort.
+    let mut batches = 0;
+    flat.map_batches(|batch| if batch.len() > 0 { batches += 1; });
+    assert!(batches > 1);
+    let mut effort = 1000;
+    flat.exert(&mut effort);
+    assert!(effort < 1000);
+}
+
+#[test]
+fn test_background_merge() {
+
+    type BackgroundTrace = OrdValSpineBackground<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    // Hand every merge made by this thread to a helper thread.
+    background::configure(BackgroundConfig { threads: 2, threshold: 0 });
+
+    let mut foreground = IntegerTrace::new(OperatorInfo::new(0, 0, &[]), None, None);
+    let mut background = BackgroundTrace::new(OperatorInfo::new(0, 0, &[]), None, None);
+    foreground.distinguish_since(AntichainRef::new(&[]));
+    background.distinguish_since(AntichainRef::new(&[]));
+    {
+        let mut batcher1 = <<IntegerTrace as TraceReader>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        let mut batcher2 = <<BackgroundTrace as TraceReader>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        for time in 0 .. 20 {
+            let updates: Vec<_> = (0 .. 100u64).map(|key| ((key.into(), key % 7), time, if time % 2 == 0 { 1 } else { -1 })).collect();
+            batcher1.push_batch(&mut updates.clone());
+            batcher2.push_batch(&mut updates.clone());
+            foreground.insert(batcher1.seal(Antichain::from_elem(time + 1)));
+            background.insert(batcher2.seal(Antichain::from_elem(time + 1)));
+        }
+    }
+    for _ in 0 .. 100 {
+        foreground.exert(&mut 1000);
+        background.exert(&mut 1000);
+    }
+    // Merges remain in progress until their helper threads deliver them, rather than block the worker.
+    let mut batches = 2;
+    while batches > 1 {
+        background.exert(&mut 1000);
+        batches = 0;
+        background.map_batches(|batch| if !batch.is_empty() { batches += 1; });
+    }
+
+    let (mut cursor1, storage1) = foreground.cursor();
+    let (mut cursor2, storage2) = background.cursor();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+}
+
+#[test]
+fn test_spill() {
+
+    type SpillTrace = OrdValSpineSpill<UnsignedWrapper<u64>, u64, usize, i64>;
+
+    // Spill every batch merged by this thread.
+    let directory = TempDir::new("differential-test-spill");
+    spill::configure(Some(SpillConfig { directory: directory.path().to_path_buf(), threshold: 1 }));
+
+    let mut memory = IntegerTrace::new(OperatorInfo::new(0, 0, &[]), None, None);
+    let mut spilled = SpillTrace::new(OperatorInfo::new(0, 0, &[]), None, None);
+    memory.distinguish_since(AntichainRef::new(&[]));
+    spilled.distinguish_since(AntichainRef::new(&[]));
+    {
+        let mut batcher1 = <<IntegerTrace as TraceReader>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        let mut batcher2 = <<SpillTrace as TraceReader>::Batch as Batch<UnsignedWrapper<u64>, u64, usize, i64>>::Batcher::new();
+        for time in 0 .. 20 {
+            let updates: Vec<_> = (0 .. 100u64).map(|key| ((key.into(), key % 7), time, if time % 3 == 2 { -1 } else { 1 })).collect();
+            batcher1.push_batch(&mut updates.clone());
+            batcher2.push_batch(&mut updates.clone());
+            memory.insert(batcher1.seal(Antichain::from_elem(time + 1)));
+            spilled.insert(batcher2.seal(Antichain::from_elem(time + 1)));
+            // Merges of spilled batches themselves spill.
+            memory.exert(&mut 100);
+            spilled.exert(&mut 100);
+        }
+    }
+    for _ in 0 .. 100 {
+        memory.exert(&mut 1000);
+        spilled.exert(&mut 1000);
+    }
+    // Merges remain in progress until their helper threads deliver them.
+    let mut batches = 2;
+    while batches > 1 {
+        spilled.exert(&mut 1000);
+        batches = 0;
+        spilled.map_batches(|batch| if !batch.is_empty() { batches += 1; });
+    }
+
+    // Spilled files are unlinked once mapped, from the directory the configuration created.
+    let created: Vec<_> = ::std::fs::read_dir(directory.path()).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(created.len(), 1);
+    assert_eq!(::std::fs::read_dir(&created[0]).unwrap().count(), 0);
+    spill::configure(None);
+
+    let mut spills = 0;
+    spilled.map_batches(|batch| if batch.is_spilled() { spills += 1; });
+    assert!(spills > 0);
+
+    let (mut cursor1, storage1) = memory.cursor();
+    let (mut cursor2, storage2) = spilled.cursor();
+    assert_eq!(cursor1.to_vec(&storage1), cursor2.to_vec(&storage2));
+
+    // Seeking in spilled batches lands on the same keys and values.
+    let (mut cursor1, storage1) = memory.cursor();
+    let (mut cursor2, storage2) = spilled.cursor();
+    for key in (0 .. 100u64).step_by(9) {
+        cursor1.rewind_keys(&storage1);
+        cursor2.rewind_keys(&storage2);
+        cursor1.seek_key(&storage1, &key.into());
+        cursor2.seek_key(&storage2, &key.into());
+        cursor1.seek_val(&storage1, &(key % 7));
+        cursor2.seek_val(&storage2, &(key % 7));
+        assert_eq!(cursor2.get_key(&storage2), Some(&key.into()));
+        assert_eq!(cursor2.get_val(&storage2), Some(&(key % 7)));
+        assert_eq!(cursor1.get_key(&storage1), cursor2.get_key(&storage2));
+        assert_eq!(cursor1.get_val(&storage1), cursor2.get_val(&storage2));
+    }
+}
+
+#[test]
+fn test_interned() {
+
+    type InternedTrace = InternedValSpine<String, String, usize, i64>;
+
+    let mut trace = InternedTrace::new(OperatorInfo::new(0, 0, &[]), None, None);
+    {
+        let mut batcher = <<InternedTrace as TraceReader>::Batch as Batch<String, String, usize, i64>>::Batcher::new();
+        batcher.push_batch(&mut vec![
+            (("bob".to_string(), "bread".to_string()), 0, 1),
+            (("alice".to_string(), "milk".to_string()), 0, 1),
+            (("alice".to_string(), "bread".to_string()), 1, 1),
+        ]);
+        for time in 1 .. 3 {
+            trace.insert(batcher.seal(Antichain::from_elem(time)));
+        }
+    }
+
+    let (mut cursor, storage) = trace.cursor();
+    assert_eq!(cursor.to_vec(&storage), vec![
+        (("alice".to_string(), "bread".to_string()), vec![(1, 1)]),
+        (("alice".to_string(), "milk".to_string()), vec![(0, 1)]),
+        (("bob".to_string(), "bread".to_string()), vec![(0, 1)]),
+    ]);
+
+    // Seeking accepts keys and values whether or not they are interned.
+    cursor.rewind_keys(&storage);
+    cursor.seek_key(&storage, &"b".to_string());
+    assert_eq!(cursor.key(&storage), "bob");
+    cursor.rewind_keys(&storage);
+    cursor.seek_val(&storage, &"milk".to_string());
+    assert_eq!(cursor.val(&storage), "milk");
+
+    // Keys and values share one copy of each distinct string.
+    assert_eq!(Interner::<String>::local().borrow().len(), 4);
+}
+
 // #[test]
 // fn test_advance() {
 //     let mut trace = get_trace();